@@ -30,7 +30,7 @@ fn load_config() -> Config {
     let mut config = load_toml(cli.config_path.as_str());
 
     if let Some(port) = cli.network_port {
-        config.network.port = port;
+        config.network.port = Some(port);
     }
 
     if let Some(log_level) = cli.log_level {
@@ -59,5 +59,6 @@ fn main() -> color_eyre::eyre::Result<()> {
         }
     };
     blockchain.run();
+    log::info!("Application '{}' stopped.", app_name);
     Ok(())
 }
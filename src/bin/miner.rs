@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use crossbeam::channel::unbounded;
+use dotenv::dotenv;
+use tokio::runtime::Runtime;
+
+use ember_chain::{
+    config::{loader::load_toml_miner, models::MinerConfig},
+    crypto::account::Account,
+    mining::{
+        pow_utils::{proof_of_work, worker_threads, PowInterrupts, PowResult},
+        template::BlockTemplate,
+    },
+    network::rpc::connect_rpc,
+    pool::worker::run_worker,
+    proto::proto_node,
+    transaction::Transaction,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        default_value_t = String::from("./configs/miner.toml")
+    )]
+    config_path: String,
+}
+
+fn load_config(config_path: &str) -> MinerConfig {
+    let config = load_toml_miner(config_path);
+    log::info!("{:#?}", config);
+    config
+}
+
+fn main() {
+    dotenv().ok();
+    env_logger::init();
+    let cli = Args::parse();
+    let config = load_config(cli.config_path.as_str());
+    let rt = Runtime::new().unwrap();
+    let account = Account::load_or_create(config.account.clone()).unwrap();
+    let threads = worker_threads(config.threads);
+
+    if let Some(pool_address) = &config.pool_address {
+        log::info!(
+            "Mining for {} in pool {} with {} threads.",
+            account.public_key_as_hex(),
+            pool_address,
+            threads
+        );
+        if let Err(err) = run_worker(pool_address, &account.public_key().to_vec(), threads) {
+            log::error!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut client = match rt.block_on(connect_rpc(&config.rpc_url, config.rpc_token.as_deref())) {
+        Ok(client) => client,
+        Err(err) => {
+            log::error!("Failed to connect to {}. Error: {}", &config.rpc_url, &err);
+            std::process::exit(1);
+        }
+    };
+    log::info!(
+        "Mining for {} with {} threads.",
+        account.public_key_as_hex(),
+        threads
+    );
+
+    // the miner runs until it is killed, new transactions are picked up on refresh
+    let (_cancel_tx, cancel_rx) = unbounded::<()>();
+    let (_template_update_tx, template_update_rx) = unbounded::<()>();
+    let mut extra_nonce = 0u64;
+
+    loop {
+        let template = rt
+            .block_on(client.get_block_template(proto_node::None {}))
+            .map_err(|status| status.message().to_string())
+            .and_then(|resp| BlockTemplate::try_from(resp.into_inner()));
+        let template = match template {
+            Ok(template) => template,
+            Err(err) => {
+                log::error!("Failed to get a block template: {}", err);
+                std::thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+
+        let coinbase = Transaction::create_block_coinbase(
+            &[(account.public_key().to_vec(), template.coinbase_value())],
+            template.height,
+            extra_nonce,
+        );
+        let mut block = template.assemble(vec![coinbase]);
+
+        let interrupts = PowInterrupts {
+            cancel_rx: &cancel_rx,
+            template_update_rx: &template_update_rx,
+            refresh_at: Instant::now() + Duration::from_secs(config.template_refresh_secs),
+        };
+        let started = Instant::now();
+        let mut hash_count = 0;
+        let result = proof_of_work(
+            block.header.difficulty,
+            &mut block.header,
+            &interrupts,
+            &mut hash_count,
+            threads,
+        );
+        log::debug!(
+            "Average hashes per second: {:.2}",
+            hash_count as f64 / started.elapsed().as_secs_f64()
+        );
+
+        match result {
+            PowResult::Found(block_hash) => {
+                block.hash = block_hash;
+                let block_json = serde_json::to_string(&block).unwrap();
+                match rt.block_on(client.submit_block(proto_node::Block { block_json })) {
+                    Ok(_) => log::info!(
+                        "★★★ You successfully mined a block ({})! ★★★",
+                        block.get_hash_as_string(true)
+                    ),
+                    Err(status) => log::warn!("Block was rejected: {}", status.message()),
+                }
+            }
+            PowResult::Exhausted | PowResult::Stale => {}
+            PowResult::Cancelled => break,
+        }
+        // a new extra nonce keeps the next template from repeating work
+        extra_nonce += 1;
+    }
+}
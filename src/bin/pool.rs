@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use clap::Parser;
+use dotenv::dotenv;
+
+use ember_chain::{
+    config::{loader::load_toml_pool, models::PoolConfig},
+    crypto::account::Account,
+    pool::server::PoolServer,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        default_value_t = String::from("./configs/pool.toml")
+    )]
+    config_path: String,
+}
+
+fn load_config(config_path: &str) -> PoolConfig {
+    let config = load_toml_pool(config_path);
+    log::info!("{:#?}", config);
+    config
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    env_logger::init();
+    let cli = Args::parse();
+    let config = load_config(cli.config_path.as_str());
+    let account = Account::load_or_create(config.account.clone()).unwrap();
+    log::info!(
+        "Pool remainders are paid to {}.",
+        account.public_key_as_hex()
+    );
+
+    let pool = Arc::new(PoolServer::new(config, account.public_key().to_vec()));
+    if let Err(err) = pool.run().await {
+        log::error!("Mining pool stopped. Error: {}", err);
+        std::process::exit(1);
+    }
+}
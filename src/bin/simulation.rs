@@ -0,0 +1,71 @@
+use std::time::Instant;
+
+use clap::Parser;
+use dotenv::dotenv;
+
+use ember_chain::{
+    config::{loader::load_toml_simulation, models::SimulationConfig},
+    simulation::simulation::Simulation,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        default_value_t = String::from("./configs/simulation.toml")
+    )]
+    config_path: String,
+    /// Overrides the seed of the configuration, e.g. to replay a failed scenario.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn load_config(config_path: &str) -> SimulationConfig {
+    let config = load_toml_simulation(config_path);
+    log::info!("{:#?}", config);
+    config
+}
+
+fn main() {
+    dotenv().ok();
+    env_logger::init();
+    let cli = Args::parse();
+    let mut config = load_config(cli.config_path.as_str());
+    if let Some(seed) = cli.seed {
+        config.seed = seed;
+    }
+
+    let seed = config.seed;
+    let mut simulation = match Simulation::new(config) {
+        Ok(simulation) => simulation,
+        Err(err) => {
+            log::error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let started = Instant::now();
+    simulation.run();
+
+    println!(
+        "Seed {}: mined {} blocks in {:.1} virtual hours ({:.2?} real time).",
+        seed,
+        simulation.mined_blocks(),
+        simulation.elapsed_ms() as f64 / 3_600_000.0,
+        started.elapsed()
+    );
+    for node in simulation.nodes() {
+        println!(
+            "Node {}: height {}, head {}, difficulty {}, mined {}, rejected {}, reorganizations {}.",
+            node.id,
+            node.block_height(),
+            hex::encode(node.head_hash().get(..5).unwrap()),
+            node.difficulty(),
+            node.mined_blocks(),
+            node.rejected_blocks(),
+            node.reorganizations()
+        );
+    }
+}
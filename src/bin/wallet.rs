@@ -6,7 +6,13 @@ use tokio::runtime::Runtime;
 
 use ember_chain::{
     config::{loader::load_toml_wallet, models::WalletConfig},
-    wallet::wallet::Wallet,
+    crypto::hash_utils::{hash_from_vec_u8, Address},
+    types::Satoshi,
+    wallet::{
+        batch::{load_recipients, parse_recipient},
+        coin_selection::CoinSelectionStrategy,
+        wallet::Wallet,
+    },
 };
 
 #[derive(Parser, Debug)]
@@ -35,6 +41,11 @@ enum ConsoleAction {
     Address,
     GetBalance,
     CreateTransaction,
+    SendMany,
+    SendBatch,
+    BumpFee,
+    EstimateFee,
+    Generate,
 }
 impl ConsoleAction {
     fn from(action: &str) -> ConsoleAction {
@@ -42,13 +53,18 @@ impl ConsoleAction {
             "address" => ConsoleAction::Address,
             "balance" => ConsoleAction::GetBalance,
             "create_transaction" => ConsoleAction::CreateTransaction,
+            "send_many" => ConsoleAction::SendMany,
+            "send_batch" => ConsoleAction::SendBatch,
+            "bump_fee" => ConsoleAction::BumpFee,
+            "estimate_fee" => ConsoleAction::EstimateFee,
+            "generate" => ConsoleAction::Generate,
             "help" => ConsoleAction::Help,
             "quit" => ConsoleAction::Quit,
             "exit" => ConsoleAction::Quit,
             _ => ConsoleAction::Invalid,
         }
     }
-    pub fn into_iter() -> core::array::IntoIter<ConsoleAction, 6> {
+    pub fn into_iter() -> core::array::IntoIter<ConsoleAction, 11> {
         [
             ConsoleAction::Invalid,
             ConsoleAction::Quit,
@@ -56,11 +72,68 @@ impl ConsoleAction {
             ConsoleAction::Address,
             ConsoleAction::GetBalance,
             ConsoleAction::CreateTransaction,
+            ConsoleAction::SendMany,
+            ConsoleAction::SendBatch,
+            ConsoleAction::BumpFee,
+            ConsoleAction::EstimateFee,
+            ConsoleAction::Generate,
         ]
         .into_iter()
     }
 }
 
+/// Options shared by the batch payment actions, given as `key=value` tokens.
+struct SendOptions {
+    strategy: CoinSelectionStrategy,
+    change_address: Option<Address>,
+    data: Option<Vec<u8>>,
+}
+
+fn parse_send_options(wallet: &Wallet, tokens: &[&str]) -> Result<SendOptions, String> {
+    let mut options = SendOptions {
+        strategy: wallet.config.coin_selection.strategy,
+        change_address: None,
+        data: None,
+    };
+    for token in tokens {
+        match token.split_once('=') {
+            Some(("strategy", strategy)) => options.strategy = strategy.parse()?,
+            Some(("change", address)) => {
+                options.change_address = Some(
+                    wallet
+                        .get_address_from_string(address)
+                        .ok_or("change address must be in a valid hex form")?,
+                )
+            }
+            Some(("data", data)) => options.data = Some(data.as_bytes().to_vec()),
+            _ => return Err(format!("invalid option `{}`", token)),
+        }
+    }
+    Ok(options)
+}
+
+fn send_batch(wallet: &mut Wallet, recipients: Vec<(Address, Satoshi)>, options: SendOptions) {
+    let total: Satoshi = recipients.iter().map(|x| x.1).sum();
+    match wallet.create_batch_transaction(
+        &recipients,
+        options.change_address,
+        options.data,
+        options.strategy,
+    ) {
+        Ok(tx_hash) => {
+            log::info!(
+                "You sent {} satoshis to {} recipients.",
+                total,
+                recipients.len()
+            );
+            log::debug!("Transaction hash: {:?}", hex::encode(tx_hash));
+        }
+        Err(err) => {
+            log::error!("Failed to create transaction: {}", err);
+        }
+    }
+}
+
 fn start_console(wallet: &mut Wallet) {
     if wallet.connect_node().is_err() {
         return;
@@ -82,14 +155,21 @@ fn start_console(wallet: &mut Wallet) {
                 log::info!("Address: {}", wallet.get_address());
             }
             ConsoleAction::GetBalance => {
+                let balance = wallet.get_balance();
                 log::info!(
-                    "Balance: {} satoshis.",
-                    wallet.get_balance(wallet.account.public_key().to_vec())
+                    "Balance: {} satoshis confirmed, {} satoshis pending.",
+                    balance.confirmed,
+                    balance.pending
                 );
+                for tx_hash in wallet.get_pending_transactions() {
+                    log::info!("Pending transaction: {}", hex::encode(tx_hash));
+                }
             }
             ConsoleAction::CreateTransaction => 'create_tx: {
-                if tokens.len() != 3 {
-                    log::debug!("usage: create_transaction [receiver_address] [amount]");
+                if tokens.len() != 3 && tokens.len() != 4 {
+                    log::debug!(
+                        "usage: create_transaction [receiver_address] [amount] [strategy?]"
+                    );
                     break 'create_tx;
                 }
                 let rx_address = wallet.get_address_from_string(tokens[1]);
@@ -106,9 +186,24 @@ fn start_console(wallet: &mut Wallet) {
                 }
                 let amount = amount.unwrap();
 
-                match wallet.create_transaction(&rx_address, amount, 0) {
+                let strategy = match tokens.get(3) {
+                    Some(strategy) => match strategy.parse::<CoinSelectionStrategy>() {
+                        Ok(strategy) => strategy,
+                        Err(err) => {
+                            log::debug!("usage: {}", err);
+                            break 'create_tx;
+                        }
+                    },
+                    None => wallet.config.coin_selection.strategy,
+                };
+
+                match wallet.create_transaction(&rx_address, amount, strategy) {
                     Ok(tx_hash) => {
-                        log::info!("You sent {} satoshis to {:?}", amount, hex::encode(rx_address));
+                        log::info!(
+                            "You sent {} satoshis to {:?}",
+                            amount,
+                            hex::encode(rx_address)
+                        );
                         log::debug!("Transaction hash: {:?}", hex::encode(tx_hash));
                     }
                     Err(err) => {
@@ -116,6 +211,90 @@ fn start_console(wallet: &mut Wallet) {
                     }
                 }
             }
+            ConsoleAction::SendMany => 'send_many: {
+                let usage = "usage: send_many [address:amount]... [strategy=?] [change=?] [data=?]";
+                let (recipients, options): (Vec<&str>, Vec<&str>) =
+                    tokens[1..].iter().partition(|x| !x.contains('='));
+                if recipients.is_empty() {
+                    log::debug!("{}", usage);
+                    break 'send_many;
+                }
+                let recipients: Result<Vec<_>, String> = recipients
+                    .iter()
+                    .map(|recipient| parse_recipient(recipient, ':'))
+                    .collect();
+                match (recipients, parse_send_options(wallet, &options)) {
+                    (Ok(recipients), Ok(options)) => send_batch(wallet, recipients, options),
+                    (Err(err), _) | (_, Err(err)) => log::debug!("{} ({})", usage, err),
+                }
+            }
+            ConsoleAction::SendBatch => 'send_batch: {
+                let usage = "usage: send_batch [csv_file] [strategy=?] [change=?] [data=?]";
+                if tokens.len() < 2 {
+                    log::debug!("{}", usage);
+                    break 'send_batch;
+                }
+                match (
+                    load_recipients(tokens[1]),
+                    parse_send_options(wallet, &tokens[2..]),
+                ) {
+                    (Ok(recipients), Ok(options)) => send_batch(wallet, recipients, options),
+                    (Err(err), _) | (_, Err(err)) => log::debug!("{} ({})", usage, err),
+                }
+            }
+            ConsoleAction::BumpFee => 'bump_fee: {
+                let usage = "usage: bump_fee [tx_hash] [fee_rate?]";
+                let tx_hash = tokens
+                    .get(1)
+                    .and_then(|x| hex::decode(x).ok())
+                    .filter(|x| x.len() == 32);
+                let fee_rate = tokens.get(2).map(|x| x.parse::<Satoshi>());
+                let (Some(tx_hash), None | Some(Ok(_))) = (&tx_hash, &fee_rate) else {
+                    log::debug!("{}", usage);
+                    break 'bump_fee;
+                };
+
+                match wallet.bump_fee(&hash_from_vec_u8(tx_hash), fee_rate.and_then(|x| x.ok())) {
+                    Ok(new_tx_hash) => {
+                        log::info!("Replaced transaction with {}.", hex::encode(new_tx_hash));
+                    }
+                    Err(err) => {
+                        log::error!("Failed to bump the fee: {}", err);
+                    }
+                }
+            }
+            ConsoleAction::EstimateFee => 'estimate_fee: {
+                let target_blocks = match tokens.get(1).map(|x| x.parse::<u32>()) {
+                    Some(Ok(target_blocks)) => target_blocks,
+                    Some(Err(_)) => {
+                        log::debug!("usage: estimate_fee [target_blocks?]");
+                        break 'estimate_fee;
+                    }
+                    None => wallet.config.coin_selection.fee_target_blocks,
+                };
+                match wallet.estimate_fee(target_blocks) {
+                    Ok(fee_rate) => log::info!(
+                        "Estimated fee rate for {} blocks: {} satoshis per kilobyte.",
+                        target_blocks,
+                        fee_rate
+                    ),
+                    Err(err) => log::error!("Failed to estimate the fee: {}", err),
+                }
+            }
+            ConsoleAction::Generate => {
+                let Some(Ok(count)) = tokens.get(1).map(|x| x.parse::<u32>()) else {
+                    log::debug!("usage: generate [count]");
+                    continue;
+                };
+                match wallet.generate_blocks(count) {
+                    Ok(hashes) => {
+                        for hash in hashes {
+                            log::info!("Generated block ({}).", hex::encode(&hash[..5]));
+                        }
+                    }
+                    Err(err) => log::error!("Failed to generate blocks: {}", err),
+                }
+            }
             ConsoleAction::Help => println!(
                 "Actions: {:?}",
                 ConsoleAction::into_iter().collect::<Vec<ConsoleAction>>()
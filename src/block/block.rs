@@ -3,7 +3,8 @@ use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    crypto::hash_utils::HashResult, database::database::DatabaseType, transaction::Transaction,
+    crypto::hash_utils::HashResult, database::database::DatabaseType,
+    mempool::fee_utils::transaction_fee, transaction::Transaction, types::Satoshi,
 };
 
 use super::BlockHeader;
@@ -30,9 +31,26 @@ impl Block {
         } else {
             panic!("No previous block found to verify.")
         }
+        self.verify_transactions(database)
+    }
+    /// Verifies the transactions of the block, regardless of the chain it extends.
+    pub fn verify_transactions(&self, database: &Arc<Mutex<DatabaseType>>) -> bool {
+        // the coinbase may claim the block reward and the fees of all transactions
+        let fees = {
+            let db = database.lock().unwrap();
+            self.transactions
+                .iter()
+                .filter(|tx| !tx.is_coinbase())
+                .map(|tx| transaction_fee(&*db, &self.transactions, tx))
+                .sum::<Option<Satoshi>>()
+        };
+        let Some(fees) = fees else {
+            log::error!("Block contains a transaction with missing inputs.");
+            return false;
+        };
 
         for tx in self.transactions.iter() {
-            if !tx.verify(self.header.reward, database, &self.transactions)
+            if !tx.verify(self.header.reward + fees, database, &self.transactions)
                 || !tx.verify_inputs(database)
             {
                 return false;
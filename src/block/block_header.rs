@@ -39,6 +39,7 @@ impl BlockHeader {
     fn as_bytes(&self) -> Vec<u8> {
         let mut data = Vec::new();
         data.extend(self.previous_block_hash);
+        data.extend(self.merkle_root);
         data.extend(self.difficulty.to_string().as_bytes());
         data.extend(self.timestamp.to_string().as_bytes());
         data.extend(self.nonce.to_string().as_bytes());
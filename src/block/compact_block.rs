@@ -0,0 +1,239 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crypto::{
+        hash_utils::{sha256, HashResult},
+        merkle_tree::generate_merkle_root,
+    },
+    transaction::Transaction,
+};
+
+use super::{Block, BlockHeader};
+
+/// Number of bytes of a short transaction ID.
+const SHORT_ID_LEN: usize = 6;
+
+pub type ShortId = [u8; SHORT_ID_LEN];
+
+/// Block announcement consisting of the header and short IDs of the
+/// transactions, which the receiver mostly already has in its pending pool.
+/// Coinbase transactions, which no one else can know, and transactions
+/// spending outputs of the same block are sent in full.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactBlock {
+    pub header: BlockHeader,
+    pub hash: HashResult,
+    /// Short IDs of the transactions which are not prefilled.
+    pub short_ids: Vec<ShortId>,
+    /// Transactions sent in full with their index in the block.
+    pub prefilled: Vec<(u32, Transaction)>,
+}
+
+/// Short transaction ID salted with the block hash, so that collisions cannot
+/// be prepared for all blocks at once.
+pub fn short_id(block_hash: &HashResult, tx_hash: &HashResult) -> ShortId {
+    let mut bytes = block_hash.to_vec();
+    bytes.extend_from_slice(tx_hash);
+    sha256(&bytes)[..SHORT_ID_LEN].try_into().unwrap()
+}
+
+impl CompactBlock {
+    pub fn from_block(block: &Block) -> Self {
+        let tx_hashes: HashSet<HashResult> = block.transactions.iter().map(|x| x.hash()).collect();
+        let mut short_ids = vec![];
+        let mut prefilled = vec![];
+        for (index, tx) in block.transactions.iter().enumerate() {
+            // like the miner's spend of its coinbase, they are unlikely to be pending elsewhere
+            let spends_block = tx
+                .inputs
+                .iter()
+                .any(|input| tx_hashes.contains(&input.utxo_tx_hash));
+            if tx.is_coinbase() || spends_block {
+                prefilled.push((index as u32, tx.clone()));
+            } else {
+                short_ids.push(short_id(&block.hash, &tx.hash()));
+            }
+        }
+        Self {
+            header: block.header.clone(),
+            hash: block.hash,
+            short_ids,
+            prefilled,
+        }
+    }
+
+    pub fn transaction_count(&self) -> usize {
+        self.short_ids.len() + self.prefilled.len()
+    }
+
+    /// Fills the transactions of the block from the prefilled ones and the
+    /// pending transactions. Missing transactions are left empty.
+    pub fn reconstruct(&self, pending: &[Transaction]) -> Vec<Option<Transaction>> {
+        let mut transactions: Vec<Option<Transaction>> = vec![None; self.transaction_count()];
+        for (index, tx) in self.prefilled.iter() {
+            if let Some(slot) = transactions.get_mut(*index as usize) {
+                *slot = Some(tx.clone());
+            }
+        }
+
+        let pending: HashMap<ShortId, &Transaction> = pending
+            .iter()
+            .map(|tx| (short_id(&self.hash, &tx.hash()), tx))
+            .collect();
+        let mut short_ids = self.short_ids.iter();
+        for slot in transactions.iter_mut().filter(|x| x.is_none()) {
+            let Some(short_id) = short_ids.next() else {
+                break;
+            };
+            *slot = pending.get(short_id).map(|tx| (*tx).clone());
+        }
+        transactions
+    }
+
+    /// Indices of the transactions missing in the reconstruction.
+    pub fn missing(transactions: &[Option<Transaction>]) -> Vec<u32> {
+        transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, tx)| tx.is_none())
+            .map(|(index, _)| index as u32)
+            .collect()
+    }
+
+    /// Assembles the block from the reconstructed transactions, checking them
+    /// against the merkle root of the header. Fails if transactions are missing
+    /// or a short ID matched the wrong transaction.
+    pub fn assemble(self, transactions: Vec<Option<Transaction>>) -> Option<Block> {
+        let transactions: Vec<Transaction> = transactions.into_iter().collect::<Option<_>>()?;
+        let merkle_root = generate_merkle_root(transactions.iter().map(|x| x.hash()).collect());
+        if merkle_root != self.header.merkle_root {
+            return None;
+        }
+        Some(Block::new(self.header, transactions, self.hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU8;
+    use std::sync::Arc;
+
+    use super::CompactBlock;
+    use crate::{
+        config::{
+            chain_params::ChainParams,
+            models::{AccountConfig, MiningConfig},
+        },
+        crypto::{account::Account, merkle_tree::generate_merkle_root},
+        mining::{miner::Miner, template::BlockTemplate},
+        transaction::{input::Input, script::Script, Transaction},
+    };
+
+    fn spend(i: u8) -> Transaction {
+        Transaction::new(
+            vec![],
+            vec![Input::new([i; 32], 0, Script::new(vec![]))],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let coinbase = Transaction::create_block_coinbase(&[(vec![1; 32], 50)], 1, 0);
+        let template = BlockTemplate {
+            previous_block_hash: [0; 32],
+            height: 1,
+            difficulty: 1,
+            timestamp: 0,
+            reward: 50,
+            fees: 0,
+            transactions: vec![spend(1), spend(2), spend(3)],
+        };
+        let block = template.assemble(vec![coinbase]);
+        let compact_block = CompactBlock::from_block(&block);
+        assert_eq!(compact_block.prefilled.len(), 1);
+        assert_eq!(compact_block.short_ids.len(), 3);
+
+        // the second transaction is not pending
+        let pending = vec![spend(3), spend(1), spend(9)];
+        let mut transactions = compact_block.reconstruct(&pending);
+        assert_eq!(CompactBlock::missing(&transactions), vec![2]);
+        assert!(compact_block
+            .clone()
+            .assemble(transactions.clone())
+            .is_none());
+
+        transactions[2] = Some(spend(2));
+        let reconstructed = compact_block.assemble(transactions).unwrap();
+        assert_eq!(
+            generate_merkle_root(
+                reconstructed
+                    .transactions
+                    .iter()
+                    .map(|x| x.hash())
+                    .collect()
+            ),
+            block.header.merkle_root
+        );
+    }
+
+    #[test]
+    fn test_reconstruct_mined_block() {
+        let account = Arc::new(
+            Account::new(AccountConfig {
+                keys_path: "./configs/keys_test.pkcs8".to_string(),
+            })
+            .unwrap(),
+        );
+        let chain_params = ChainParams::regtest();
+        let miner = Miner::new(
+            MiningConfig {
+                enabled: false,
+                max_block_size: 1_000_000,
+                threads: 1,
+                template_refresh_secs: 5,
+            },
+            chain_params.clone(),
+            account,
+            Arc::new(AtomicU8::new(chain_params.start_difficulty_bit)),
+        );
+        let template = BlockTemplate {
+            previous_block_hash: [0; 32],
+            height: 1,
+            difficulty: 1,
+            timestamp: 0,
+            reward: 50,
+            fees: 0,
+            transactions: vec![spend(1)],
+        };
+        let block = template.assemble(miner.coinbase_transactions(&template, 0));
+        let compact_block = CompactBlock::from_block(&block);
+        assert_eq!(compact_block.prefilled.len(), 2);
+
+        // the spend of the coinbase is not pending anywhere
+        let transactions = compact_block.reconstruct(&[spend(1)]);
+        assert!(CompactBlock::missing(&transactions).is_empty());
+        assert_eq!(
+            compact_block.assemble(transactions).unwrap().hash,
+            block.hash
+        );
+    }
+
+    #[test]
+    fn test_wrong_transaction() {
+        let block = BlockTemplate {
+            previous_block_hash: [0; 32],
+            height: 1,
+            difficulty: 1,
+            timestamp: 0,
+            reward: 50,
+            fees: 0,
+            transactions: vec![spend(1)],
+        }
+        .assemble(vec![]);
+        let compact_block = CompactBlock::from_block(&block);
+        assert!(compact_block.assemble(vec![Some(spend(2))]).is_none());
+    }
+}
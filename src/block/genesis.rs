@@ -0,0 +1,14 @@
+use crate::crypto::merkle_tree::generate_merkle_root;
+
+use super::{Block, BlockHeader};
+
+/// Creates the first block of a chain, which has no transactions and no predecessor.
+pub fn genesis_block(timestamp: u64) -> Block {
+    let merkle_root = generate_merkle_root(vec![]);
+    let header = BlockHeader::from(merkle_root, [0u8; 32], 0, timestamp, 0);
+    Block {
+        hash: header.finalize(),
+        header,
+        transactions: vec![],
+    }
+}
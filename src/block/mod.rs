@@ -1,5 +1,7 @@
 pub mod block;
 pub mod block_header;
+pub mod compact_block;
+pub mod genesis;
 
 pub use block::Block;
 pub use block_header::BlockHeader;
@@ -1,37 +1,42 @@
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
     thread,
     time::{Duration, Instant},
 };
 
-use chrono::DateTime;
-use crossbeam::channel::{select, unbounded, Receiver};
+use crossbeam::channel::{select, unbounded};
 use tokio::runtime::Runtime;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
 
 use crate::{
-    block::{block_header::BlockHeader, Block},
+    block::Block,
+    chain::{self, ChainUpdate},
     config::models::Config,
-    crypto::{
-        account::{Account, AccountError},
-        merkle_tree::generate_merkle_root,
-    },
+    crypto::account::{Account, AccountError},
     database::{database::DatabaseType, InMemoryDatabase},
+    mempool::fee_estimator::FeeEstimator,
+    metrics::metrics,
     mining::miner::Miner,
-    network::node::Network,
-    transaction::Transaction,
-    types::Satoshi,
+    network::{events::ChainEvents, node::Network},
 };
 
+/// Time the servers get to complete the requests in progress on shutdown.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct Blockchain {
     // dependencies
     database: Arc<Mutex<DatabaseType>>,
-    account: Arc<Account>,
+    fee_estimator: Arc<Mutex<FeeEstimator>>,
     miner: Miner,
+    /// Current mining difficulty, adjusted by the miner.
+    difficulty: Arc<AtomicU8>,
     // other
     config: Config,
     running: bool,
-    transactions_rx: Arc<Mutex<Receiver<Transaction>>>,
-    current_block_reward: Satoshi,
     blocks_announce_tx_rx: (
         crossbeam::channel::Sender<Block>,
         crossbeam::channel::Receiver<Block>,
@@ -40,6 +45,16 @@ pub struct Blockchain {
         crossbeam::channel::Sender<Block>,
         crossbeam::channel::Receiver<Block>,
     ),
+    /// Notifies the miner about newly accepted pending transactions.
+    mempool_update_tx_rx: (
+        crossbeam::channel::Sender<()>,
+        crossbeam::channel::Receiver<()>,
+    ),
+    /// Stops mining and the processing of blocks on SIGINT or SIGTERM.
+    shutdown_tx_rx: (
+        crossbeam::channel::Sender<()>,
+        crossbeam::channel::Receiver<()>,
+    ),
 }
 
 #[derive(Debug)]
@@ -54,140 +69,236 @@ impl From<AccountError> for BlockchainError {
 
 impl Blockchain {
     pub fn new(config: Config) -> Result<Self, BlockchainError> {
-        let (transactions_tx, transactions_rx) = unbounded::<Transaction>();
         let account = Arc::new(Account::load_or_create(config.account.clone())?);
         let database = Arc::new(Mutex::new(InMemoryDatabase::default()));
+        let difficulty = Arc::new(AtomicU8::new(config.chain_params.start_difficulty_bit));
+        log::info!("Running on {}.", config.chain_params.name);
 
         Ok(Self {
             running: true,
             database,
-            miner: Miner::new(config.mining.clone(), account.clone()),
-            account,
-            transactions_rx: Arc::new(Mutex::new(transactions_rx)),
-            current_block_reward: config.mining.mining_reward,
+            fee_estimator: Arc::new(Mutex::new(FeeEstimator::new(
+                config.mempool.fee_estimation_blocks,
+                config.mining.max_block_size,
+            ))),
+            miner: Miner::new(
+                config.mining.clone(),
+                config.chain_params.clone(),
+                account,
+                difficulty.clone(),
+            ),
+            difficulty,
             blocks_announce_tx_rx: unbounded::<Block>(),
             blocks_publish_tx_rx: unbounded::<Block>(),
+            mempool_update_tx_rx: unbounded::<()>(),
+            shutdown_tx_rx: unbounded::<()>(),
             config,
         })
     }
     pub fn run(&mut self) {
-        let transactions_rx = self.transactions_rx.clone();
-        let mut pen_txs = self
-            .database
-            .lock()
-            .unwrap()
-            .get_pending_transactions()
-            .to_vec();
-
         thread::scope(|s| {
-            s.spawn(|| loop {
-                match transactions_rx.lock().unwrap().recv() {
-                    Ok(tx) => {
-                        pen_txs.push(tx);
-                    }
-                    Err(_e) => {
-                        thread::sleep(Duration::from_millis(10));
-                    }
-                }
-            });
             let db = self.database.clone();
-            let port = self.config.network.port;
-            let seed_list = self.config.network.seed_list.clone();
             let network = Arc::new(Network::new(
-                port,
-                seed_list,
+                self.config.clone(),
                 db,
+                self.fee_estimator.clone(),
+                self.difficulty.clone(),
                 self.blocks_announce_tx_rx.0.clone(),
                 self.blocks_publish_tx_rx.1.clone(),
+                self.mempool_update_tx_rx.0.clone(),
             ));
 
-            self.database.lock().unwrap().create_genesis_block();
+            self.database
+                .lock()
+                .unwrap()
+                .create_genesis_block(self.config.chain_params.genesis_block());
+            let chain_events = network.chain_events();
+            chain_events.chain_updated(&*self.database.lock().unwrap());
 
+            let shutdown_tx = self.shutdown_tx_rx.0.clone();
             s.spawn(move || {
                 let rt = Runtime::new().unwrap();
                 rt.block_on(async {
+                    let mut servers = vec![];
                     let network_cloned = network.clone();
-                    let hdl = tokio::spawn(async move {
+                    servers.push(tokio::spawn(async move {
                         if network_cloned.start_network_node().await.is_err() {
                             log::error!("☠☠ network node crashed ☠☠");
                         }
-                    });
-                    if let Err(err) = network.start_sync().await {
-                        log::error!("Synchronization failed: {}", err);
-                    }
+                    }));
+                    let network_cloned = network.clone();
+                    servers.push(tokio::spawn(async move {
+                        if let Err(err) = network_cloned.start_rpc_server().await {
+                            log::error!("RPC server crashed: {}", err);
+                        }
+                    }));
+                    let network_cloned = network.clone();
+                    servers.push(tokio::spawn(async move {
+                        if let Err(err) = network_cloned.start_http_gateway().await {
+                            log::error!("HTTP gateway crashed: {}", err);
+                        }
+                    }));
+                    let network_cloned = network.clone();
+                    servers.push(tokio::spawn(async move {
+                        if let Err(err) = network_cloned.start_metrics_server().await {
+                            log::error!("Metrics endpoint crashed: {}", err);
+                        }
+                    }));
 
+                    // the background tasks are dropped with the runtime
                     let network_cloned = network.clone();
-                    tokio::spawn(async move { network_cloned.wait_on_publish_block().await });
+                    tokio::spawn(async move {
+                        let network = network_cloned;
+                        if let Err(err) = network.start_sync().await {
+                            log::error!("Synchronization failed: {}", err);
+                        }
+                        // the saved transactions are validated against the synchronized chain
+                        network.load_mempool();
 
-                    hdl.await.unwrap();
-                    log::warn!("async runtime ended");
+                        let network_cloned = network.clone();
+                        tokio::spawn(async move { network_cloned.wait_on_publish_block().await });
+                        let network_cloned = network.clone();
+                        tokio::spawn(async move { network_cloned.maintain_connections().await });
+                        tokio::spawn(async move { network.monitor_peers().await });
+                    });
+
+                    shutdown_signal().await;
+                    log::info!("Shutting down, completing the requests in progress.");
+                    let _ = shutdown_tx.send(());
+                    network.shutdown();
+                    let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+                        for server in servers {
+                            let _ = server.await;
+                        }
+                    })
+                    .await;
+                    if drained.is_err() {
+                        log::warn!("Requests still in progress after {:?}.", SHUTDOWN_TIMEOUT);
+                    }
+                    network.persist();
                 });
+                log::info!("Network stopped.");
             });
 
             while self.running {
-                let block = self.get_next_block();
-
-                if !block.verify(&self.database) {
-                    log::warn!(
-                        "☠☠ Invalid block ({}) ☠☠.",
-                        hex::encode(block.hash.get(..5).unwrap())
-                    );
-                    let mut db = self.database.lock().unwrap();
-                    for tx in block.transactions.iter() {
-                        db.remove_transaction(tx.hash());
-                    }
+                let Some(block) = self.get_next_block() else {
+                    self.running = false;
                     continue;
-                }
+                };
 
-                let mut db = self.database.lock().unwrap();
-                db.insert_block(block);
-                if db.block_height() % self.config.mining.block_adjustment_interval == 0 {
-                    self.miner.adjust_difficulty();
-                }
+                self.add_block(block, &chain_events);
             }
         });
     }
-    fn get_next_block(&mut self) -> Block {
-        let start = Instant::now();
-        let last_block = self.database.lock().unwrap().head().unwrap().clone();
+    /// Verifies the block and adds it to the chain it extends. A block on a
+    /// side chain becomes the head once its chain is the longest.
+    fn add_block(&mut self, block: Block, chain_events: &ChainEvents) {
+        // the genesis timestamp is fixed, so the first interval is meaningless
+        let parent_timestamp = {
+            let db = self.database.lock().unwrap();
+            db.get_block(&block.header.previous_block_hash)
+                .filter(|parent| parent.header.previous_block_hash != [0u8; 32])
+                .map(|parent| parent.header.timestamp)
+        };
+        match chain::add_block(&self.database, &self.config.chain_params, block.clone()) {
+            Ok(ChainUpdate::SideChain) => {
+                log::info!(
+                    "Block ({}) extends a side chain.",
+                    hex::encode(block.hash.get(..5).unwrap())
+                );
+                return;
+            }
+            Ok(ChainUpdate::Reorganized(depth)) => log::warn!(
+                "Fork detected. Replaced {} blocks with the chain of block ({}).",
+                depth,
+                hex::encode(block.hash.get(..5).unwrap())
+            ),
+            Ok(ChainUpdate::Extended) => {}
+            Err(reason) => {
+                metrics().validation_failed("block", reason);
+                log::warn!(
+                    "☠☠ Invalid block ({}) ☠☠.",
+                    hex::encode(block.hash.get(..5).unwrap())
+                );
+                // its transactions may be confirmed by blocks of the chain, so the store is left as it is
+                return;
+            }
+        }
 
+        let db = self.database.lock().unwrap();
+        chain_events.chain_updated(&*db);
+        if let Some(parent_timestamp) = parent_timestamp {
+            metrics().block_connected(block.header.timestamp.saturating_sub(parent_timestamp));
+        }
+        self.fee_estimator
+            .lock()
+            .unwrap()
+            .process_block(&*db, &block, db.block_height());
+        // the next block is mined with the difficulty of the chain, adjusted at the interval
+        if let Some(head) = db.head() {
+            self.difficulty
+                .store(head.header.difficulty, Ordering::Relaxed);
+        }
+        if !self.config.chain_params.fixed_difficulty
+            && db
+                .block_height()
+                .is_multiple_of(self.config.chain_params.block_adjustment_interval)
+        {
+            self.miner.adjust_difficulty();
+        }
+    }
+    /// Waits for the next block, mined by us or received from the network.
+    /// Returns `None` once the node is shutting down.
+    fn get_next_block(&mut self) -> Option<Block> {
+        let start = Instant::now();
         let (mining_tx, mining_rx) = unbounded::<Block>();
         let (mining_cancel_tx, mining_cancel_rx) = unbounded::<()>();
         let (net_tx, net_rx) = unbounded::<Block>();
         let (net_cancel_tx, net_cancel_rx) = unbounded::<()>();
 
         let mut hash_count = 0;
-        let mut final_block = Block::default();
+        let mut final_block = None;
+        let height = self.database.lock().unwrap().block_height();
+        let reward = self.config.chain_params.block_reward(height);
 
         thread::scope(|s| {
-            s.spawn(|| {
-                let block: Option<Block>;
-                (block, hash_count) = self.miner.mine(
-                    &self.database,
-                    mining_cancel_rx,
-                    last_block.hash,
-                    self.current_block_reward,
-                    self.config.simulation.fake_mining,
-                );
+            // without the built-in miner, blocks come from peers and external miners only,
+            // on some test networks they are generated on demand
+            if self.config.mining.enabled && !self.config.chain_params.generate_on_demand {
+                s.spawn(|| {
+                    let block: Option<Block>;
+                    (block, hash_count) = self.miner.mine(
+                        &self.database,
+                        mining_cancel_rx,
+                        &self.mempool_update_tx_rx.1,
+                        reward,
+                    );
 
-                if let Some(block) = block {
-                    mining_tx.send(block.clone()).unwrap();
-                }
-            });
+                    if let Some(block) = block {
+                        mining_tx.send(block.clone()).unwrap();
+                    }
+                });
+            }
 
             s.spawn(|| loop {
                 select! {
                     recv(self.blocks_announce_tx_rx.1) -> block => {
                         let block = block.unwrap();
-                        let last_block = self.database.lock().unwrap().head().unwrap().clone();
+                        // blocks competing with the head are kept on a side chain
+                        let parent_known = self
+                            .database
+                            .lock()
+                            .unwrap()
+                            .get_block(&block.header.previous_block_hash)
+                            .is_some();
 
-                        if block.header.previous_block_hash == last_block.hash {
+                        if parent_known {
                             net_tx.send(block.clone()).unwrap();
                             return;
                         } else {
-                            log::warn!("Block ({}) does not fit onto latest block ({}).",
-                                hex::encode(block.header.previous_block_hash.get(..5).unwrap()),
-                                hex::encode(last_block.hash.get(..5).unwrap())
+                            log::warn!("Block ({}) does not fit onto a known block ({}).",
+                                hex::encode(block.hash.get(..5).unwrap()),
+                                hex::encode(block.header.previous_block_hash.get(..5).unwrap())
                             );
                         }
                     }
@@ -203,18 +314,136 @@ impl Blockchain {
                     let block = my_block.unwrap();
                     self.blocks_publish_tx_rx.0.send(block.clone()).unwrap();
                     log::info!("★★★ You successfully mined a block ({})! ★★★", hex::encode(block.hash.get(..5).unwrap()));
-                    block
+                    Some(block)
                 }
                 recv(net_rx) -> other_block => {
                     _ = mining_cancel_tx.send(());
                     let block = other_block.unwrap();
                     log::info!("A participant has mined a block! ({})!", hex::encode(block.hash.get(..5).unwrap()));
-                    block
+                    Some(block)
+                }
+                recv(self.shutdown_tx_rx.1) -> _ => {
+                    _ = mining_cancel_tx.send(());
+                    _ = net_cancel_tx.send(());
+                    log::info!("Stopped processing blocks.");
+                    None
                 }
             };
         });
 
+        let block = final_block?;
         self.miner.add_mining_time(start.elapsed(), hash_count);
-        final_block
+        self.miner.get_hash_count(start.elapsed());
+        Some(block)
+    }
+}
+
+/// Completes on SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+    #[cfg(unix)]
+    {
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM.");
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = ctrl_c.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Blockchain;
+    use crate::{
+        block::Block, config::loader::load_toml, crypto::hash_utils::HashResult,
+        mining::template::BlockTemplate, network::events::ChainEvents, transaction::Transaction,
+    };
+
+    fn block(blockchain: &Blockchain, parent: HashResult, height: usize, timestamp: u64) -> Block {
+        let reward = blockchain.config.chain_params.block_reward(height);
+        let coinbase =
+            Transaction::create_block_coinbase(&[(vec![1; 32], reward)], height, timestamp);
+        BlockTemplate {
+            previous_block_hash: parent,
+            height,
+            difficulty: 1,
+            timestamp,
+            reward,
+            fees: 0,
+            transactions: vec![],
+        }
+        .assemble(vec![coinbase])
+    }
+
+    /// Receives the block from the network like the blockchain thread.
+    fn receive(blockchain: &mut Blockchain, block: Block, chain_events: &ChainEvents) {
+        blockchain
+            .blocks_announce_tx_rx
+            .0
+            .send(block.clone())
+            .unwrap();
+        let next_block = blockchain.get_next_block().unwrap();
+        assert_eq!(next_block.hash, block.hash);
+        blockchain.add_block(next_block, chain_events);
+    }
+
+    #[test]
+    fn test_fork_block() {
+        let mut config = load_toml("./configs/regtest.toml");
+        config.account.keys_path = "./configs/keys_test.pkcs8".to_string();
+        let genesis = config.chain_params.genesis_block();
+        let mut blockchain = Blockchain::new(config).unwrap();
+        blockchain
+            .database
+            .lock()
+            .unwrap()
+            .create_genesis_block(genesis.clone());
+        let chain_events = ChainEvents::new();
+
+        let a1 = block(&blockchain, genesis.hash, 1, 1);
+        receive(&mut blockchain, a1.clone(), &chain_events);
+
+        // a competing block is kept, although it does not extend the head
+        let b1 = block(&blockchain, genesis.hash, 1, 2);
+        receive(&mut blockchain, b1.clone(), &chain_events);
+        let head = blockchain.database.lock().unwrap().head().unwrap().hash;
+        assert_eq!(head, a1.hash);
+
+        // its chain becomes the longest with the next block
+        let b2 = block(&blockchain, b1.hash, 2, 3);
+        receive(&mut blockchain, b2.clone(), &chain_events);
+        let db = blockchain.database.lock().unwrap();
+        assert_eq!(db.head().unwrap().hash, b2.hash);
+        let hashes: Vec<HashResult> = db.get_blocks().iter().map(|x| x.hash).collect();
+        assert_eq!(hashes, vec![genesis.hash, b1.hash, b2.hash]);
+    }
+
+    #[test]
+    fn test_invalid_block() {
+        let mut config = load_toml("./configs/regtest.toml");
+        config.account.keys_path = "./configs/keys_test.pkcs8".to_string();
+        let genesis = config.chain_params.genesis_block();
+        let mut blockchain = Blockchain::new(config).unwrap();
+        blockchain
+            .database
+            .lock()
+            .unwrap()
+            .create_genesis_block(genesis.clone());
+        let chain_events = ChainEvents::new();
+        let a1 = block(&blockchain, genesis.hash, 1, 1);
+        receive(&mut blockchain, a1.clone(), &chain_events);
+
+        // a block claiming too much reward with an already confirmed transaction
+        let mut invalid = block(&blockchain, a1.hash, 2, 2);
+        invalid.header.reward += 1;
+        invalid.transactions.push(a1.transactions[0].clone());
+        receive(&mut blockchain, invalid, &chain_events);
+        let db = blockchain.database.lock().unwrap();
+        assert_eq!(db.head().unwrap().hash, a1.hash);
+        let coinbase_hash = a1.transactions[0].hash();
+        assert!(db.get_transaction(&coinbase_hash).is_some());
+        assert!(db.is_utxo(&coinbase_hash, 0));
     }
 }
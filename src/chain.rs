@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    block::{Block, BlockHeader},
+    config::chain_params::ChainParams,
+    crypto::hash_utils::HashResult,
+    database::database::DatabaseType,
+};
+
+/// How a valid block changed the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainUpdate {
+    /// The block extends the head and became the new head.
+    Extended,
+    /// The block extends a side chain which is not longer than the chain.
+    /// It is stored without affecting the unspent outputs.
+    SideChain,
+    /// The side chain of the block became the longest and replaced this many
+    /// blocks of the chain.
+    Reorganized(usize),
+}
+
+/// Verifies the block and adds it to the chain it extends. A side chain
+/// growing longer than the chain replaces it: the blocks after the fork are
+/// disconnected, returning their transactions to the pending ones, and the
+/// blocks of the side chain are verified and connected. A rejected block
+/// leaves the chain as it was, the reason is returned as metrics label.
+pub fn add_block(
+    database: &Arc<Mutex<DatabaseType>>,
+    chain_params: &ChainParams,
+    block: Block,
+) -> Result<ChainUpdate, &'static str> {
+    let (height, head_hash, valid_difficulty) = {
+        let db = database.lock().unwrap();
+        let height = db.chain_length(&block.header.previous_block_hash);
+        let valid_difficulty = verify_difficulty(&*db, chain_params, &block.header);
+        (height, db.head().map(|head| head.hash), valid_difficulty)
+    };
+    let Some(height) = height else {
+        return Err("unknown_parent");
+    };
+    if block.header.reward != chain_params.block_reward(height) {
+        return Err("invalid_reward");
+    }
+    if !valid_difficulty {
+        return Err("invalid_difficulty");
+    }
+
+    if head_hash == Some(block.header.previous_block_hash) {
+        if !block.verify_transactions(database) {
+            return Err("invalid_block");
+        }
+        database.lock().unwrap().insert_block(block);
+        return Ok(ChainUpdate::Extended);
+    }
+
+    let mut db = database.lock().unwrap();
+    if height < db.block_height() {
+        db.insert_block(block);
+        return Ok(ChainUpdate::SideChain);
+    }
+    let Some(branch) = side_chain(&*db, block.clone()) else {
+        return Err("unknown_parent");
+    };
+    db.insert_block(block);
+    let fork = branch[0].header.previous_block_hash;
+    let mut disconnected = vec![];
+    while db.head().map(|head| head.hash) != Some(fork) {
+        match db.disconnect_head() {
+            Some(block) => disconnected.push(block),
+            None => break,
+        }
+    }
+    drop(db);
+
+    // the blocks of the side chain are verified against the chain up to their parent
+    for (connected, block) in branch.iter().enumerate() {
+        if !block.verify_transactions(database) {
+            let mut db = database.lock().unwrap();
+            for _ in 0..connected {
+                db.disconnect_head();
+            }
+            for block in disconnected.into_iter().rev() {
+                db.insert_block(block);
+            }
+            remove_unspendable_pending(&mut *db);
+            return Err("invalid_block");
+        }
+        database.lock().unwrap().insert_block(block.clone());
+    }
+    remove_unspendable_pending(&mut *database.lock().unwrap());
+    Ok(ChainUpdate::Reorganized(disconnected.len()))
+}
+
+/// Checks the difficulty of a block against the block it extends. The first
+/// block after the genesis block has the start difficulty, which changes by
+/// one bit at most after each adjustment interval. Blocks extending an unknown
+/// block pass, they are checked once it arrives.
+pub fn verify_difficulty(
+    db: &DatabaseType,
+    chain_params: &ChainParams,
+    header: &BlockHeader,
+) -> bool {
+    let Some(parent) = db.get_block(&header.previous_block_hash) else {
+        return true;
+    };
+    let height = db.chain_length(&parent.hash).unwrap_or_default();
+    let expected = if height <= 1 {
+        chain_params.start_difficulty_bit
+    } else {
+        parent.header.difficulty
+    };
+    let adjusted = !chain_params.fixed_difficulty
+        && height.is_multiple_of(chain_params.block_adjustment_interval)
+        && header.difficulty.abs_diff(expected) == 1;
+    header.difficulty > 0 && (header.difficulty == expected || adjusted)
+}
+
+/// Blocks of the side chain ending with the block, from the first block after
+/// the fork. Returns `None` if the side chain does not lead back to the chain.
+fn side_chain(db: &DatabaseType, block: Block) -> Option<Vec<Block>> {
+    let chain: HashSet<HashResult> = db.get_blocks().iter().map(|block| block.hash).collect();
+    let mut branch = vec![block];
+    loop {
+        let parent_hash = branch.last()?.header.previous_block_hash;
+        if chain.contains(&parent_hash) {
+            break;
+        }
+        branch.push(db.get_block(&parent_hash)?.clone());
+    }
+    branch.reverse();
+    Some(branch)
+}
+
+/// Removes the pending transactions spending outputs the chain no longer
+/// contains, like those of disconnected coinbase transactions, together with
+/// the transactions spending their outputs.
+fn remove_unspendable_pending(db: &mut DatabaseType) {
+    let mut spendable: HashSet<HashResult> = HashSet::new();
+    let mut unspendable = vec![];
+    for tx in db.get_pending_transactions() {
+        let tx_hash = tx.hash();
+        if tx.inputs.iter().all(|input| {
+            db.is_utxo(&input.utxo_tx_hash, input.utxo_output_index)
+                || spendable.contains(&input.utxo_tx_hash)
+        }) {
+            spendable.insert(tx_hash);
+        } else {
+            unspendable.push(tx_hash);
+        }
+    }
+    for tx_hash in unspendable {
+        log::debug!(
+            "Dropped pending transaction {} spending a disconnected output.",
+            hex::encode(tx_hash)
+        );
+        db.remove_pending_transaction(&tx_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{add_block, verify_difficulty, ChainUpdate};
+    use crate::{
+        block::Block,
+        config::{chain_params::ChainParams, models::AccountConfig},
+        crypto::{account::Account, hash_utils::HashResult},
+        database::{
+            database::{Database, DatabaseType},
+            InMemoryDatabase,
+        },
+        mining::template::BlockTemplate,
+        transaction::{builder::TransactionBuilder, Transaction},
+    };
+
+    fn block(
+        chain_params: &ChainParams,
+        parent: HashResult,
+        height: usize,
+        miner: u8,
+        transactions: Vec<Transaction>,
+    ) -> Block {
+        let reward = chain_params.block_reward(height);
+        let coinbase = Transaction::create_block_coinbase(&[(vec![miner; 32], reward)], height, 0);
+        BlockTemplate {
+            previous_block_hash: parent,
+            height,
+            difficulty: chain_params.start_difficulty_bit,
+            timestamp: height as u64,
+            reward,
+            fees: 0,
+            transactions,
+        }
+        .assemble(vec![coinbase])
+    }
+
+    #[test]
+    fn test_reorganization() {
+        let chain_params = ChainParams::regtest();
+        let genesis = chain_params.genesis_block();
+        let database: Arc<Mutex<DatabaseType>> = Arc::new(Mutex::new(InMemoryDatabase::new()));
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let funding = TransactionBuilder::new(&account)
+            .add_input([9u8; 32], 0, 10_000)
+            .add_output(account.public_key(), 10_000)
+            .build()
+            .unwrap();
+        {
+            let mut db = database.lock().unwrap();
+            db.create_genesis_block(genesis.clone());
+            db.add_transaction(funding.hash(), funding.clone());
+            db.add_utxo(funding.hash(), 0);
+        }
+        let tx = TransactionBuilder::new(&account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 9_000)
+            .build()
+            .unwrap();
+
+        let a1 = block(&chain_params, genesis.hash, 1, 1, vec![tx.clone()]);
+        let update = add_block(&database, &chain_params, a1.clone());
+        assert_eq!(update, Ok(ChainUpdate::Extended));
+
+        // the competing block is stored without spending or adding outputs
+        let b1 = block(&chain_params, genesis.hash, 1, 2, vec![]);
+        let update = add_block(&database, &chain_params, b1.clone());
+        assert_eq!(update, Ok(ChainUpdate::SideChain));
+        {
+            let db = database.lock().unwrap();
+            assert!(!db.is_utxo(&funding.hash(), 0));
+            assert!(db.is_utxo(&tx.hash(), 0));
+            assert!(!db.is_utxo(&b1.transactions[0].hash(), 0));
+            assert!(db.get_transaction(&b1.transactions[0].hash()).is_none());
+        }
+
+        // its chain replaces the first block, whose transaction is pending again
+        let b2 = block(&chain_params, b1.hash, 2, 2, vec![]);
+        let update = add_block(&database, &chain_params, b2.clone());
+        assert_eq!(update, Ok(ChainUpdate::Reorganized(1)));
+        {
+            let db = database.lock().unwrap();
+            assert_eq!(db.head().unwrap().hash, b2.hash);
+            assert!(db.is_utxo(&funding.hash(), 0));
+            assert!(!db.is_utxo(&tx.hash(), 0));
+            assert!(!db.is_utxo(&a1.transactions[0].hash(), 0));
+            assert!(db.get_transaction(&a1.transactions[0].hash()).is_none());
+            assert!(db.get_transaction_hashes(&[1u8; 32]).is_empty());
+            assert!(db.is_utxo(&b1.transactions[0].hash(), 0));
+            assert!(db.is_utxo(&b2.transactions[0].hash(), 0));
+            let pending: Vec<_> = db
+                .get_pending_transactions()
+                .iter()
+                .map(|x| x.hash())
+                .collect();
+            assert_eq!(pending, vec![tx.hash()]);
+        }
+
+        // a side chain with an invalid block is rolled back
+        let invalid = TransactionBuilder::new(&account)
+            .add_input([8u8; 32], 0, 10_000)
+            .add_output(&[7u8; 32], 9_000)
+            .build()
+            .unwrap();
+        let a2 = block(&chain_params, a1.hash, 2, 1, vec![invalid]);
+        let update = add_block(&database, &chain_params, a2.clone());
+        assert_eq!(update, Ok(ChainUpdate::SideChain));
+        let a3 = block(&chain_params, a2.hash, 3, 1, vec![]);
+        let update = add_block(&database, &chain_params, a3);
+        assert_eq!(update, Err("invalid_block"));
+        let db = database.lock().unwrap();
+        assert_eq!(db.head().unwrap().hash, b2.hash);
+        assert_eq!(db.block_height(), 3);
+        assert!(db.is_utxo(&funding.hash(), 0));
+        assert!(db.is_utxo(&b2.transactions[0].hash(), 0));
+        assert!(!db.is_utxo(&a1.transactions[0].hash(), 0));
+        assert_eq!(db.get_pending_transactions().len(), 1);
+        drop(db);
+
+        // blocks whose parent is unknown are rejected
+        let orphan = block(&chain_params, [5u8; 32], 1, 1, vec![]);
+        assert_eq!(
+            add_block(&database, &chain_params, orphan),
+            Err("unknown_parent")
+        );
+    }
+
+    #[test]
+    fn test_verify_difficulty() {
+        let chain_params = ChainParams {
+            block_adjustment_interval: 2,
+            start_difficulty_bit: 8,
+            ..ChainParams::mainnet()
+        };
+        let genesis = chain_params.genesis_block();
+        let mut db = InMemoryDatabase::new();
+        db.create_genesis_block(genesis.clone());
+        let mut header = block(&chain_params, genesis.hash, 1, 1, vec![]).header;
+
+        // the first block has the start difficulty
+        assert!(verify_difficulty(&db, &chain_params, &header));
+        header.difficulty = 1;
+        assert!(!verify_difficulty(&db, &chain_params, &header));
+        let database: Arc<Mutex<DatabaseType>> = Arc::new(Mutex::new(db));
+        let mut easy = block(&chain_params, genesis.hash, 1, 1, vec![]);
+        easy.header.difficulty = 1;
+        assert_eq!(
+            add_block(&database, &chain_params, easy),
+            Err("invalid_difficulty")
+        );
+
+        // it changes by one bit after the adjustment interval
+        let b1 = block(&chain_params, genesis.hash, 1, 1, vec![]);
+        let b2 = block(&chain_params, b1.hash, 2, 1, vec![]);
+        let mut db = database.lock().unwrap();
+        db.insert_block(b1.clone());
+        db.insert_block(b2.clone());
+        let mut header = block(&chain_params, b2.hash, 3, 2, vec![]).header;
+        header.difficulty = 9;
+        assert!(!verify_difficulty(&*db, &chain_params, &header));
+        header.previous_block_hash = b1.hash;
+        for (difficulty, valid) in [(7, true), (8, true), (9, true), (10, false), (0, false)] {
+            header.difficulty = difficulty;
+            assert_eq!(verify_difficulty(&*db, &chain_params, &header), valid);
+        }
+        let fixed = ChainParams {
+            fixed_difficulty: true,
+            ..chain_params.clone()
+        };
+        header.difficulty = 9;
+        assert!(!verify_difficulty(&*db, &fixed, &header));
+
+        // blocks extending an unknown block are checked once it arrives
+        header.previous_block_hash = [5u8; 32];
+        assert!(verify_difficulty(&*db, &chain_params, &header));
+    }
+}
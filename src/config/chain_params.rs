@@ -0,0 +1,131 @@
+use serde::Deserialize;
+
+use crate::{
+    block::{genesis::genesis_block, Block},
+    types::Satoshi,
+};
+
+/// Consensus rules of a network. Nodes only connect to peers of the same
+/// network, so that several chains can run side by side.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChainParams {
+    pub name: String,
+    /// Identifier exchanged in the handshake.
+    pub network_id: u32,
+    /// Port the node listens on unless configured otherwise.
+    pub default_port: u16,
+    /// Unix timestamp of the genesis block, which makes it unique to the network.
+    pub genesis_timestamp: u64,
+    /// Block reward before the first halvening.
+    pub mining_reward: Satoshi,
+    /// Number of blocks after which the block reward is halved, 0 never halves it.
+    pub reward_halvening_interval: usize,
+    pub block_time_secs: usize,
+    pub block_adjustment_interval: usize,
+    pub start_difficulty_bit: u8,
+    /// Keeps the difficulty at the start difficulty.
+    pub fixed_difficulty: bool,
+    /// Blocks are only mined on demand with the `GenerateBlocks` RPC.
+    pub generate_on_demand: bool,
+}
+
+impl ChainParams {
+    pub fn mainnet() -> Self {
+        Self {
+            name: "mainnet".to_string(),
+            network_id: 0x454d_4231,
+            default_port: 1337,
+            genesis_timestamp: 1231006505,
+            mining_reward: 50,
+            reward_halvening_interval: 100,
+            block_time_secs: 30,
+            block_adjustment_interval: 10,
+            start_difficulty_bit: 22,
+            fixed_difficulty: false,
+            generate_on_demand: false,
+        }
+    }
+
+    /// Public test network with a lower start difficulty.
+    pub fn testnet() -> Self {
+        Self {
+            name: "testnet".to_string(),
+            network_id: 0x454d_4254,
+            default_port: 11337,
+            genesis_timestamp: 1414776313,
+            start_difficulty_bit: 16,
+            ..Self::mainnet()
+        }
+    }
+
+    /// Regression test network, where blocks are generated on demand at a
+    /// minimal difficulty.
+    pub fn regtest() -> Self {
+        Self {
+            name: "regtest".to_string(),
+            network_id: 0x454d_4252,
+            default_port: 18444,
+            genesis_timestamp: 1296688602,
+            reward_halvening_interval: 150,
+            start_difficulty_bit: 1,
+            fixed_difficulty: true,
+            generate_on_demand: true,
+            ..Self::mainnet()
+        }
+    }
+
+    /// Parameters of a built-in network by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mainnet" => Some(Self::mainnet()),
+            "testnet" => Some(Self::testnet()),
+            "regtest" => Some(Self::regtest()),
+            _ => None,
+        }
+    }
+
+    pub fn genesis_block(&self) -> Block {
+        genesis_block(self.genesis_timestamp)
+    }
+
+    /// Reward of the block at the given height, halved every `reward_halvening_interval` blocks.
+    pub fn block_reward(&self, height: usize) -> Satoshi {
+        if self.reward_halvening_interval == 0 {
+            return self.mining_reward;
+        }
+        let halvenings = height / self.reward_halvening_interval;
+        self.mining_reward
+            .checked_shr(halvenings as u32)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChainParams;
+
+    #[test]
+    fn test_block_reward() {
+        let params = ChainParams::mainnet();
+        assert_eq!(params.block_reward(1), 50);
+        assert_eq!(params.block_reward(99), 50);
+        assert_eq!(params.block_reward(100), 25);
+        assert_eq!(params.block_reward(250), 12);
+        assert_eq!(params.block_reward(100 * 64), 0);
+    }
+
+    #[test]
+    fn test_distinct_networks() {
+        let networks = [
+            ChainParams::mainnet(),
+            ChainParams::testnet(),
+            ChainParams::regtest(),
+        ];
+        for (i, a) in networks.iter().enumerate() {
+            for b in networks.iter().skip(i + 1) {
+                assert_ne!(a.network_id, b.network_id);
+                assert_ne!(a.genesis_block().hash, b.genesis_block().hash);
+            }
+        }
+    }
+}
@@ -1,10 +1,20 @@
 use std::fs;
 
-use super::models::{Config, WalletConfig};
+use super::chain_params::ChainParams;
+use super::models::{Config, MinerConfig, PoolConfig, SimulationConfig, WalletConfig};
 
 pub fn load_toml(path: &str) -> Config {
     let contents = fs::read_to_string(path).unwrap();
     log::info!("Loaded configuration file: {}.", path);
+    let mut config: Config = toml::from_str(&contents).unwrap();
+    config.chain_params = ChainParams::from_name(&config.network.chain)
+        .unwrap_or_else(|| load_toml_chain_params(&config.network.chain));
+    config
+}
+
+pub fn load_toml_chain_params(path: &str) -> ChainParams {
+    let contents = fs::read_to_string(path).unwrap();
+    log::info!("Loaded chain parameters file: {}.", path);
     toml::from_str(&contents).unwrap()
 }
 
@@ -13,3 +23,21 @@ pub fn load_toml_wallet(path: &str) -> WalletConfig {
     log::info!("Loaded wallet configuration file: {}.", path);
     toml::from_str(&contents).unwrap()
 }
+
+pub fn load_toml_miner(path: &str) -> MinerConfig {
+    let contents = fs::read_to_string(path).unwrap();
+    log::info!("Loaded miner configuration file: {}.", path);
+    toml::from_str(&contents).unwrap()
+}
+
+pub fn load_toml_pool(path: &str) -> PoolConfig {
+    let contents = fs::read_to_string(path).unwrap();
+    log::info!("Loaded pool configuration file: {}.", path);
+    toml::from_str(&contents).unwrap()
+}
+
+pub fn load_toml_simulation(path: &str) -> SimulationConfig {
+    let contents = fs::read_to_string(path).unwrap();
+    log::info!("Loaded simulation configuration file: {}.", path);
+    toml::from_str(&contents).unwrap()
+}
@@ -1,2 +1,3 @@
+pub mod chain_params;
 pub mod loader;
 pub mod models;
@@ -1,25 +1,96 @@
 use serde::Deserialize;
 
-use crate::types::Satoshi;
+use crate::{types::Satoshi, wallet::coin_selection::CoinSelectionStrategy};
+
+use super::chain_params::ChainParams;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct MiningConfig {
-    pub block_time_secs: usize,
-    pub block_adjustment_interval: usize,
-    pub start_difficulty_bit: u8,
-    pub mining_reward: Satoshi,
-    pub reward_halvening_interval: usize,
+    /// Runs the built-in miner. Without it, blocks are mined by external miners
+    /// using the block template RPCs.
+    pub enabled: bool,
+    /// Maximum size in bytes of the transactions included in a block.
+    pub max_block_size: usize,
+    /// Number of worker threads used for mining, 0 uses all available cores.
+    pub threads: usize,
+    /// Maximum age of a block template before it is rebuilt with the latest
+    /// pending transactions and timestamp.
+    pub template_refresh_secs: u64,
 }
 
 #[derive(Deserialize, Clone, Debug)]
-pub struct SimulationConfig {
-    pub fake_mining: bool,
+pub struct MempoolConfig {
+    /// Minimum fee rate in satoshis per kilobyte for a transaction to be accepted.
+    /// Replacements must increase the fee by at least this rate.
+    pub min_relay_fee_rate: Satoshi,
+    /// Maximum number of pending transactions a replacement can evict.
+    pub max_replacements: usize,
+    /// Number of recent blocks whose transactions are used for fee estimation.
+    pub fee_estimation_blocks: usize,
+    /// File the pending transactions are saved to on shutdown and loaded from on startup.
+    pub persist_path: String,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct NetworkConfig {
-    pub port: u16,
+    /// Name of a built-in network (mainnet, testnet, regtest) or the path to
+    /// a TOML file with custom chain parameters.
+    pub chain: String,
+    /// Defaults to the port of the chain parameters.
+    pub port: Option<u16>,
     pub seed_list: Vec<String>,
+    /// Number of outbound connections the node maintains.
+    pub target_outbound_peers: usize,
+    /// File the known peer addresses are persisted to.
+    pub peers_path: String,
+    /// Encrypts the connections to peers, which have to present a certificate
+    /// of the same authority.
+    pub tls: Option<TlsConfig>,
+    /// Requires peers to prove ownership of an identity key on each connection.
+    pub auth: Option<AuthConfig>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TlsConfig {
+    /// PEM certificate and private key identifying the node.
+    pub cert_path: String,
+    pub key_path: String,
+    /// PEM certificate of the authority which issued the certificates of all nodes.
+    pub ca_path: String,
+    /// Name the certificates are issued for, verified instead of the host of a peer.
+    pub domain_name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AuthConfig {
+    /// Ed25519 key identifying the node, created if it does not exist.
+    pub identity_keys_path: String,
+    /// Hex encoded public keys of the peers we connect to. If empty, any peer
+    /// proving ownership of a key is accepted.
+    pub trusted_peers: Vec<String>,
+}
+
+/// Client-facing RPC service of wallets, miners and administrators, served
+/// separately from the peer-to-peer service.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RpcConfig {
+    pub bind_address: String,
+    /// Token clients have to send as bearer token. If unset, no token is required.
+    pub auth_token: Option<String>,
+    /// Requests per second allowed per client IP, 0 disables the limit.
+    pub requests_per_second: u32,
+    /// Number of requests a client may send at once before it is limited.
+    pub burst: u32,
+    /// Refuses calls changing the state of the node, like submitting transactions.
+    pub read_only: bool,
+    /// Address of the HTTP JSON gateway to the RPC service, disabled if unset.
+    pub http_bind_address: Option<String>,
+}
+
+/// HTTP endpoint exporting the metrics of the node to Prometheus.
+#[derive(Deserialize, Clone, Debug)]
+pub struct MetricsConfig {
+    pub bind_address: String,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -30,13 +101,94 @@ pub struct AccountConfig {
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub mining: MiningConfig,
-    pub simulation: SimulationConfig,
     pub network: NetworkConfig,
     pub account: AccountConfig,
+    pub mempool: MempoolConfig,
+    pub rpc: RpcConfig,
+    /// Serves `/metrics` if set.
+    pub metrics: Option<MetricsConfig>,
+    /// Resolved from `network.chain` when the configuration is loaded.
+    #[serde(skip, default = "ChainParams::mainnet")]
+    pub chain_params: ChainParams,
+}
+
+impl Config {
+    /// Port the node listens on.
+    pub fn port(&self) -> u16 {
+        self.network.port.unwrap_or(self.chain_params.default_port)
+    }
+}
+
+/// Configuration of a standalone miner, which mines block templates of a node.
+#[derive(Deserialize, Clone, Debug)]
+pub struct MinerConfig {
+    pub account: AccountConfig,
+    pub rpc_url: String,
+    /// Auth token of the RPC service, if it requires one.
+    pub rpc_token: Option<String>,
+    /// Number of worker threads used for mining, 0 uses all available cores.
+    pub threads: usize,
+    /// Interval in which a new block template is requested from the node.
+    pub template_refresh_secs: u64,
+    /// Address of a mining pool. If set, shares are mined for the pool instead
+    /// of blocks for the node.
+    pub pool_address: Option<String>,
+}
+
+/// Configuration of a mining pool, which distributes block templates of a node to workers.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PoolConfig {
+    /// Account receiving the part of the reward not paid to the workers.
+    pub account: AccountConfig,
+    pub rpc_url: String,
+    /// Auth token of the RPC service, if it requires one.
+    pub rpc_token: Option<String>,
+    pub bind_address: String,
+    /// Difficulty bit of the shares, lower than the block difficulty.
+    pub share_difficulty_bit: u8,
+    /// Number of most recent shares the block reward is split among.
+    pub pplns_window: usize,
+    /// Interval in which a new block template is requested from the node.
+    pub template_refresh_secs: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CoinSelectionConfig {
+    pub strategy: CoinSelectionStrategy,
+    /// Fee rate in satoshis per kilobyte, used if the node cannot estimate one.
+    pub fee_rate: Satoshi,
+    /// Number of blocks within which sent transactions should be confirmed.
+    pub fee_target_blocks: u32,
+    pub dust_threshold: Satoshi,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct WalletConfig {
     pub account: AccountConfig,
     pub rpc_url: String,
+    /// Auth token of the RPC service, if it requires one.
+    pub rpc_token: Option<String>,
+    /// Signals that sent transactions may be replaced with ones paying a higher fee.
+    pub replace_by_fee: bool,
+    pub coin_selection: CoinSelectionConfig,
+}
+
+/// Scenario of a deterministic simulation, in which several in-process nodes
+/// mine on a virtual clock. The same configuration replays the same scenario.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SimulationConfig {
+    /// Seed of the random number generator, which decides the node accounts
+    /// and the message latencies.
+    pub seed: u64,
+    pub nodes: usize,
+    /// Number of blocks mined before the simulation ends.
+    pub blocks: usize,
+    /// Hashes per virtual second of each node.
+    pub hashrate: u64,
+    /// Delay of a message between two nodes in virtual milliseconds, drawn
+    /// uniformly from this range.
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub mining: MiningConfig,
+    pub chain: ChainParams,
 }
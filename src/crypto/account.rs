@@ -25,6 +25,11 @@ impl From<ring::error::KeyRejected> for AccountError {
     }
 }
 
+const PKCS8_V2_PREFIX: &[u8] = &[
+    0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+const PKCS8_V2_MIDDLE: &[u8] = &[0xa1, 0x23, 0x03, 0x21, 0x00];
+
 pub struct Account {
     config: AccountConfig,
     pkcs8_data: Vec<u8>,
@@ -45,6 +50,23 @@ impl Account {
         })
     }
 
+    /// Derives the account from a seed, e.g. to replay a simulation with the same accounts.
+    pub fn from_seed(config: AccountConfig, seed: &[u8; 32]) -> Result<Self, AccountError> {
+        let key_pair = signature::Ed25519KeyPair::from_seed_unchecked(seed)?;
+
+        // PKCS#8 v2 document as generated by ring, so that the account can be saved
+        let mut pkcs8_data = PKCS8_V2_PREFIX.to_vec();
+        pkcs8_data.extend_from_slice(seed);
+        pkcs8_data.extend_from_slice(PKCS8_V2_MIDDLE);
+        pkcs8_data.extend_from_slice(key_pair.public_key().as_ref());
+
+        Ok(Account {
+            config,
+            pkcs8_data,
+            key_pair,
+        })
+    }
+
     pub fn load_or_create(config: AccountConfig) -> Result<Self, AccountError> {
         Ok(match Account::load(config.clone()) {
             Ok(acc) => acc,
@@ -61,7 +83,7 @@ impl Account {
         if let Ok(key_data) = fs::read(&config.keys_path) {
             log::info!("Loaded the account data from: {}", &config.keys_path);
             return Ok(Account {
-                config: config,
+                config,
                 pkcs8_data: key_data.to_vec(),
                 key_pair: signature::Ed25519KeyPair::from_pkcs8(&key_data)?,
             });
@@ -95,7 +117,7 @@ impl Account {
     }
 
     pub fn public_key_from_hex(&self, hex: &str) -> Option<Vec<u8>> {
-        let rx_address = hex::decode(hex.as_bytes().to_vec());
+        let rx_address = hex::decode(hex.as_bytes());
         if rx_address.is_err() {
             log::debug!("usage: address must be in a valid hex form");
             return None;
@@ -147,4 +169,19 @@ mod tests {
 
         assert!(is_valid);
     }
+
+    #[test]
+    fn test_from_seed() {
+        let config = AccountConfig {
+            keys_path: "./configs/keys_test_seed.pkcs8".to_string(),
+        };
+        let account = Account::from_seed(config.clone(), &[7u8; 32]).unwrap();
+        let other = Account::from_seed(config.clone(), &[7u8; 32]).unwrap();
+        assert_eq!(account.public_key(), other.public_key());
+
+        assert!(account.save());
+        let loaded_account = Account::load(config.clone()).unwrap();
+        assert_eq!(account.public_key(), loaded_account.public_key());
+        std::fs::remove_file(&config.keys_path).unwrap();
+    }
 }
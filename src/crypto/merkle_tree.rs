@@ -27,7 +27,7 @@ pub fn get_leaf_direction(hash: &HashResult, hashes: &[Vec<HashResult>]) -> Dire
 
 /// Calculate merkle root of the hashes.
 pub fn generate_merkle_root(mut hashes: Vec<HashResult>) -> HashResult {
-    if hashes.len() % 2 != 0 {
+    if !hashes.len().is_multiple_of(2) {
         hashes.push(*hashes.last().unwrap());
     }
 
@@ -57,7 +57,7 @@ pub fn generate_merkle_tree(mut hashes: Vec<HashResult>) -> Vec<Vec<HashResult>>
         if combined_hashes.len() == 1 {
             break;
         }
-        if combined_hashes.len() % 2 != 0 {
+        if !combined_hashes.len().is_multiple_of(2) {
             combined_hashes.push(*combined_hashes.last().unwrap());
         }
         ensure_even(&mut combined_hashes);
@@ -71,7 +71,7 @@ pub fn generate_merkle_tree(mut hashes: Vec<HashResult>) -> Vec<Vec<HashResult>>
 }
 
 fn ensure_even(hashes: &mut Vec<HashResult>) {
-    if hashes.len() % 2 != 0 && hashes.len() > 1 {
+    if !hashes.len().is_multiple_of(2) && hashes.len() > 1 {
         hashes.push(*hashes.last().unwrap());
     }
 }
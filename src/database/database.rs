@@ -8,21 +8,33 @@ pub trait Database {
     /// Retrieves the program's version
     fn get_version(&self) -> String;
 
-    /// Creates and inserts the genesis block.
-    fn create_genesis_block(&mut self);
+    /// Inserts the genesis block of the chain.
+    fn create_genesis_block(&mut self, block: Block);
 
-    /// Inserts a block into the database.
+    /// Inserts a block into the database. A block extending the head becomes
+    /// the new head, its transactions are applied to the unspent outputs and
+    /// removed from the pending ones. Other blocks are only stored on the side
+    /// chain they extend.
     fn insert_block(&mut self, block: Block);
 
+    /// Removes the head from the chain and returns it, its parent becomes the
+    /// head. The effects of its transactions on the unspent outputs are undone
+    /// and all but the coinbase are returned to the pending transactions. The
+    /// block stays stored on a side chain. The genesis block is never removed.
+    fn disconnect_head(&mut self) -> Option<Block>;
+
+    /// Searches for a block given its hash, whether it is part of the longest chain or not.
+    fn get_block(&self, block_hash: &HashResult) -> Option<&Block>;
+
     /// Retrieves the blocks of the longest chain.
     fn get_blocks(&self) -> Vec<&Block>;
 
-    /// Resolves the fork by determining the longest chain.
-    fn resolve_fork(&mut self);
-
     /// Retrieves the number of blocks.
     fn block_height(&self) -> usize;
 
+    /// Retrieves the number of blocks of the chain ending with the block.
+    fn chain_length(&self, block_hash: &HashResult) -> Option<usize>;
+
     /// Retrieves the last block inserted.
     fn head(&self) -> Option<&Block>;
 
@@ -62,9 +74,15 @@ pub trait Database {
     /// Adds a pending transaction
     fn add_pending_transaction(&mut self, transaction: Transaction);
 
+    /// Removes a pending transaction identified by its hash
+    fn remove_pending_transaction(&mut self, tx_hash: &HashResult) -> Option<Transaction>;
+
     /// Retrieves all the pending transactions
     fn get_pending_transactions(&self) -> &[Transaction];
 
     /// Clears (removes) all the pending transactions
     fn clear_pending_transactions(&mut self);
+
+    /// Checks if a transaction output is spent by one of the pending transactions.
+    fn is_spent_in_mempool(&self, tx_hash: &HashResult, output_index: u32) -> bool;
 }
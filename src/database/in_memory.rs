@@ -1,10 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use chrono::DateTime;
-
-use crate::block::BlockHeader;
 use crate::crypto::hash_utils::Address;
-use crate::crypto::merkle_tree::generate_merkle_root;
 use crate::types::Satoshi;
 use crate::{block::Block, crypto::hash_utils::HashResult, transaction::Transaction};
 
@@ -41,17 +37,7 @@ impl Default for InMemoryDatabase {
 }
 
 impl Database for InMemoryDatabase {
-    fn create_genesis_block(&mut self) {
-        let ts = DateTime::parse_from_rfc3339("2009-01-03T18:15:05-00:00")
-            .unwrap()
-            .timestamp() as u64;
-        let merkle_root = generate_merkle_root(vec![]);
-        let header = BlockHeader::from(merkle_root, [0u8; 32], 0, ts, 0);
-        let block = Block {
-            hash: header.finalize(),
-            header,
-            transactions: vec![],
-        };
+    fn create_genesis_block(&mut self, block: Block) {
         let block_hash = block.get_hash_as_string(false).clone();
 
         self.blocks.insert(block_hash.clone(), block.clone());
@@ -66,6 +52,29 @@ impl Database for InMemoryDatabase {
     }
     fn insert_block(&mut self, block: Block) {
         let block_height = self.block_height();
+        let block_hash = block.get_hash_as_string(false);
+        let parent_hash = hex::encode(block.header.previous_block_hash);
+        let extends_head = self.head().is_none() || parent_hash == self.longest_chain_tip_hash;
+
+        let chain = match self.chains.get(&parent_hash) {
+            Some(chain) => {
+                let mut chain = chain.clone();
+                chain.push(block_hash.clone());
+                chain
+            }
+            // Orphan blocks
+            None => vec![block_hash.clone()],
+        };
+        self.chains.insert(block_hash.clone(), chain);
+        self.blocks.insert(block_hash.clone(), block.clone());
+
+        if !extends_head {
+            log::info!(
+                "Block ({}) stored on a side chain.",
+                hex::encode(block.hash.get(..5).unwrap())
+            );
+            return;
+        }
 
         for tx in block.transactions.iter() {
             let tx_hash = tx.hash();
@@ -77,45 +86,88 @@ impl Database for InMemoryDatabase {
             }
 
             // add new unspent outputs
-            for output_index in 0..tx.outputs.len() {
-                self.add_utxo(tx_hash, output_index as u32);
+            for (output_index, output) in tx.outputs.iter().enumerate() {
+                if !output.is_unspendable() {
+                    self.add_utxo(tx_hash, output_index as u32);
+                }
             }
 
             // update transaction mappings
             self.map_address_to_transaction_hash(&tx.sender, tx_hash);
-            for output in tx.outputs.iter() {
+            for output in tx.outputs.iter().filter(|x| !x.is_unspendable()) {
                 self.map_address_to_transaction_hash(&output.receiver, tx_hash);
             }
         }
 
+        // pending transactions which are confirmed or double-spent by this block
+        self.pending_transactions.retain(|pending_tx| {
+            !block.transactions.iter().any(|tx| {
+                tx.hash() == pending_tx.hash()
+                    || tx.inputs.iter().any(|input| {
+                        pending_tx
+                            .inputs
+                            .iter()
+                            .any(|pending_input| pending_input.spends_same_output(input))
+                    })
+            })
+        });
+
         log::info!(
             "Block ({}) added at height {} with {} transactions.",
             hex::encode(block.hash.get(..5).unwrap()),
             block_height,
             block.transactions.len()
         );
+        self.longest_chain_tip_hash = block_hash;
+    }
 
-        let block_hash = block.get_hash_as_string(false);
-        self.blocks.insert(block_hash.clone(), block.clone());
-
-        if let Some(chain) = self
-            .chains
-            .get(&hex::encode(block.header.previous_block_hash))
-        {
-            let mut new_chain = chain.clone();
-            new_chain.push(block_hash.clone());
+    fn disconnect_head(&mut self) -> Option<Block> {
+        let chain = self.chains.get(&self.longest_chain_tip_hash)?;
+        let parent_hash = chain.get(chain.len().checked_sub(2)?)?.clone();
+        let block = self.blocks.get(&self.longest_chain_tip_hash)?.clone();
 
-            let new_chain_height = new_chain.len();
-            self.chains.insert(block_hash.clone(), new_chain);
+        // later transactions may spend the outputs of earlier ones
+        for tx in block.transactions.iter().rev() {
+            let tx_hash = tx.hash();
+            for (output_index, output) in tx.outputs.iter().enumerate() {
+                if !output.is_unspendable() {
+                    self.remove_utxo(&tx_hash, output_index as u32);
+                }
+            }
+            for input in tx.inputs.iter().filter(|x| x.utxo_tx_hash != [0u8; 32]) {
+                self.add_utxo(input.utxo_tx_hash, input.utxo_output_index);
+            }
 
-            if new_chain_height > self.chains.get(&self.longest_chain_tip_hash).unwrap().len() {
-                self.longest_chain_tip_hash = block_hash.clone();
+            self.remove_transaction(tx_hash);
+            let addresses =
+                std::iter::once(&tx.sender).chain(tx.outputs.iter().map(|output| &output.receiver));
+            for address in addresses {
+                if let Some(hashes) = self.address_to_txs.get_mut(address) {
+                    hashes.remove(&tx_hash);
+                }
             }
-        } else {
-            // Orphan blocks
-            self.chains
-                .insert(block_hash.clone(), vec![block_hash.clone()]);
         }
+
+        // the blocks are disconnected from the head backwards, so the
+        // transactions of earlier blocks end up before those spending them
+        let returned: Vec<Transaction> = block
+            .transactions
+            .iter()
+            .filter(|tx| !tx.is_coinbase())
+            .cloned()
+            .collect();
+        self.pending_transactions.splice(0..0, returned);
+
+        log::info!(
+            "Block ({}) disconnected from the chain.",
+            hex::encode(block.hash.get(..5).unwrap())
+        );
+        self.longest_chain_tip_hash = parent_hash;
+        Some(block)
+    }
+
+    fn get_block(&self, block_hash: &HashResult) -> Option<&Block> {
+        self.blocks.get(&hex::encode(block_hash))
     }
 
     fn get_blocks(&self) -> Vec<&Block> {
@@ -128,27 +180,6 @@ impl Database for InMemoryDatabase {
         blocks
     }
 
-    fn resolve_fork(&mut self) {
-        let mut longest_chain_tip_hash = self.longest_chain_tip_hash.clone();
-        let mut max_length = self.chains.get(&longest_chain_tip_hash).unwrap().len();
-
-        for (tip, chain) in &self.chains {
-            if chain.len() > max_length {
-                longest_chain_tip_hash = tip.clone();
-                max_length = chain.len();
-            }
-        }
-
-        if longest_chain_tip_hash != self.longest_chain_tip_hash {
-            log::warn!(
-                "Fork detected. Changed head to {}.",
-                longest_chain_tip_hash.get(..5).unwrap()
-            )
-        }
-
-        self.longest_chain_tip_hash = longest_chain_tip_hash;
-    }
-
     fn block_height(&self) -> usize {
         if let Some(block_hashes) = self.chains.get(&self.longest_chain_tip_hash) {
             block_hashes.len()
@@ -157,6 +188,12 @@ impl Database for InMemoryDatabase {
         }
     }
 
+    fn chain_length(&self, block_hash: &HashResult) -> Option<usize> {
+        self.chains
+            .get(&hex::encode(block_hash))
+            .map(|chain| chain.len())
+    }
+
     fn head(&self) -> Option<&Block> {
         if let Some(block_hashes) = self.chains.get(&self.longest_chain_tip_hash) {
             if let Some(block) = self.blocks.get(block_hashes.last().unwrap()) {
@@ -243,6 +280,14 @@ impl Database for InMemoryDatabase {
         self.pending_transactions.push(transaction);
     }
 
+    fn remove_pending_transaction(&mut self, tx_hash: &HashResult) -> Option<Transaction> {
+        let index = self
+            .pending_transactions
+            .iter()
+            .position(|tx| &tx.hash() == tx_hash)?;
+        Some(self.pending_transactions.remove(index))
+    }
+
     fn get_pending_transactions(&self) -> &[Transaction] {
         &self.pending_transactions
     }
@@ -251,6 +296,14 @@ impl Database for InMemoryDatabase {
         self.pending_transactions.clear();
     }
 
+    fn is_spent_in_mempool(&self, tx_hash: &HashResult, output_index: u32) -> bool {
+        self.pending_transactions.iter().any(|tx| {
+            tx.inputs.iter().any(|input| {
+                &input.utxo_tx_hash == tx_hash && input.utxo_output_index == output_index
+            })
+        })
+    }
+
     fn get_version(&self) -> String {
         env!("CARGO_PKG_VERSION").to_string()
     }
@@ -261,14 +314,52 @@ mod tests {
     use crate::{
         block::Block,
         database::{database::Database, InMemoryDatabase},
+        transaction::{input::Input, script::Script, Transaction},
     };
 
+    fn spend(tx_hash: [u8; 32], output_index: u32, sender: u8) -> Transaction {
+        Transaction::new(
+            vec![sender],
+            vec![Input::new(tx_hash, output_index, Script::new(vec![]))],
+            vec![],
+        )
+    }
+
     #[test]
     fn test_insert_block() {
         let mut in_memory_db = InMemoryDatabase::new();
         in_memory_db.insert_block(Block::default());
         assert_eq!(in_memory_db.blocks.len(), 1);
-        in_memory_db.insert_block(Block::default());
+        in_memory_db.insert_block(Block {
+            hash: [1u8; 32],
+            ..Block::default()
+        });
         assert_eq!(in_memory_db.blocks.len(), 2);
     }
+
+    #[test]
+    fn test_pending_transactions_confirmed() {
+        let mut db = InMemoryDatabase::new();
+        let confirmed = spend([1u8; 32], 0, 1);
+        let double_spent = spend([1u8; 32], 1, 1);
+        let remaining = spend([1u8; 32], 2, 1);
+        for tx in [&confirmed, &double_spent, &remaining] {
+            db.add_pending_transaction(tx.clone());
+        }
+        assert!(db.is_spent_in_mempool(&[1u8; 32], 0));
+        assert!(!db.is_spent_in_mempool(&[1u8; 32], 3));
+
+        // the block confirms the first and spends the output of the second differently
+        db.insert_block(Block {
+            transactions: vec![confirmed, spend([1u8; 32], 1, 2)],
+            ..Block::default()
+        });
+        let pending: Vec<_> = db
+            .get_pending_transactions()
+            .iter()
+            .map(|x| x.hash())
+            .collect();
+        assert_eq!(pending, vec![remaining.hash()]);
+        assert!(!db.is_spent_in_mempool(&[1u8; 32], 0));
+    }
 }
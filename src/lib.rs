@@ -1,11 +1,18 @@
+#![allow(clippy::module_inception)]
+
 pub mod block;
 pub mod blockchain;
+pub mod chain;
 pub mod config;
 pub mod crypto;
 pub mod database;
+pub mod mempool;
+pub mod metrics;
 pub mod mining;
 pub mod network;
+pub mod pool;
 pub mod proto;
+pub mod simulation;
 pub mod transaction;
 pub mod types;
 pub mod wallet;
@@ -0,0 +1,406 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::{
+    config::models::MempoolConfig, crypto::hash_utils::HashResult,
+    database::database::DatabaseType, transaction::Transaction, types::Satoshi,
+};
+
+use super::fee_utils::{calculate_fee, fee_rate, input_value, transaction_fee};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MempoolError {
+    AlreadyKnown,
+    Coinbase,
+    /// Spends the same output with more than one input.
+    DuplicateInputs,
+    /// An unlocking script, like the signature, does not satisfy the spent output.
+    InvalidScript,
+    MissingInputs,
+    NegativeFee,
+    FeeTooLow {
+        fee_rate: Satoshi,
+        min_fee_rate: Satoshi,
+    },
+    /// Spends an output already spent by a pending transaction which does not signal replacement.
+    Conflict,
+    ReplacementRejected(String),
+}
+
+impl MempoolError {
+    /// Label of the rejection in the metrics.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            MempoolError::AlreadyKnown => "already_known",
+            MempoolError::Coinbase => "coinbase",
+            MempoolError::DuplicateInputs => "duplicate_inputs",
+            MempoolError::InvalidScript => "invalid_script",
+            MempoolError::MissingInputs => "missing_inputs",
+            MempoolError::NegativeFee => "negative_fee",
+            MempoolError::FeeTooLow { .. } => "fee_too_low",
+            MempoolError::Conflict => "conflict",
+            MempoolError::ReplacementRejected(_) => "replacement_rejected",
+        }
+    }
+}
+
+impl fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MempoolError::AlreadyKnown => write!(f, "Transaction is already pending."),
+            MempoolError::Coinbase => write!(f, "Coinbase transactions are only valid in blocks."),
+            MempoolError::DuplicateInputs => {
+                write!(f, "Transaction spends an output more than once.")
+            }
+            MempoolError::InvalidScript => {
+                write!(
+                    f,
+                    "Transaction inputs are not signed by the owners of the spent outputs."
+                )
+            }
+            MempoolError::MissingInputs => {
+                write!(f, "Transaction spends a missing or already spent output.")
+            }
+            MempoolError::NegativeFee => write!(f, "Transaction outputs exceed its inputs."),
+            MempoolError::FeeTooLow {
+                fee_rate,
+                min_fee_rate,
+            } => write!(
+                f,
+                "Fee rate {} is below the minimum of {} satoshis per kilobyte.",
+                fee_rate, min_fee_rate
+            ),
+            MempoolError::Conflict => write!(
+                f,
+                "Transaction spends an output already spent by a pending transaction."
+            ),
+            MempoolError::ReplacementRejected(reason) => {
+                write!(f, "Replacement rejected: {}", reason)
+            }
+        }
+    }
+}
+
+/// Validates a transaction and adds it to the pending transactions.
+///
+/// A transaction spending the same output as pending transactions which
+/// signal replace-by-fee evicts them, along with their descendants, if:
+///
+/// - it pays a higher fee rate than each of the transactions it conflicts with,
+/// - it pays at least the fees of all evicted transactions,
+/// - the additional fee covers its own size at the minimum relay fee rate,
+/// - no more than `max_replacements` transactions are evicted,
+/// - it does not spend outputs of the evicted transactions.
+///
+/// Returns the hashes of the evicted transactions.
+pub fn accept_transaction(
+    database: &mut DatabaseType,
+    tx: Transaction,
+    config: &MempoolConfig,
+) -> Result<Vec<HashResult>, MempoolError> {
+    let tx_hash = tx.hash();
+    let pending = database.get_pending_transactions().to_vec();
+
+    if pending.iter().any(|x| x.hash() == tx_hash) {
+        return Err(MempoolError::AlreadyKnown);
+    }
+    if tx.is_coinbase() || tx.inputs.iter().any(|x| x.utxo_tx_hash == [0u8; 32]) {
+        return Err(MempoolError::Coinbase);
+    }
+    if tx.has_duplicate_inputs() {
+        return Err(MempoolError::DuplicateInputs);
+    }
+    // checked before conflicts, so that only the owner of an output can replace its spend
+    if !tx.verify_scripts(database, &pending) {
+        return Err(MempoolError::InvalidScript);
+    }
+
+    let conflicts: Vec<&Transaction> = pending
+        .iter()
+        .filter(|pending_tx| {
+            pending_tx.inputs.iter().any(|pending_input| {
+                tx.inputs
+                    .iter()
+                    .any(|input| input.spends_same_output(pending_input))
+            })
+        })
+        .collect();
+
+    // inputs must be confirmed unspent outputs or outputs of other pending transactions
+    for input in tx.inputs.iter() {
+        let spendable = database.is_utxo(&input.utxo_tx_hash, input.utxo_output_index)
+            || pending.iter().any(|pending_tx| {
+                pending_tx.hash() == input.utxo_tx_hash
+                    && pending_tx
+                        .outputs
+                        .get(input.utxo_output_index as usize)
+                        .is_some_and(|output| !output.is_unspendable())
+            });
+        if !spendable || input_value(database, &pending, input).is_none() {
+            return Err(MempoolError::MissingInputs);
+        }
+    }
+
+    let fee = transaction_fee(database, &pending, &tx).ok_or(MempoolError::NegativeFee)?;
+    let size = tx.size();
+    if fee_rate(fee, size) < config.min_relay_fee_rate {
+        return Err(MempoolError::FeeTooLow {
+            fee_rate: fee_rate(fee, size),
+            min_fee_rate: config.min_relay_fee_rate,
+        });
+    }
+
+    let mut evicted = vec![];
+    if !conflicts.is_empty() {
+        evicted = check_replacement(database, &pending, &tx, fee, &conflicts, config)?;
+        for hash in evicted.iter() {
+            database.remove_pending_transaction(hash);
+        }
+        log::info!(
+            "Transaction {} replaced {} pending transactions.",
+            hex::encode(tx_hash),
+            evicted.len()
+        );
+    }
+
+    database.add_pending_transaction(tx);
+    Ok(evicted)
+}
+
+fn check_replacement(
+    database: &DatabaseType,
+    pending: &[Transaction],
+    tx: &Transaction,
+    fee: Satoshi,
+    conflicts: &[&Transaction],
+    config: &MempoolConfig,
+) -> Result<Vec<HashResult>, MempoolError> {
+    if conflicts.iter().any(|x| !x.signals_replacement()) {
+        return Err(MempoolError::Conflict);
+    }
+
+    let conflict_hashes: Vec<HashResult> = conflicts.iter().map(|x| x.hash()).collect();
+    let evicted = with_descendants(pending, &conflict_hashes);
+    if evicted.len() > config.max_replacements {
+        return Err(MempoolError::ReplacementRejected(format!(
+            "would evict {} transactions, at most {} allowed",
+            evicted.len(),
+            config.max_replacements
+        )));
+    }
+    if tx
+        .inputs
+        .iter()
+        .any(|input| evicted.contains(&input.utxo_tx_hash))
+    {
+        return Err(MempoolError::ReplacementRejected(
+            "spends an output of a replaced transaction".to_string(),
+        ));
+    }
+
+    let size = tx.size();
+    for conflict in conflicts {
+        let conflict_fee = transaction_fee(database, pending, conflict).unwrap_or(0);
+        if fee_rate(fee, size) <= fee_rate(conflict_fee, conflict.size()) {
+            return Err(MempoolError::ReplacementRejected(format!(
+                "fee rate {} does not exceed fee rate {} of {}",
+                fee_rate(fee, size),
+                fee_rate(conflict_fee, conflict.size()),
+                hex::encode(conflict.hash())
+            )));
+        }
+    }
+
+    let evicted_fee: Satoshi = pending
+        .iter()
+        .filter(|x| evicted.contains(&x.hash()))
+        .map(|x| transaction_fee(database, pending, x).unwrap_or(0))
+        .sum();
+    if fee < evicted_fee {
+        return Err(MempoolError::ReplacementRejected(format!(
+            "fee {} is lower than the {} paid by the replaced transactions",
+            fee, evicted_fee
+        )));
+    }
+    let min_increase = calculate_fee(size, config.min_relay_fee_rate);
+    if fee - evicted_fee < min_increase {
+        return Err(MempoolError::ReplacementRejected(format!(
+            "fee must increase by at least {}",
+            min_increase
+        )));
+    }
+
+    Ok(evicted)
+}
+
+/// Collects the given transactions and all pending transactions spending their outputs.
+pub fn with_descendants(pending: &[Transaction], tx_hashes: &[HashResult]) -> Vec<HashResult> {
+    let mut result: Vec<HashResult> = tx_hashes.to_vec();
+    let mut seen: HashSet<HashResult> = tx_hashes.iter().cloned().collect();
+    let mut index = 0;
+
+    while index < result.len() {
+        let parent = result[index];
+        for tx in pending {
+            if tx.inputs.iter().any(|x| x.utxo_tx_hash == parent) {
+                let hash = tx.hash();
+                if seen.insert(hash) {
+                    result.push(hash);
+                }
+            }
+        }
+        index += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::models::{AccountConfig, MempoolConfig},
+        crypto::account::Account,
+        database::{database::Database, InMemoryDatabase},
+        mempool::acceptance::{accept_transaction, MempoolError},
+        transaction::{builder::TransactionBuilder, script::Item, Transaction},
+    };
+
+    fn setup() -> (InMemoryDatabase, Account, Transaction) {
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let mut database = InMemoryDatabase::new();
+        // a confirmed output which only the account can spend
+        let funding = TransactionBuilder::new(&account)
+            .add_input([9u8; 32], 0, 10_000)
+            .add_output(account.public_key(), 10_000)
+            .build()
+            .unwrap();
+        database.add_transaction(funding.hash(), funding.clone());
+        database.add_utxo(funding.hash(), 0);
+        (database, account, funding)
+    }
+
+    fn config() -> MempoolConfig {
+        MempoolConfig {
+            min_relay_fee_rate: 1000,
+            max_replacements: 100,
+            fee_estimation_blocks: 100,
+            persist_path: String::new(),
+        }
+    }
+
+    fn spend(account: &Account, funding: &Transaction, fee: u64, replaceable: bool) -> Transaction {
+        TransactionBuilder::new(account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 5_000)
+            .fee(fee)
+            .replaceable(replaceable)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_accept_and_reject_double_spend() {
+        let (mut database, account, funding) = setup();
+        let original = spend(&account, &funding, 500, false);
+        assert_eq!(
+            accept_transaction(&mut database, original, &config()),
+            Ok(vec![])
+        );
+
+        let replacement = spend(&account, &funding, 1000, false);
+        assert_eq!(
+            accept_transaction(&mut database, replacement, &config()),
+            Err(MempoolError::Conflict)
+        );
+    }
+
+    #[test]
+    fn test_fee_too_low() {
+        let (mut database, account, funding) = setup();
+        let tx = spend(&account, &funding, 0, false);
+        assert!(matches!(
+            accept_transaction(&mut database, tx, &config()),
+            Err(MempoolError::FeeTooLow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let (mut database, account, funding) = setup();
+        let original = spend(&account, &funding, 500, true);
+        let original_hash = original.hash();
+        accept_transaction(&mut database, original, &config()).unwrap();
+
+        // a child spending the original is evicted as well
+        let child = TransactionBuilder::new(&account)
+            .add_input(original_hash, 1, 4_500)
+            .add_output(&[8u8; 32], 4_000)
+            .fee(500)
+            .build()
+            .unwrap();
+        accept_transaction(&mut database, child, &config()).unwrap();
+
+        // must pay for the evicted child too
+        let insufficient = spend(&account, &funding, 900, true);
+        assert!(matches!(
+            accept_transaction(&mut database, insufficient, &config()),
+            Err(MempoolError::ReplacementRejected(_))
+        ));
+
+        let replacement = spend(&account, &funding, 2000, true);
+        let evicted = accept_transaction(&mut database, replacement, &config()).unwrap();
+        assert_eq!(evicted.len(), 2);
+        assert_eq!(evicted[0], original_hash);
+        assert_eq!(database.get_pending_transactions().len(), 1);
+    }
+
+    #[test]
+    fn test_unsigned_replacement() {
+        let (mut database, account, funding) = setup();
+        let original = spend(&account, &funding, 500, true);
+        let original_hash = original.hash();
+        accept_transaction(&mut database, original, &config()).unwrap();
+
+        // signed by a key not owning the spent output
+        let attacker = Account::new(AccountConfig {
+            keys_path: String::new(),
+        })
+        .unwrap();
+        let forged = spend(&attacker, &funding, 2000, true);
+        assert_eq!(
+            accept_transaction(&mut database, forged, &config()),
+            Err(MempoolError::InvalidScript)
+        );
+
+        let mut unsigned = spend(&account, &funding, 2000, true);
+        for input in unsigned.inputs.iter_mut() {
+            input.script_sig.items[0] = Item::Data(vec![], Some("sig".to_string()));
+        }
+        assert_eq!(
+            accept_transaction(&mut database, unsigned, &config()),
+            Err(MempoolError::InvalidScript)
+        );
+
+        let pending = database.get_pending_transactions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].hash(), original_hash);
+    }
+
+    #[test]
+    fn test_duplicate_inputs() {
+        let (mut database, account, funding) = setup();
+        let tx = TransactionBuilder::new(&account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 15_000)
+            .fee(500)
+            .build()
+            .unwrap();
+        assert_eq!(
+            accept_transaction(&mut database, tx, &config()),
+            Err(MempoolError::DuplicateInputs)
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    block::Block, crypto::hash_utils::HashResult, database::database::DatabaseType, types::Satoshi,
+};
+
+use super::fee_utils::{fee_rate, transaction_fee};
+
+/// Share of the sampled transactions, in percent, which must have been
+/// confirmed within the target for a fee rate to be recommended.
+const SUCCESS_PERCENTAGE: usize = 85;
+/// Minimum number of sampled transactions needed for an estimate.
+const MIN_SAMPLES: usize = 3;
+
+/// Estimates the fee rate needed for a transaction to be confirmed within a
+/// number of blocks.
+///
+/// The estimator remembers the block height at which pending transactions
+/// arrived. Once they are confirmed, their fee rate is sampled along with the
+/// number of blocks they waited. Only the samples of the most recent
+/// `history_blocks` blocks are kept.
+pub struct FeeEstimator {
+    history_blocks: usize,
+    max_block_size: usize,
+    /// Block height at which each pending transaction was first seen.
+    entry_heights: HashMap<HashResult, usize>,
+    /// Fee rate and number of blocks waited of the confirmed transactions, per block.
+    samples: VecDeque<Vec<(Satoshi, usize)>>,
+}
+
+impl FeeEstimator {
+    pub fn new(history_blocks: usize, max_block_size: usize) -> Self {
+        Self {
+            history_blocks,
+            max_block_size,
+            entry_heights: HashMap::new(),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Remembers that a transaction entered the pending transactions at the given block height.
+    pub fn track_transaction(&mut self, tx_hash: HashResult, block_height: usize) {
+        self.entry_heights.entry(tx_hash).or_insert(block_height);
+    }
+
+    /// Forgets a pending transaction which was replaced or dropped.
+    pub fn untrack_transaction(&mut self, tx_hash: &HashResult) {
+        self.entry_heights.remove(tx_hash);
+    }
+
+    /// Samples the tracked transactions confirmed by a block, which was inserted at the given height.
+    pub fn process_block(&mut self, database: &DatabaseType, block: &Block, block_height: usize) {
+        let mut samples = vec![];
+        for tx in block.transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let Some(entry_height) = self.entry_heights.remove(&tx.hash()) else {
+                continue;
+            };
+            if let Some(fee) = transaction_fee(database, &[], tx) {
+                let waited = block_height.saturating_sub(entry_height).max(1);
+                samples.push((fee_rate(fee, tx.size()), waited));
+            }
+        }
+
+        self.samples.push_back(samples);
+        while self.samples.len() > self.history_blocks {
+            self.samples.pop_front();
+        }
+        // transactions waiting longer than the history are unlikely to be confirmed
+        self.entry_heights
+            .retain(|_, entry_height| *entry_height + self.history_blocks >= block_height);
+    }
+
+    /// Estimates the fee rate in satoshis per kilobyte for a transaction to be
+    /// confirmed within `target_blocks` blocks.
+    ///
+    /// This is the lowest sampled fee rate for which at least 85% of the
+    /// transactions paying as much were confirmed in time. It is raised if the
+    /// pending transactions paying more would fill the target blocks by
+    /// themselves. Returns `None` if there are not enough samples.
+    pub fn estimate_fee(&self, database: &DatabaseType, target_blocks: usize) -> Option<Satoshi> {
+        let mut samples: Vec<(Satoshi, usize)> = self.samples.iter().flatten().cloned().collect();
+        samples.sort_by_key(|x| x.0);
+
+        // walk from the highest fee rate down, counting samples paying at least the current rate
+        let mut estimate = None;
+        let mut total = 0;
+        let mut confirmed_in_time = 0;
+        for (rate, waited) in samples.iter().rev() {
+            total += 1;
+            if *waited <= target_blocks {
+                confirmed_in_time += 1;
+            }
+            if total >= MIN_SAMPLES && confirmed_in_time * 100 >= total * SUCCESS_PERCENTAGE {
+                estimate = Some(*rate);
+            }
+        }
+
+        estimate.map(|rate| rate.max(self.pending_fee_rate(database, target_blocks)))
+    }
+
+    /// Fee rate needed to outbid the pending transactions which would fill the target blocks.
+    fn pending_fee_rate(&self, database: &DatabaseType, target_blocks: usize) -> Satoshi {
+        let pending = database.get_pending_transactions();
+        let mut rates: Vec<(Satoshi, usize)> = pending
+            .iter()
+            .filter_map(|tx| {
+                transaction_fee(database, pending, tx)
+                    .map(|fee| (fee_rate(fee, tx.size()), tx.size()))
+            })
+            .collect();
+        rates.sort_by_key(|x| std::cmp::Reverse(x.0));
+
+        let capacity = self.max_block_size.saturating_mul(target_blocks);
+        let mut size = 0;
+        for (rate, tx_size) in rates {
+            size += tx_size;
+            if size >= capacity {
+                return rate + 1;
+            }
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block::Block,
+        config::models::AccountConfig,
+        crypto::account::Account,
+        database::{database::Database, InMemoryDatabase},
+        mempool::{fee_estimator::FeeEstimator, fee_utils::fee_rate},
+        transaction::{builder::TransactionBuilder, Transaction},
+        types::Satoshi,
+    };
+
+    fn spend(account: &Account, database: &mut InMemoryDatabase, fee: Satoshi) -> Transaction {
+        let funding = Transaction::create_coinbase(10_000, account.public_key().to_vec());
+        database.add_transaction(funding.hash(), funding.clone());
+        database.add_utxo(funding.hash(), 0);
+        TransactionBuilder::new(account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 5_000)
+            .fee(fee)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_estimate_fee() {
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let mut database = InMemoryDatabase::new();
+        let mut estimator = FeeEstimator::new(10, 1_000_000);
+
+        // high fee transactions are confirmed in the next block, low fee ones wait three blocks
+        let fast: Vec<Transaction> = (1..=3)
+            .map(|i| spend(&account, &mut database, 1_000 * i))
+            .collect();
+        let slow: Vec<Transaction> = (1..=3)
+            .map(|i| spend(&account, &mut database, 10 * i))
+            .collect();
+        for tx in fast.iter().chain(slow.iter()) {
+            estimator.track_transaction(tx.hash(), 1);
+        }
+        assert_eq!(estimator.estimate_fee(&database, 1), None);
+
+        let block = |transactions: &[Transaction]| Block {
+            transactions: transactions.to_vec(),
+            ..Block::default()
+        };
+        estimator.process_block(&database, &block(&fast), 2);
+        estimator.process_block(&database, &block(&[]), 3);
+        estimator.process_block(&database, &block(&slow), 4);
+
+        let lowest_fast_rate = fee_rate(1_000, fast[0].size());
+        let lowest_rate = fee_rate(10, slow[0].size());
+        assert_eq!(estimator.estimate_fee(&database, 1), Some(lowest_fast_rate));
+        assert_eq!(estimator.estimate_fee(&database, 3), Some(lowest_rate));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    database::database::DatabaseType,
+    transaction::{input::Input, Transaction},
+    types::Satoshi,
+};
+
+/// Calculates the fee of a transaction given its size and a fee rate in satoshis per kilobyte.
+/// The result is rounded up, so that any non-zero fee rate results in a non-zero fee.
+pub fn calculate_fee(size: usize, fee_rate: Satoshi) -> Satoshi {
+    (size as Satoshi * fee_rate).div_ceil(1000)
+}
+
+/// Calculates the fee rate in satoshis per kilobyte.
+pub fn fee_rate(fee: Satoshi, size: usize) -> Satoshi {
+    fee * 1000 / size.max(1) as Satoshi
+}
+
+/// Looks up the value of the output spent by an input. The output is searched
+/// for in the chain first and then in the given unconfirmed transactions.
+pub fn input_value(
+    database: &DatabaseType,
+    unconfirmed_transactions: &[Transaction],
+    input: &Input,
+) -> Option<Satoshi> {
+    if let Some(tx) = database.get_transaction(&input.utxo_tx_hash) {
+        return tx.get_amount(input.utxo_output_index);
+    }
+    unconfirmed_transactions
+        .iter()
+        .find(|tx| tx.hash() == input.utxo_tx_hash)
+        .and_then(|tx| tx.get_amount(input.utxo_output_index))
+}
+
+/// Calculates the fee paid by a transaction, i.e. the difference between its
+/// inputs and outputs. Returns `None` if an input cannot be found or the
+/// outputs exceed the inputs.
+pub fn transaction_fee(
+    database: &DatabaseType,
+    unconfirmed_transactions: &[Transaction],
+    tx: &Transaction,
+) -> Option<Satoshi> {
+    let total_input = tx
+        .inputs
+        .iter()
+        .map(|input| input_value(database, unconfirmed_transactions, input))
+        .sum::<Option<Satoshi>>()?;
+    let total_output: Satoshi = tx.outputs.iter().map(|output| output.value).sum();
+    total_input.checked_sub(total_output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_fee, fee_rate};
+
+    #[test]
+    fn test_calculate_fee() {
+        assert_eq!(calculate_fee(250, 0), 0);
+        assert_eq!(calculate_fee(250, 1), 1);
+        assert_eq!(calculate_fee(1000, 3), 3);
+        assert_eq!(calculate_fee(1001, 1000), 1001);
+    }
+
+    #[test]
+    fn test_fee_rate() {
+        assert_eq!(fee_rate(250, 250), 1000);
+        assert_eq!(fee_rate(1, 2000), 0);
+        assert_eq!(fee_rate(5, 0), 5000);
+    }
+}
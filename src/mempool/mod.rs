@@ -0,0 +1,5 @@
+pub mod acceptance;
+pub mod fee_estimator;
+pub mod fee_utils;
+pub mod persistence;
+pub mod selection;
@@ -0,0 +1,53 @@
+use std::fs;
+
+use crate::transaction::Transaction;
+
+/// Saves the pending transactions in the order they were accepted, so that
+/// parents are loaded before the transactions spending them. The file is
+/// replaced at once, an interrupted save keeps the previous one.
+pub fn save(path: &str, transactions: &[Transaction]) -> Result<(), String> {
+    let json = serde_json::to_string(transactions)
+        .map_err(|err| format!("Failed to encode pending transactions: {}", err))?;
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, json)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|err| format!("Failed to write pending transactions to {}: {}", path, err))
+}
+
+/// Loads the pending transactions saved at the path. A missing file yields
+/// no transactions.
+pub fn load(path: &str) -> Result<Vec<Transaction>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(format!("Failed to read {}: {}", path, err)),
+    };
+    serde_json::from_str(&contents).map_err(|err| format!("Invalid mempool file {}: {}", path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save};
+    use crate::transaction::Transaction;
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("mempool_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert!(load(path).unwrap().is_empty());
+
+        let transactions = vec![
+            Transaction::create_coinbase(100, vec![1u8; 32]),
+            Transaction::create_coinbase(200, vec![2u8; 32]),
+        ];
+        save(path, &transactions).unwrap();
+        let loaded = load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let hashes: Vec<_> = loaded.iter().map(|tx| tx.hash()).collect();
+        assert_eq!(
+            hashes,
+            transactions.iter().map(|tx| tx.hash()).collect::<Vec<_>>()
+        );
+    }
+}
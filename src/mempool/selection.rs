@@ -0,0 +1,190 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    crypto::hash_utils::HashResult, database::database::DatabaseType, transaction::Transaction,
+    types::Satoshi,
+};
+
+use super::fee_utils::{fee_rate, transaction_fee};
+
+struct Entry {
+    tx: Transaction,
+    fee: Satoshi,
+    size: usize,
+    /// Pending transactions whose outputs this transaction spends.
+    parents: Vec<HashResult>,
+}
+
+/// Selects pending transactions for a new block, up to `max_size` bytes.
+///
+/// Transactions are ranked by the fee rate of their package, i.e. the
+/// transaction together with its not yet selected pending ancestors, so that
+/// a child paying a high fee pulls in its low-fee parent (child-pays-for-parent).
+/// Parents are always placed before their children.
+///
+/// Returns the selected transactions and the sum of their fees.
+pub fn select_transactions(
+    database: &DatabaseType,
+    max_size: usize,
+) -> (Vec<Transaction>, Satoshi) {
+    let pending = database.get_pending_transactions();
+    let hashes: Vec<HashResult> = pending.iter().map(|tx| tx.hash()).collect();
+
+    let mut entries: HashMap<HashResult, Entry> = HashMap::new();
+    for (tx, hash) in pending.iter().zip(hashes.iter()) {
+        if let Some(fee) = transaction_fee(database, pending, tx) {
+            let parents = tx
+                .inputs
+                .iter()
+                .map(|input| input.utxo_tx_hash)
+                .filter(|parent| hashes.contains(parent))
+                .collect();
+            entries.insert(
+                *hash,
+                Entry {
+                    tx: tx.clone(),
+                    fee,
+                    size: tx.size(),
+                    parents,
+                },
+            );
+        }
+    }
+
+    let mut selected: Vec<HashResult> = vec![];
+    let mut selected_set: HashSet<HashResult> = HashSet::new();
+    let mut block_size = 0;
+    let mut fees = 0;
+
+    loop {
+        let mut best: Option<(Satoshi, HashResult, Vec<HashResult>)> = None;
+
+        for hash in hashes.iter().filter(|x| !selected_set.contains(*x)) {
+            let Some(package) = package(&entries, &selected_set, hash) else {
+                continue;
+            };
+            let package_fee: Satoshi = package.iter().map(|x| entries[x].fee).sum();
+            let package_size: usize = package.iter().map(|x| entries[x].size).sum();
+            if block_size + package_size > max_size {
+                continue;
+            }
+
+            let rate = fee_rate(package_fee, package_size);
+            if best
+                .as_ref()
+                .is_none_or(|(best_rate, _, _)| rate > *best_rate)
+            {
+                best = Some((rate, *hash, package));
+            }
+        }
+
+        let Some((_, _, package)) = best else {
+            break;
+        };
+        for hash in package {
+            let entry = &entries[&hash];
+            block_size += entry.size;
+            fees += entry.fee;
+            selected_set.insert(hash);
+            selected.push(hash);
+        }
+    }
+
+    let txs = selected
+        .into_iter()
+        .map(|hash| entries.remove(&hash).unwrap().tx)
+        .collect();
+    (txs, fees)
+}
+
+/// Collects the transaction and its unselected ancestors, parents first.
+/// Returns `None` if an ancestor is invalid (e.g. missing inputs).
+fn package(
+    entries: &HashMap<HashResult, Entry>,
+    selected: &HashSet<HashResult>,
+    hash: &HashResult,
+) -> Option<Vec<HashResult>> {
+    let mut package = vec![];
+    let mut visited = HashSet::new();
+    visit(entries, selected, hash, &mut visited, &mut package)?;
+    Some(package)
+}
+
+fn visit(
+    entries: &HashMap<HashResult, Entry>,
+    selected: &HashSet<HashResult>,
+    hash: &HashResult,
+    visited: &mut HashSet<HashResult>,
+    package: &mut Vec<HashResult>,
+) -> Option<()> {
+    if selected.contains(hash) || !visited.insert(*hash) {
+        return Some(());
+    }
+    let entry = entries.get(hash)?;
+    for parent in entry.parents.iter() {
+        visit(entries, selected, parent, visited, package)?;
+    }
+    package.push(*hash);
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::models::AccountConfig,
+        crypto::account::Account,
+        database::{database::Database, InMemoryDatabase},
+        mempool::selection::select_transactions,
+        transaction::{builder::TransactionBuilder, Transaction},
+    };
+
+    #[test]
+    fn test_child_pays_for_parent() {
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let mut database = InMemoryDatabase::new();
+        let funding_a = Transaction::create_coinbase(10_000, account.public_key().to_vec());
+        let funding_b = Transaction::create_coinbase(10_000, account.public_key().to_vec());
+        for funding in [&funding_a, &funding_b] {
+            database.add_transaction(funding.hash(), funding.clone());
+            database.add_utxo(funding.hash(), 0);
+        }
+
+        let parent = TransactionBuilder::new(&account)
+            .add_input(funding_a.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 9_999)
+            .fee(1)
+            .build()
+            .unwrap();
+        let child = TransactionBuilder::new(&account)
+            .add_input(parent.hash(), 0, 9_999)
+            .add_output(&[8u8; 32], 8_999)
+            .fee(1_000)
+            .build()
+            .unwrap();
+        let other = TransactionBuilder::new(&account)
+            .add_input(funding_b.hash(), 0, 10_000)
+            .add_output(&[9u8; 32], 9_900)
+            .fee(100)
+            .build()
+            .unwrap();
+
+        database.add_pending_transaction(child.clone());
+        database.add_pending_transaction(other.clone());
+        database.add_pending_transaction(parent.clone());
+
+        // room for two transactions only: the parent and child package wins
+        let max_size = parent.size() + child.size();
+        let (txs, fees) = select_transactions(&database, max_size);
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].hash(), parent.hash());
+        assert_eq!(txs[1].hash(), child.hash());
+        assert_eq!(fees, 1_001);
+
+        let (txs, fees) = select_transactions(&database, usize::MAX);
+        assert_eq!(txs.len(), 3);
+        assert_eq!(fees, 1_101);
+    }
+}
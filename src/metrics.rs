@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::http::Request as HttpRequest;
+use tower::{Layer, Service};
+
+use crate::proto::proto_node::ChainInfo;
+
+/// Buckets of the block interval in seconds.
+const BLOCK_INTERVAL_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0];
+/// Buckets of the number of blocks disconnected by a reorganization.
+const REORG_DEPTH_BUCKETS: &[f64] = &[1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+/// Buckets of the RPC latency in seconds.
+const RPC_LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0];
+
+/// Distribution of observed values, counted in buckets of upper bounds.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Number of observations per bucket, the last one counts values above all bounds.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Writes the cumulative buckets, sum and count in the text exposition format.
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter()) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}{}le=\"{}\"}} {}",
+                name, labels, separator, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+            name,
+            labels,
+            separator,
+            self.count()
+        );
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels)
+        };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, self.count());
+    }
+}
+
+#[derive(Debug)]
+struct Registry {
+    block_interval: Histogram,
+    reorg_depth: Histogram,
+    hash_rate: f64,
+    rpc_latency: BTreeMap<String, Histogram>,
+    validation_failures: BTreeMap<(&'static str, &'static str), u64>,
+}
+
+/// Metrics of the node exported to Prometheus. The values of the chain and
+/// the node are taken from the chain info when the metrics are rendered.
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+/// Metrics shared by all components of the node, like the logger.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            registry: Mutex::new(Registry {
+                block_interval: Histogram::new(BLOCK_INTERVAL_BUCKETS),
+                reorg_depth: Histogram::new(REORG_DEPTH_BUCKETS),
+                hash_rate: 0.0,
+                rpc_latency: BTreeMap::new(),
+                validation_failures: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Records the time between a connected block and its parent.
+    pub fn block_connected(&self, interval_secs: u64) {
+        let mut registry = self.registry.lock().unwrap();
+        registry.block_interval.observe(interval_secs as f64);
+    }
+
+    pub fn reorganized(&self, depth: usize) {
+        let mut registry = self.registry.lock().unwrap();
+        registry.reorg_depth.observe(depth as f64);
+    }
+
+    pub fn set_hash_rate(&self, hash_rate: f64) {
+        self.registry.lock().unwrap().hash_rate = hash_rate;
+    }
+
+    pub fn rpc_completed(&self, method: &str, latency_secs: f64) {
+        let mut registry = self.registry.lock().unwrap();
+        registry
+            .rpc_latency
+            .entry(method.to_string())
+            .or_insert_with(|| Histogram::new(RPC_LATENCY_BUCKETS))
+            .observe(latency_secs);
+    }
+
+    /// Counts a block or transaction rejected for the reason.
+    pub fn validation_failed(&self, kind: &'static str, reason: &'static str) {
+        let mut registry = self.registry.lock().unwrap();
+        *registry
+            .validation_failures
+            .entry((kind, reason))
+            .or_default() += 1;
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self, info: &ChainInfo) -> String {
+        let mut out = String::new();
+        let gauges = [
+            (
+                "block_height",
+                "Number of blocks in the chain.",
+                info.block_height as f64,
+            ),
+            (
+                "difficulty",
+                "Current difficulty bit.",
+                info.difficulty as f64,
+            ),
+            (
+                "mempool_transactions",
+                "Number of pending transactions.",
+                info.mempool_size as f64,
+            ),
+            (
+                "mempool_bytes",
+                "Size of the pending transactions.",
+                info.mempool_bytes as f64,
+            ),
+            (
+                "peers",
+                "Number of connected peers.",
+                info.peer_count as f64,
+            ),
+            (
+                "sync_progress",
+                "Block height relative to the best peer.",
+                info.sync_progress,
+            ),
+            (
+                "uptime_seconds",
+                "Time since the node started.",
+                info.uptime_secs as f64,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            write_header(&mut out, name, help, "gauge");
+            let _ = writeln!(out, "ember_{} {}", name, value);
+        }
+
+        let registry = self.registry.lock().unwrap();
+        write_header(
+            &mut out,
+            "hash_rate",
+            "Hashes per second of the built-in miner.",
+            "gauge",
+        );
+        let _ = writeln!(out, "ember_hash_rate {}", registry.hash_rate);
+
+        write_header(
+            &mut out,
+            "block_interval_seconds",
+            "Time between connected blocks.",
+            "histogram",
+        );
+        registry
+            .block_interval
+            .write(&mut out, "ember_block_interval_seconds", "");
+        write_header(
+            &mut out,
+            "reorg_depth",
+            "Blocks disconnected by reorganizations.",
+            "histogram",
+        );
+        registry
+            .reorg_depth
+            .write(&mut out, "ember_reorg_depth", "");
+
+        write_header(
+            &mut out,
+            "rpc_latency_seconds",
+            "Time to answer RPCs.",
+            "histogram",
+        );
+        for (method, histogram) in registry.rpc_latency.iter() {
+            let labels = format!("method=\"{}\"", method);
+            histogram.write(&mut out, "ember_rpc_latency_seconds", &labels);
+        }
+
+        write_header(
+            &mut out,
+            "validation_failures_total",
+            "Rejected blocks and transactions.",
+            "counter",
+        );
+        for ((kind, reason), count) in registry.validation_failures.iter() {
+            let _ = writeln!(
+                out,
+                "ember_validation_failures_total{{kind=\"{}\",reason=\"{}\"}} {}",
+                kind, reason, count
+            );
+        }
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP ember_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE ember_{} {}", name, kind);
+}
+
+/// Records the latency of the requests to a gRPC service by method.
+#[derive(Debug, Clone, Default)]
+pub struct RpcMetricsLayer;
+
+impl<S> Layer<S> for RpcMetricsLayer {
+    type Service = RpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcMetricsService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcMetricsService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<HttpRequest<B>> for RpcMetricsService<S>
+where
+    S: Service<HttpRequest<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: HttpRequest<B>) -> Self::Future {
+        // the path consists of the package, service and method, e.g. /proto_node.Rpc/GetUTXO
+        let method = request
+            .uri()
+            .path()
+            .trim_start_matches("/proto_node.")
+            .to_string();
+        let start = Instant::now();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await;
+            metrics().rpc_completed(&method, start.elapsed().as_secs_f64());
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Histogram, Metrics};
+    use crate::proto::proto_node::ChainInfo;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::new(&[1.0, 10.0]);
+        for value in [0.5, 1.0, 5.0, 50.0] {
+            histogram.observe(value);
+        }
+        let mut out = String::new();
+        histogram.write(&mut out, "interval", "");
+        assert_eq!(
+            out,
+            "interval_bucket{le=\"1\"} 2\n\
+             interval_bucket{le=\"10\"} 3\n\
+             interval_bucket{le=\"+Inf\"} 4\n\
+             interval_sum 56.5\n\
+             interval_count 4\n"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.validation_failed("block", "invalid_pow");
+        metrics.validation_failed("block", "invalid_pow");
+        metrics.rpc_completed("Rpc/GetUTXO", 0.002);
+        let info = ChainInfo {
+            block_height: 7,
+            ..Default::default()
+        };
+        let out = metrics.render(&info);
+        assert!(out.contains("ember_block_height 7\n"));
+        assert!(out.contains(
+            "ember_validation_failures_total{kind=\"block\",reason=\"invalid_pow\"} 2\n"
+        ));
+        assert!(out
+            .contains("ember_rpc_latency_seconds_bucket{method=\"Rpc/GetUTXO\",le=\"0.005\"} 1\n"));
+    }
+}
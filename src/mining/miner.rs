@@ -1,54 +1,132 @@
 use std::{
     collections::VecDeque,
-    sync::{Arc, Mutex},
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use crossbeam::channel::Receiver;
 
 use crate::{
-    block::{Block, BlockHeader},
-    config::models::MiningConfig,
-    crypto::{account::Account, hash_utils::HashResult, merkle_tree::generate_merkle_root},
+    block::Block,
+    config::{chain_params::ChainParams, models::MiningConfig},
+    crypto::account::Account,
     database::database::DatabaseType,
-    mining::pow_utils::proof_of_work,
+    metrics::metrics,
+    mining::{
+        pow_utils::{proof_of_work, worker_threads, PowInterrupts, PowResult},
+        template::BlockTemplate,
+    },
     transaction::Transaction,
     types::Satoshi,
 };
 
 pub struct Miner {
     config: MiningConfig,
-    difficulty: u8,
-    last_hash_count: u32,
+    chain_params: ChainParams,
+    /// Shared with the network, which hands out block templates to external miners.
+    difficulty: Arc<AtomicU8>,
+    /// Number of worker threads searching for a nonce.
+    threads: usize,
+    last_hash_count: u64,
     account: Arc<Account>,
     last_mining_times: VecDeque<f64>,
 }
 
 impl Miner {
-    pub fn new(config: MiningConfig, account: Arc<Account>) -> Self {
+    pub fn new(
+        config: MiningConfig,
+        chain_params: ChainParams,
+        account: Arc<Account>,
+        difficulty: Arc<AtomicU8>,
+    ) -> Self {
         Self {
-            difficulty: config.start_difficulty_bit,
+            difficulty,
+            threads: worker_threads(config.threads),
             last_hash_count: 0,
-            last_mining_times: VecDeque::with_capacity(config.block_adjustment_interval),
+            last_mining_times: VecDeque::with_capacity(chain_params.block_adjustment_interval),
             account,
             config,
+            chain_params,
         }
     }
 
+    /// Mines a block on top of the current head until it is found or
+    /// mining is cancelled. The block template (pending transactions and
+    /// timestamp) is rebuilt whenever new transactions arrive and at least
+    /// every `template_refresh_secs`.
     pub fn mine(
         &self,
         database: &Arc<Mutex<DatabaseType>>,
         cancel_mine_rx: Receiver<()>,
-        prev_block_hash: HashResult,
+        template_update_rx: &Receiver<()>,
         reward: Satoshi,
-        fake_mining: bool,
-    ) -> (Option<Block>, u32) {
-        let pending_txs = database.lock().unwrap().get_pending_transactions().to_vec();
-        database.lock().unwrap().clear_pending_transactions();
+    ) -> (Option<Block>, u64) {
+        let mut hash_count = 0u64;
+        let mut extra_nonce = 0u64;
+
+        loop {
+            // transactions announced so far are part of the new template
+            while template_update_rx.try_recv().is_ok() {}
+
+            // pending transactions are removed once the block is inserted
+            let template = BlockTemplate::new(
+                &*database.lock().unwrap(),
+                self.difficulty(),
+                reward,
+                self.config.max_block_size,
+            );
+            let mut block = template.assemble(self.coinbase_transactions(&template, extra_nonce));
+
+            let interrupts = PowInterrupts {
+                cancel_rx: &cancel_mine_rx,
+                template_update_rx,
+                refresh_at: Instant::now() + Duration::from_secs(self.config.template_refresh_secs),
+            };
+            let result = proof_of_work(
+                block.header.difficulty,
+                &mut block.header,
+                &interrupts,
+                &mut hash_count,
+                self.threads,
+            );
+
+            match result {
+                PowResult::Found(block_hash) => {
+                    block.hash = block_hash;
+                    return (Some(block), hash_count);
+                }
+                PowResult::Cancelled => return (None, hash_count),
+                PowResult::Exhausted => {
+                    log::debug!("Nonces exhausted, rolling extra nonce {}.", extra_nonce + 1);
+                }
+                PowResult::Stale => {
+                    log::debug!(
+                        "Refreshing block template with {} transactions.",
+                        template.transactions.len()
+                    );
+                }
+            }
+            // a new extra nonce keeps the next template from repeating work
+            extra_nonce += 1;
+        }
+    }
 
-        let coinbase = Transaction::create_coinbase(reward, self.account.public_key().to_vec());
+    /// Creates the coinbase for the given extra nonce and a transaction spending it.
+    pub fn coinbase_transactions(
+        &self,
+        template: &BlockTemplate,
+        extra_nonce: u64,
+    ) -> Vec<Transaction> {
+        let coinbase_amount = template.coinbase_value();
+        let coinbase = Transaction::create_block_coinbase(
+            &[(self.account.public_key().to_vec(), coinbase_amount)],
+            template.height,
+            extra_nonce,
+        );
         let coinbase_hash = coinbase.hash();
-        let coinbase_amount = coinbase.get_amount(0).unwrap_or(0);
         let tx_coinbase_spend = Transaction::create_pay_to_pub_key_hash(
             vec![(coinbase_hash, 0, coinbase_amount)],
             coinbase_amount,
@@ -58,75 +136,62 @@ impl Miner {
         )
         .expect("Failed to create spend the coinbase transaction!");
 
-        let mut txs = vec![coinbase, tx_coinbase_spend];
-        txs.append(&mut pending_txs.to_vec());
-
-        let tx_hashes = txs.iter().map(|x| x.hash()).collect();
-        let merkle_root = generate_merkle_root(tx_hashes);
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let mut block_header = BlockHeader::from(
-            merkle_root,
-            prev_block_hash,
-            self.difficulty,
-            timestamp,
-            reward,
-        );
+        vec![coinbase, tx_coinbase_spend]
+    }
 
-        let mut hash_count = 0u32;
-        if let Some(block_hash) = proof_of_work(
-            self.difficulty,
-            &mut block_header,
-            cancel_mine_rx,
-            &mut hash_count,
-            fake_mining,
-        ) {
-            let block = Block::new(block_header, txs, block_hash);
-            return (Some(block), hash_count);
-        }
-        (None, hash_count)
+    pub fn difficulty(&self) -> u8 {
+        self.difficulty.load(Ordering::Relaxed)
     }
 
     pub fn adjust_difficulty(&mut self) {
         let avg_mining_time =
             self.last_mining_times.iter().sum::<f64>() / self.last_mining_times.len() as f64;
-        let previous_difficulty = self.difficulty;
-
-        if avg_mining_time < self.config.block_time_secs as f64 * 0.8 {
-            self.difficulty += 1;
-        } else if avg_mining_time > self.config.block_time_secs as f64 * 1.2 {
-            self.difficulty -= 1;
+        let previous_difficulty = self.difficulty();
+        let mut difficulty = previous_difficulty;
+
+        // difficulty 0 is invalid
+        if avg_mining_time < self.chain_params.block_time_secs as f64 * 0.8 {
+            difficulty = difficulty.saturating_add(1);
+        } else if avg_mining_time > self.chain_params.block_time_secs as f64 * 1.2 {
+            difficulty = difficulty.saturating_sub(1).max(1);
         }
 
-        if previous_difficulty != self.difficulty {
+        if previous_difficulty != difficulty {
+            self.difficulty.store(difficulty, Ordering::Relaxed);
             log::info!(
                 "Adjust difficulty from {} to {}.",
                 previous_difficulty,
-                self.difficulty
+                difficulty
             );
         }
 
         log::info!(
             "Average block time during last {} blocks was {} seconds.",
-            self.config.block_adjustment_interval,
+            self.chain_params.block_adjustment_interval,
             avg_mining_time
         );
     }
 
-    pub fn add_mining_time(&mut self, duration: Duration, hash_count: u32) {
+    pub fn add_mining_time(&mut self, duration: Duration, hash_count: u64) {
         self.last_hash_count = hash_count;
-        if self.last_mining_times.len() >= self.config.block_adjustment_interval {
+        if self.last_mining_times.len() >= self.chain_params.block_adjustment_interval {
             self.last_mining_times.pop_front();
         }
         self.last_mining_times.push_back(duration.as_secs_f64());
     }
+    /// Logs the hashes per second of the last block and exports them as metric.
     pub fn get_hash_count(&self, block_duration: Duration) {
-        let hash_per_secs =
-            (self.last_hash_count as f64 / block_duration.as_millis() as f64) * 1000.0;
-        log::debug!("Average hashes per second: {:.2}", hash_per_secs);
+        let secs = block_duration.as_secs_f64();
+        let hash_per_secs = if secs > 0.0 {
+            self.last_hash_count as f64 / secs
+        } else {
+            0.0
+        };
+        metrics().set_hash_rate(hash_per_secs);
+        log::debug!(
+            "Average hashes per second: {:.2} ({} threads)",
+            hash_per_secs,
+            self.threads
+        );
     }
 }
@@ -1,2 +1,3 @@
 pub mod miner;
 pub mod pow_utils;
+pub mod template;
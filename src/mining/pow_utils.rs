@@ -1,15 +1,25 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
 use std::time::{Duration, Instant};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crossbeam::channel::{Receiver, TryRecvError};
+use crossbeam::channel::{at, bounded, never, select, tick, Receiver};
 use ethnum::U256;
 use rand::prelude::*;
 use rand::Rng;
 
 use crate::{block::block_header::BlockHeader, crypto::hash_utils::HashResult};
 
-pub fn target_from_difficulty_bit(bit: u8) -> U256 {
-    U256::new(2).checked_pow(256 - bit as u32).unwrap()
+/// Largest hash meeting the difficulty. Difficulty 0 is invalid, its target
+/// 2^256 does not fit.
+pub fn target_from_difficulty_bit(bit: u8) -> Option<U256> {
+    U256::new(2).checked_pow(256 - bit as u32)
+}
+
+/// Expected number of hashes to solve a block of the difficulty.
+pub fn block_work(bit: u8) -> U256 {
+    U256::ONE << bit as u32
 }
 
 pub fn compare_difficulty(target: U256, hash_int: U256) -> bool {
@@ -19,6 +29,21 @@ pub fn compare_difficulty(target: U256, hash_int: U256) -> bool {
     false
 }
 
+/// Checks that the block hash belongs to the header and meets the header's difficulty.
+pub fn verify_proof_of_work(block_header: &BlockHeader, block_hash: &HashResult) -> bool {
+    *block_hash == block_header.finalize()
+        && target_from_difficulty_bit(block_header.difficulty)
+            .is_some_and(|target| compare_difficulty(target, U256::from_be_bytes(*block_hash)))
+}
+
+/// Resolves the configured number of mining threads, where 0 means all available cores.
+pub fn worker_threads(threads: usize) -> usize {
+    match threads {
+        0 => thread::available_parallelism().map_or(1, |x| x.get()),
+        threads => threads,
+    }
+}
+
 pub fn get_random_range(min: u64, max: u64) -> u64 {
     let mut seed = [0u8; 32];
     let now_since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
@@ -28,45 +53,246 @@ pub fn get_random_range(min: u64, max: u64) -> u64 {
     rng.gen_range(min..max)
 }
 
-/// Mines a block for a given difficulty.
+/// Outcome of searching the nonce space of a block header.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PowResult {
+    Found(HashResult),
+    /// All nonces were tried; the block must be changed (e.g. extra nonce) to continue.
+    Exhausted,
+    /// The block template should be rebuilt, e.g. because new transactions arrived.
+    Stale,
+    Cancelled,
+}
+
+/// Signals which stop the search for a nonce before it is found.
+pub struct PowInterrupts<'a> {
+    pub cancel_rx: &'a Receiver<()>,
+    /// Notifies about new pending transactions, which make the block template stale.
+    pub template_update_rx: &'a Receiver<()>,
+    /// Time at which the block template becomes stale regardless.
+    pub refresh_at: Instant,
+}
+
+/// Interval in which the aggregate hashrate is logged while mining.
+const HASHRATE_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// Number of hashes a worker computes before adding them to the shared hash count.
+const HASH_COUNT_BATCH: u64 = 1024;
+/// Minimum time a block template is mined before it is rebuilt for new transactions.
+const MIN_TEMPLATE_AGE: Duration = Duration::from_secs(1);
+
+/// Mines a block for a given difficulty, which may be lower than the header's
+/// difficulty when mining shares for a pool.
+///
+/// The nonce space is split into contiguous ranges, one per worker thread.
+/// On success the winning nonce is set on the block header.
 ///
 pub fn proof_of_work(
     difficulty: u8,
     block_header: &mut BlockHeader,
-    cancel_mine_rx: Receiver<()>,
-    hash_count: &mut u32,
-    fake_mining: bool,
-) -> Option<HashResult> {
-    let mut block_hash = block_header.finalize();
-    let target = target_from_difficulty_bit(difficulty);
+    interrupts: &PowInterrupts,
+    hash_count: &mut u64,
+    threads: usize,
+) -> PowResult {
+    let target = target_from_difficulty_bit(difficulty).unwrap_or(U256::MAX);
+    let threads = threads.max(1) as u64;
+    let stop = AtomicBool::new(false);
+    let hashes = AtomicU64::new(0);
+    let (found_tx, found_rx) = bounded::<Option<u32>>(threads as usize);
     let time_started = Instant::now();
-    let wait_secs = get_random_range(8, 12);
 
-    for i in 0..u32::MAX {
-        if !fake_mining {
-            let hash_int = U256::from_be_bytes(block_hash);
-            if compare_difficulty(target, hash_int) {
-                return Some(block_hash);
-            }
+    let result = thread::scope(|s| {
+        let nonce_space = u32::MAX as u64 + 1;
+        let chunk = nonce_space / threads;
+        for i in 0..threads {
+            let start = i * chunk;
+            let end = if i == threads - 1 {
+                nonce_space
+            } else {
+                start + chunk
+            };
+            let header = block_header.clone();
+            let found_tx = found_tx.clone();
+            let (stop, hashes) = (&stop, &hashes);
+            s.spawn(move || {
+                let nonce = search_nonces(header, target, start..end, stop, hashes);
+                let _ = found_tx.send(nonce);
+            });
+        }
+        drop(found_tx);
 
-            block_header.nonce = i;
-            block_hash = block_header.finalize();
-            *hash_count += 1;
-        } else {
-            std::thread::sleep(Duration::from_millis(1));
-            if time_started.elapsed().as_secs() >= wait_secs {
-                block_header.nonce = get_random_range(0, u32::MAX as u64) as u32;
-                block_hash = block_header.finalize();
-                return Some(block_hash);
+        let report = tick(HASHRATE_REPORT_INTERVAL);
+        let mut template_update_rx = interrupts.template_update_rx.clone();
+        let mut refresh_at = interrupts.refresh_at;
+        let mut finished = 0;
+        let result = loop {
+            select! {
+                recv(found_rx) -> nonce => match nonce {
+                    Ok(Some(nonce)) => {
+                        block_header.nonce = nonce;
+                        break PowResult::Found(block_header.finalize());
+                    }
+                    Ok(None) => {
+                        finished += 1;
+                        if finished == threads {
+                            break PowResult::Exhausted;
+                        }
+                    }
+                    Err(_) => break PowResult::Exhausted,
+                },
+                recv(interrupts.cancel_rx) -> _ => break PowResult::Cancelled,
+                recv(template_update_rx) -> update => match update {
+                    Ok(_) => refresh_at = refresh_at.min(time_started + MIN_TEMPLATE_AGE),
+                    Err(_) => template_update_rx = never(),
+                },
+                recv(at(refresh_at)) -> _ => break PowResult::Stale,
+                recv(report) -> _ => {
+                    log::debug!(
+                        "Mining at {:.2} hashes per second with {} threads.",
+                        hashes.load(Ordering::Relaxed) as f64
+                            / time_started.elapsed().as_secs_f64(),
+                        threads
+                    );
+                }
             }
+        };
+        stop.store(true, Ordering::Relaxed);
+        result
+    });
+
+    *hash_count += hashes.load(Ordering::Relaxed);
+    result
+}
+
+/// Hashes the header with each nonce of the range until the hash meets the
+/// target or another worker raises the stop flag.
+fn search_nonces(
+    mut header: BlockHeader,
+    target: U256,
+    nonces: Range<u64>,
+    stop: &AtomicBool,
+    hashes: &AtomicU64,
+) -> Option<u32> {
+    let mut count = 0;
+    for nonce in nonces {
+        if stop.load(Ordering::Relaxed) {
+            break;
         }
 
-        if i % 10000 == 0 {
-            match cancel_mine_rx.try_recv() {
-                Ok(_) | Err(TryRecvError::Disconnected) => return None,
-                Err(TryRecvError::Empty) => {}
-            }
+        header.nonce = nonce as u32;
+        let hash_int = U256::from_be_bytes(header.finalize());
+        count += 1;
+        if count == HASH_COUNT_BATCH {
+            hashes.fetch_add(count, Ordering::Relaxed);
+            count = 0;
+        }
+
+        if compare_difficulty(target, hash_int) {
+            stop.store(true, Ordering::Relaxed);
+            hashes.fetch_add(count, Ordering::Relaxed);
+            return Some(nonce as u32);
         }
     }
+    hashes.fetch_add(count, Ordering::Relaxed);
     None
 }
+
+/// Searches the nonces in order on the calling thread, so that the same header
+/// always yields the same nonce. Returns the hash and the number of hashes
+/// computed, or `None` if all nonces were tried.
+pub fn solve_sequential(
+    difficulty: u8,
+    block_header: &mut BlockHeader,
+) -> Option<(HashResult, u64)> {
+    let stop = AtomicBool::new(false);
+    let hashes = AtomicU64::new(0);
+    let nonce = search_nonces(
+        block_header.clone(),
+        target_from_difficulty_bit(difficulty).unwrap_or(U256::MAX),
+        0..u32::MAX as u64 + 1,
+        &stop,
+        &hashes,
+    )?;
+    block_header.nonce = nonce;
+    Some((block_header.finalize(), hashes.load(Ordering::Relaxed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crossbeam::channel::unbounded;
+
+    use crate::block::BlockHeader;
+
+    use super::{
+        proof_of_work, solve_sequential, target_from_difficulty_bit, verify_proof_of_work,
+        PowInterrupts, PowResult,
+    };
+
+    #[test]
+    fn test_proof_of_work_multiple_threads() {
+        let (_cancel_tx, cancel_rx) = unbounded();
+        let (_update_tx, update_rx) = unbounded();
+        let interrupts = PowInterrupts {
+            cancel_rx: &cancel_rx,
+            template_update_rx: &update_rx,
+            refresh_at: Instant::now() + Duration::from_secs(60),
+        };
+        let mut header = BlockHeader::from([1u8; 32], [2u8; 32], 8, 0, 50);
+        let mut hash_count = 0;
+
+        let result = proof_of_work(8, &mut header, &interrupts, &mut hash_count, 4);
+
+        let PowResult::Found(hash) = result else {
+            panic!("expected a block hash, got {:?}", result);
+        };
+        assert!(verify_proof_of_work(&header, &hash));
+        header.nonce += 1;
+        assert!(!verify_proof_of_work(&header, &hash));
+        assert!(hash_count > 0);
+    }
+
+    #[test]
+    fn test_proof_of_work_interrupted() {
+        let (cancel_tx, cancel_rx) = unbounded();
+        let (update_tx, update_rx) = unbounded();
+        let interrupts = PowInterrupts {
+            cancel_rx: &cancel_rx,
+            template_update_rx: &update_rx,
+            refresh_at: Instant::now() + Duration::from_secs(60),
+        };
+        let mut header = BlockHeader::from([1u8; 32], [2u8; 32], 255, 0, 50);
+        let mut hash_count = 0;
+
+        // new transactions make the template stale once it is a second old
+        update_tx.send(()).unwrap();
+        let result = proof_of_work(255, &mut header, &interrupts, &mut hash_count, 2);
+        assert_eq!(result, PowResult::Stale);
+
+        cancel_tx.send(()).unwrap();
+        let result = proof_of_work(255, &mut header, &interrupts, &mut hash_count, 2);
+        assert_eq!(result, PowResult::Cancelled);
+    }
+
+    #[test]
+    fn test_solve_sequential() {
+        let mut header = BlockHeader::from([1u8; 32], [2u8; 32], 8, 0, 50);
+        let (hash, hash_count) = solve_sequential(8, &mut header).unwrap();
+        assert!(verify_proof_of_work(&header, &hash));
+        assert_eq!(hash_count, header.nonce as u64 + 1);
+
+        // the same header yields the same solution
+        let mut other = BlockHeader::from([1u8; 32], [2u8; 32], 8, 0, 50);
+        assert_eq!(solve_sequential(8, &mut other).unwrap(), (hash, hash_count));
+    }
+
+    #[test]
+    fn test_invalid_difficulty() {
+        assert!(target_from_difficulty_bit(0).is_none());
+        assert!(target_from_difficulty_bit(255).is_some());
+
+        // any hash would meet difficulty 0, which is rejected rather than panicking
+        let header = BlockHeader::from([1u8; 32], [2u8; 32], 0, 0, 50);
+        assert!(!verify_proof_of_work(&header, &header.finalize()));
+    }
+}
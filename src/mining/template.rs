@@ -0,0 +1,115 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    block::{Block, BlockHeader},
+    crypto::{
+        hash_utils::{hash_from_vec_u8, HashResult},
+        merkle_tree::generate_merkle_root,
+    },
+    database::database::DatabaseType,
+    mempool::selection::select_transactions,
+    proto::proto_node,
+    transaction::Transaction,
+    types::Satoshi,
+};
+
+/// Everything needed to mine the next block, except for the coinbase.
+#[derive(Debug, Clone)]
+pub struct BlockTemplate {
+    pub previous_block_hash: HashResult,
+    /// Height of the block to be mined.
+    pub height: usize,
+    pub difficulty: u8,
+    pub timestamp: u64,
+    /// Block reward, excluding the fees.
+    pub reward: Satoshi,
+    /// Sum of the fees paid by the selected transactions.
+    pub fees: Satoshi,
+    /// Pending transactions selected for the block, parents before children.
+    pub transactions: Vec<Transaction>,
+}
+
+impl BlockTemplate {
+    /// Creates a template on top of the current head, selecting the pending
+    /// transactions paying the highest fee rates.
+    pub fn new(
+        database: &DatabaseType,
+        difficulty: u8,
+        reward: Satoshi,
+        max_block_size: usize,
+    ) -> Self {
+        let (transactions, fees) = select_transactions(database, max_block_size);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Self {
+            previous_block_hash: database.head().map(|x| x.hash).unwrap_or_default(),
+            height: database.block_height(),
+            difficulty,
+            timestamp,
+            reward,
+            fees,
+            transactions,
+        }
+    }
+
+    /// Amount the coinbase may claim: the block reward plus the fees.
+    pub fn coinbase_value(&self) -> Satoshi {
+        self.reward + self.fees
+    }
+
+    /// Assembles the block from the given coinbase transactions followed by the
+    /// selected transactions. The header nonce is still to be found.
+    pub fn assemble(&self, coinbase_txs: Vec<Transaction>) -> Block {
+        let mut txs = coinbase_txs;
+        txs.extend(self.transactions.iter().cloned());
+        let merkle_root = generate_merkle_root(txs.iter().map(|x| x.hash()).collect());
+
+        let header = BlockHeader::from(
+            merkle_root,
+            self.previous_block_hash,
+            self.difficulty,
+            self.timestamp,
+            self.reward,
+        );
+        let hash = header.finalize();
+        Block::new(header, txs, hash)
+    }
+}
+
+impl From<&BlockTemplate> for proto_node::BlockTemplate {
+    fn from(template: &BlockTemplate) -> Self {
+        proto_node::BlockTemplate {
+            previous_block_hash: template.previous_block_hash.to_vec(),
+            height: template.height as u64,
+            difficulty: template.difficulty as u32,
+            timestamp: template.timestamp,
+            reward: template.reward,
+            fees: template.fees,
+            transactions_json: serde_json::to_string(&template.transactions).unwrap(),
+        }
+    }
+}
+
+impl TryFrom<proto_node::BlockTemplate> for BlockTemplate {
+    type Error = String;
+
+    fn try_from(template: proto_node::BlockTemplate) -> Result<Self, Self::Error> {
+        if template.previous_block_hash.len() != 32 {
+            return Err("Invalid previous block hash.".to_string());
+        }
+        Ok(Self {
+            previous_block_hash: hash_from_vec_u8(&template.previous_block_hash),
+            height: template.height as usize,
+            difficulty: u8::try_from(template.difficulty)
+                .map_err(|_| "Invalid difficulty.".to_string())?,
+            timestamp: template.timestamp,
+            reward: template.reward,
+            fees: template.fees,
+            transactions: serde_json::from_str(&template.transactions_json)
+                .map_err(|err| format!("Invalid transactions: {}", err))?,
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+/// Delay before a failed address is tried again, doubled for each further failure.
+const RETRY_BASE_SECS: u64 = 10;
+/// Upper bound of the doublings of the retry delay.
+const MAX_RETRY_EXPONENT: u32 = 6;
+/// Addresses failing this often in a row without ever succeeding are forgotten.
+const MAX_FAILURES: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AddressInfo {
+    /// Unix timestamp of the last successful connection, 0 if there was none.
+    pub last_seen: u64,
+    /// Unix timestamp of the last connection attempt.
+    pub last_attempt: u64,
+    pub successes: u32,
+    /// Failed attempts since the last success.
+    pub failures: u32,
+}
+
+impl AddressInfo {
+    /// Whether the back-off of the previous failures has passed.
+    fn is_ready(&self, now: u64) -> bool {
+        if self.failures == 0 {
+            return true;
+        }
+        let exponent = (self.failures - 1).min(MAX_RETRY_EXPONENT);
+        now >= self.last_attempt + (RETRY_BASE_SECS << exponent)
+    }
+}
+
+/// Addresses of the nodes we know about, learned from the seed list, the peer
+/// lists of other nodes and nodes connecting to us. They are persisted, so that
+/// a restarted node does not depend on the seed list alone.
+#[derive(Default)]
+pub struct AddressManager {
+    addresses: BTreeMap<String, AddressInfo>,
+}
+
+/// Peer addresses are URLs; addresses without a scheme are assumed to be plain http.
+pub fn normalize_address(address: &str) -> String {
+    if address.contains("://") {
+        address.to_string()
+    } else {
+        format!("http://{}", address)
+    }
+}
+
+impl AddressManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the addresses persisted at the path. A missing or invalid file
+    /// yields an empty address manager.
+    pub fn load(path: &str) -> Self {
+        let addresses = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        let address_manager = Self { addresses };
+        log::debug!(
+            "Loaded {} peer addresses from {}.",
+            address_manager.len(),
+            path
+        );
+        address_manager
+    }
+
+    /// Saves the addresses to a file. Returns a boolean on success or failure.
+    pub fn save(&self, path: &str) -> bool {
+        let json = serde_json::to_string_pretty(&self.addresses).unwrap();
+        if fs::write(path, json).is_err() {
+            log::error!("Failed to write peer addresses to {}", path);
+            return false;
+        }
+        true
+    }
+
+    /// Adds an unknown address. Returns whether it was added.
+    pub fn add(&mut self, address: &str) -> bool {
+        let address = normalize_address(address);
+        if self.addresses.contains_key(&address) {
+            return false;
+        }
+        self.addresses.insert(address, AddressInfo::default());
+        true
+    }
+
+    pub fn mark_success(&mut self, address: &str, now: u64) {
+        let info = self
+            .addresses
+            .entry(normalize_address(address))
+            .or_default();
+        info.last_seen = now;
+        info.last_attempt = now;
+        info.successes += 1;
+        info.failures = 0;
+    }
+
+    pub fn mark_failure(&mut self, address: &str, now: u64) {
+        let address = normalize_address(address);
+        let Some(info) = self.addresses.get_mut(&address) else {
+            return;
+        };
+        info.last_attempt = now;
+        info.failures += 1;
+        if info.failures >= MAX_FAILURES && info.successes == 0 {
+            log::debug!("Forgetting unreachable peer address {}.", address);
+            self.addresses.remove(&address);
+        }
+    }
+
+    /// Selects up to `count` addresses to connect to, skipping excluded ones and
+    /// ones still backing off. Addresses with fewer failures and more recent
+    /// successes come first.
+    pub fn select(&self, count: usize, now: u64, exclude: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut candidates: Vec<(&String, &AddressInfo)> = self
+            .addresses
+            .iter()
+            .filter(|(address, info)| info.is_ready(now) && !exclude(address))
+            .collect();
+        candidates.sort_by_key(|(address, info)| {
+            (info.failures, std::cmp::Reverse(info.last_seen), *address)
+        });
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(address, _)| address.clone())
+            .collect()
+    }
+
+    pub fn get(&self, address: &str) -> Option<&AddressInfo> {
+        self.addresses.get(&normalize_address(address))
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AddressManager;
+
+    #[test]
+    fn test_select_with_back_off() {
+        let mut address_manager = AddressManager::new();
+        assert!(address_manager.add("[::1]:1337"));
+        assert!(!address_manager.add("http://[::1]:1337"));
+        address_manager.add("http://[::1]:9999");
+        address_manager.add("http://[::1]:10000");
+
+        address_manager.mark_success("http://[::1]:10000", 100);
+        address_manager.mark_failure("http://[::1]:1337", 100);
+        assert_eq!(
+            address_manager.select(3, 100, |_| false),
+            vec!["http://[::1]:10000", "http://[::1]:9999"]
+        );
+        assert_eq!(
+            address_manager.select(1, 100, |x| x.ends_with("10000")),
+            vec!["http://[::1]:9999"]
+        );
+
+        // the retry delay doubles with each failure
+        assert_eq!(address_manager.select(3, 110, |_| false).len(), 3);
+        address_manager.mark_failure("http://[::1]:1337", 110);
+        assert_eq!(address_manager.select(3, 129, |_| false).len(), 2);
+        assert_eq!(address_manager.select(3, 130, |_| false).len(), 3);
+    }
+
+    #[test]
+    fn test_forget_unreachable() {
+        let mut address_manager = AddressManager::new();
+        address_manager.add("http://[::1]:1337");
+        for i in 0..10 {
+            address_manager.mark_failure("http://[::1]:1337", i);
+        }
+        assert!(address_manager.is_empty());
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = "./configs/peers_test.json";
+        let mut address_manager = AddressManager::new();
+        address_manager.add("http://[::1]:1337");
+        address_manager.mark_success("http://[::1]:1337", 42);
+        assert!(address_manager.save(path));
+
+        let loaded = AddressManager::load(path);
+        assert_eq!(
+            loaded.get("http://[::1]:1337"),
+            address_manager.get("http://[::1]:1337")
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
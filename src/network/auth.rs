@@ -0,0 +1,132 @@
+use crate::{
+    config::models::{AccountConfig, AuthConfig},
+    crypto::{account::Account, signature::verify},
+    proto::proto_node::AuthMessage,
+};
+
+/// Authentication messages signed longer ago are refused, as are nonces
+/// issued longer ago.
+pub const MAX_HANDSHAKE_AGE_SECS: u64 = 60;
+
+/// Ed25519 key of the node, whose ownership is proven when authenticating a
+/// connection, and the keys of the peers we trust.
+pub struct NodeIdentity {
+    account: Account,
+    trusted_peers: Vec<Vec<u8>>,
+}
+
+/// Signed content of an authentication message. The challenge binds a request
+/// to the nonce issued for its connection and an answer to the signature of
+/// the request it answers.
+fn auth_payload(message: &AuthMessage, challenge: &[u8]) -> Vec<u8> {
+    let mut payload = message.network_id.to_be_bytes().to_vec();
+    payload.extend_from_slice(message.server_address.as_bytes());
+    payload.extend_from_slice(&message.timestamp.to_be_bytes());
+    payload.extend_from_slice(&message.public_key);
+    payload.extend_from_slice(challenge);
+    payload
+}
+
+impl NodeIdentity {
+    pub fn new(account: Account, trusted_peers: Vec<Vec<u8>>) -> Self {
+        Self {
+            account,
+            trusted_peers,
+        }
+    }
+
+    pub fn load(config: &AuthConfig) -> Result<Self, String> {
+        let account = Account::load_or_create(AccountConfig {
+            keys_path: config.identity_keys_path.clone(),
+        })
+        .map_err(|_| "Failed to load the identity key.".to_string())?;
+        let trusted_peers = config
+            .trusted_peers
+            .iter()
+            .map(|key| hex::decode(key).map_err(|_| format!("Invalid trusted peer key {}.", key)))
+            .collect::<Result<Vec<Vec<u8>>, String>>()?;
+        log::info!("Node identity is {}.", account.public_key_as_hex());
+        Ok(Self::new(account, trusted_peers))
+    }
+
+    /// Signs the message over the challenge with the identity key.
+    pub fn sign(&self, message: &mut AuthMessage, timestamp: u64, challenge: &[u8]) {
+        message.public_key = self.account.public_key().to_vec();
+        message.timestamp = timestamp;
+        message.signature = self.account.sign(&auth_payload(message, challenge));
+    }
+
+    /// Checks that the message was signed over the challenge recently by a
+    /// trusted key.
+    pub fn verify(&self, message: &AuthMessage, challenge: &[u8], now: u64) -> Result<(), String> {
+        if now.abs_diff(message.timestamp) > MAX_HANDSHAKE_AGE_SECS {
+            return Err("Authentication is outdated.".to_string());
+        }
+        if !self.is_trusted(&message.public_key) {
+            return Err(format!(
+                "Peer key {} is not trusted.",
+                hex::encode(&message.public_key)
+            ));
+        }
+        verify(
+            &auth_payload(message, challenge),
+            &message.public_key,
+            &message.signature,
+        )
+        .map_err(|_| "Invalid authentication signature.".to_string())
+    }
+
+    pub fn is_trusted(&self, public_key: &[u8]) -> bool {
+        self.trusted_peers.is_empty() || self.trusted_peers.iter().any(|x| x == public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeIdentity;
+    use crate::{
+        config::models::AccountConfig, crypto::account::Account, proto::proto_node::AuthMessage,
+    };
+
+    fn identity(seed: u8, trusted_peers: Vec<Vec<u8>>) -> NodeIdentity {
+        let config = AccountConfig {
+            keys_path: String::new(),
+        };
+        NodeIdentity::new(
+            Account::from_seed(config, &[seed; 32]).unwrap(),
+            trusted_peers,
+        )
+    }
+
+    #[test]
+    fn test_handshake_authentication() {
+        let alice = identity(1, vec![]);
+        let bob = identity(2, vec![alice.account.public_key().to_vec()]);
+        let eve = identity(3, vec![]);
+
+        let nonce = [7u8; 32];
+        let mut request = AuthMessage {
+            server_address: "[::1]:1337".to_string(),
+            network_id: 1,
+            ..Default::default()
+        };
+        alice.sign(&mut request, 100, &nonce);
+        assert!(bob.verify(&request, &nonce, 110).is_ok());
+        assert!(bob.verify(&request, &nonce, 200).is_err());
+        // requests are bound to the nonce
+        assert!(bob.verify(&request, &[8u8; 32], 110).is_err());
+
+        // answers are bound to the request
+        let mut response = AuthMessage::default();
+        bob.sign(&mut response, 100, &request.signature);
+        assert!(alice.verify(&response, &request.signature, 100).is_ok());
+        assert!(alice.verify(&response, &nonce, 100).is_err());
+
+        // untrusted and tampered messages are refused
+        let mut forged = request.clone();
+        eve.sign(&mut forged, 100, &nonce);
+        assert!(bob.verify(&forged, &nonce, 100).is_err());
+        request.server_address = "[::1]:9999".to_string();
+        assert!(bob.verify(&request, &nonce, 100).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use tonic::transport::Uri;
+
+/// Peers reaching this score are banned.
+pub const BAN_THRESHOLD: u32 = 100;
+/// Duration of automatic bans and of manual bans without a duration.
+pub const DEFAULT_BAN_DURATION_SECS: u64 = 24 * 60 * 60;
+
+/// Misbehavior of a peer, each adding to its ban score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Misbehavior {
+    /// A block which can never be valid, e.g. with a hash not meeting its difficulty.
+    InvalidBlock,
+    /// A transaction which can never be valid, e.g. with outputs exceeding its inputs.
+    InvalidTransaction,
+    /// A message which cannot be decoded.
+    MalformedMessage,
+    /// A message breaking the protocol, e.g. a handshake of another network.
+    ProtocolViolation,
+}
+
+impl Misbehavior {
+    pub fn score(&self) -> u32 {
+        match self {
+            Misbehavior::InvalidBlock => 100,
+            Misbehavior::InvalidTransaction => 10,
+            Misbehavior::MalformedMessage => 20,
+            Misbehavior::ProtocolViolation => 50,
+        }
+    }
+}
+
+/// Ban scores and bans of peers by their IP address.
+#[derive(Default)]
+pub struct BanManager {
+    scores: HashMap<IpAddr, u32>,
+    /// Unix timestamps until which the addresses are banned.
+    bans: HashMap<IpAddr, u64>,
+}
+
+/// Extracts the IP address of a peer URL like `http://[::1]:1337` or of a plain IP address.
+pub fn peer_ip(address: &str) -> Option<IpAddr> {
+    if let Ok(ip) = address.parse::<IpAddr>() {
+        return Some(ip.to_canonical());
+    }
+    let uri = address.parse::<Uri>().ok()?;
+    let host = uri.host()?.trim_start_matches('[').trim_end_matches(']');
+    host.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+impl BanManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the score of the misbehavior to the peer and bans it, once the
+    /// threshold is reached. Returns whether the peer got banned.
+    pub fn misbehaving(&mut self, ip: IpAddr, misbehavior: Misbehavior, now: u64) -> bool {
+        let score = self.scores.entry(ip).or_default();
+        *score += misbehavior.score();
+        log::warn!(
+            "Peer {} misbehaved ({:?}), ban score is {}.",
+            ip,
+            misbehavior,
+            score
+        );
+        if *score < BAN_THRESHOLD {
+            return false;
+        }
+        self.ban(ip, now + DEFAULT_BAN_DURATION_SECS);
+        true
+    }
+
+    pub fn ban(&mut self, ip: IpAddr, until: u64) {
+        log::info!("Banned peer {} until {}.", ip, until);
+        self.scores.remove(&ip);
+        self.bans.insert(ip, until);
+    }
+
+    /// Lifts the ban and resets the ban score. Returns whether the peer was banned.
+    pub fn unban(&mut self, ip: IpAddr) -> bool {
+        self.scores.remove(&ip);
+        self.bans.remove(&ip).is_some()
+    }
+
+    pub fn is_banned(&self, ip: IpAddr, now: u64) -> bool {
+        self.bans.get(&ip).is_some_and(|until| now < *until)
+    }
+
+    /// Removes the expired bans.
+    pub fn prune(&mut self, now: u64) {
+        self.bans.retain(|_, until| now < *until);
+    }
+
+    pub fn score(&self, ip: IpAddr) -> u32 {
+        self.scores.get(&ip).copied().unwrap_or(0)
+    }
+
+    /// Peers with a ban score or an active ban, as address, ban score and end of
+    /// the ban (0 if not banned), sorted by address.
+    pub fn entries(&self, now: u64) -> Vec<(IpAddr, u32, u64)> {
+        let mut entries: Vec<(IpAddr, u32, u64)> = self
+            .scores
+            .iter()
+            .map(|(ip, score)| (*ip, *score, 0))
+            .chain(
+                self.bans
+                    .iter()
+                    .filter(|(_, until)| now < **until)
+                    .map(|(ip, until)| (*ip, 0, *until)),
+            )
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{peer_ip, BanManager, Misbehavior, DEFAULT_BAN_DURATION_SECS};
+
+    #[test]
+    fn test_ban_on_threshold() {
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut ban_manager = BanManager::new();
+        assert!(!ban_manager.misbehaving(ip, Misbehavior::ProtocolViolation, 100));
+        assert_eq!(ban_manager.score(ip), 50);
+        assert!(!ban_manager.is_banned(ip, 100));
+
+        assert!(ban_manager.misbehaving(ip, Misbehavior::ProtocolViolation, 100));
+        assert!(ban_manager.is_banned(ip, 100));
+        assert_eq!(ban_manager.score(ip), 0);
+
+        // bans expire
+        let until = 100 + DEFAULT_BAN_DURATION_SECS;
+        assert_eq!(ban_manager.entries(100), vec![(ip, 0, until)]);
+        assert!(!ban_manager.is_banned(ip, until));
+        ban_manager.prune(until);
+        assert!(ban_manager.entries(until).is_empty());
+    }
+
+    #[test]
+    fn test_unban() {
+        let ip: IpAddr = "::1".parse().unwrap();
+        let mut ban_manager = BanManager::new();
+        assert!(ban_manager.misbehaving(ip, Misbehavior::InvalidBlock, 0));
+        assert!(ban_manager.unban(ip));
+        assert!(!ban_manager.is_banned(ip, 0));
+        assert!(!ban_manager.unban(ip));
+    }
+
+    #[test]
+    fn test_peer_ip() {
+        assert_eq!(peer_ip("http://[::1]:1337"), Some("::1".parse().unwrap()));
+        assert_eq!(
+            peer_ip("http://127.0.0.1:1337"),
+            Some("127.0.0.1".parse().unwrap())
+        );
+        assert_eq!(peer_ip("10.0.0.1"), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(
+            peer_ip("::ffff:10.0.0.1"),
+            Some("10.0.0.1".parse().unwrap())
+        );
+        assert_eq!(peer_ip("http://localhost:1337"), None);
+    }
+}
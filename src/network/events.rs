@@ -0,0 +1,150 @@
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+
+use crate::{
+    block::Block, crypto::hash_utils::HashResult, database::database::DatabaseType,
+    metrics::metrics, transaction::Transaction,
+};
+
+/// Number of events buffered for each subscriber. Subscribers falling
+/// further behind miss events and are disconnected.
+const EVENT_CAPACITY: usize = 1024;
+
+/// Change of the chain or the pending transactions pushed to subscribers.
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    BlockConnected {
+        block: Block,
+        height: usize,
+    },
+    /// Block removed from the chain by a reorganization.
+    BlockDisconnected {
+        block: Block,
+        height: usize,
+    },
+    TransactionAccepted(Transaction),
+}
+
+/// Publishes the changes of the chain and the pending transactions.
+pub struct ChainEvents {
+    sender: broadcast::Sender<ChainEvent>,
+    /// Block hashes of the chain at the last update.
+    chain: Mutex<Vec<HashResult>>,
+}
+
+impl Default for ChainEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChainEvents {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+            chain: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ChainEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publishes the blocks disconnected and connected since the last update,
+    /// the disconnected ones from the old head backwards.
+    pub fn chain_updated(&self, db: &DatabaseType) {
+        let blocks = db.get_blocks();
+        let mut chain = self.chain.lock().unwrap();
+        let fork = chain
+            .iter()
+            .zip(blocks.iter())
+            .take_while(|(hash, block)| **hash == block.hash)
+            .count();
+
+        if chain.len() > fork {
+            metrics().reorganized(chain.len() - fork);
+        }
+        for (height, hash) in chain.iter().enumerate().skip(fork).rev() {
+            if let Some(block) = db.get_block(hash) {
+                self.publish(ChainEvent::BlockDisconnected {
+                    block: block.clone(),
+                    height,
+                });
+            }
+        }
+        for (height, block) in blocks.iter().enumerate().skip(fork) {
+            self.publish(ChainEvent::BlockConnected {
+                block: (*block).clone(),
+                height,
+            });
+        }
+        *chain = blocks.iter().map(|block| block.hash).collect();
+    }
+
+    pub fn transaction_accepted(&self, tx: &Transaction) {
+        self.publish(ChainEvent::TransactionAccepted(tx.clone()));
+    }
+
+    fn publish(&self, event: ChainEvent) {
+        // fails only if there are no subscribers
+        let _ = self.sender.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChainEvent, ChainEvents};
+    use crate::{
+        block::Block,
+        database::{database::Database, InMemoryDatabase},
+    };
+
+    fn block(hash: u8, parent: u8) -> Block {
+        let mut block = Block {
+            hash: [hash; 32],
+            ..Default::default()
+        };
+        block.header.previous_block_hash = [parent; 32];
+        block
+    }
+
+    fn events(receiver: &mut tokio::sync::broadcast::Receiver<ChainEvent>) -> Vec<(bool, u8)> {
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            match event {
+                ChainEvent::BlockConnected { block, .. } => events.push((true, block.hash[0])),
+                ChainEvent::BlockDisconnected { block, .. } => events.push((false, block.hash[0])),
+                ChainEvent::TransactionAccepted(_) => {}
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_reorganization() {
+        let mut db = InMemoryDatabase::new();
+        db.create_genesis_block(block(0, 0));
+        let chain_events = ChainEvents::new();
+        chain_events.chain_updated(&db);
+        let mut receiver = chain_events.subscribe();
+
+        db.insert_block(block(1, 0));
+        db.insert_block(block(2, 1));
+        chain_events.chain_updated(&db);
+        assert_eq!(events(&mut receiver), vec![(true, 1), (true, 2)]);
+
+        // a longer fork replaces the second block
+        db.insert_block(block(3, 1));
+        chain_events.chain_updated(&db);
+        assert!(events(&mut receiver).is_empty());
+        db.disconnect_head();
+        db.insert_block(block(3, 1));
+        db.insert_block(block(4, 3));
+        chain_events.chain_updated(&db);
+        assert_eq!(
+            events(&mut receiver),
+            vec![(false, 2), (true, 3), (true, 4)]
+        );
+    }
+}
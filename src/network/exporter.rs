@@ -0,0 +1,37 @@
+use std::future::Future;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+
+use super::node::NetworkServer;
+use crate::metrics::metrics;
+
+/// Serves the metrics of the node to Prometheus at `/metrics`.
+pub(super) async fn serve(
+    server: NetworkServer,
+    address: &str,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let router = Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(server);
+
+    log::info!("Metrics endpoint started on {}", address);
+    axum::Server::bind(&address.parse()?)
+        .serve(router.into_make_service())
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+async fn get_metrics(State(server): State<NetworkServer>) -> Response {
+    let info = server.chain_info();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics().render(&info),
+    )
+        .into_response()
+}
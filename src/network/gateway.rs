@@ -0,0 +1,392 @@
+use std::future::Future;
+use std::net::SocketAddr;
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Path, State};
+use axum::http::{header, Request as HttpRequest, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde_json::{json, Value};
+use tonic::{Code, Request, Status};
+
+use super::node::NetworkServer;
+use super::rpc::RpcGuard;
+use crate::crypto::hash_utils::{Address, HashResult};
+use crate::proto::proto_node::rpc_server::Rpc;
+use crate::proto::proto_node::{BlockReq, PublicKey, Transaction};
+
+/// HTTP JSON gateway to the RPC service, for clients which cannot speak gRPC.
+/// It shares the auth token, rate limit and read-only mode of the RPC service.
+#[derive(Clone)]
+struct Gateway {
+    server: NetworkServer,
+    guard: RpcGuard,
+}
+
+pub(super) async fn serve(
+    server: NetworkServer,
+    guard: RpcGuard,
+    address: &str,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let gateway = Gateway { server, guard };
+    let router = Router::new()
+        .route("/chain", get(get_chain))
+        .route("/blocks/:id", get(get_block))
+        .route("/transactions", axum::routing::post(submit_transaction))
+        .route("/transactions/:hash", get(get_transaction))
+        .route("/addresses/:address/balance", get(get_balance))
+        .route("/addresses/:address/utxos", get(get_utxos))
+        .route("/addresses/:address/transactions", get(get_history))
+        .route("/mempool", get(get_mempool))
+        .layer(middleware::from_fn_with_state(
+            gateway.clone(),
+            check_client,
+        ))
+        .with_state(gateway);
+
+    log::info!("HTTP gateway started on {}", address);
+    axum::Server::bind(&address.parse()?)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+fn json_response(status: StatusCode, value: Value) -> Response {
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/json")],
+        value.to_string(),
+    )
+        .into_response()
+}
+
+fn ok(value: Value) -> Response {
+    json_response(StatusCode::OK, value)
+}
+
+fn error(status: Status) -> Response {
+    let code = match status.code() {
+        Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists => StatusCode::CONFLICT,
+        Code::FailedPrecondition => StatusCode::UNPROCESSABLE_ENTITY,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    json_response(code, json!({ "error": status.message() }))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_hash(hash: &str) -> Result<HashResult, Response> {
+    HashResult::try_from(hex::decode(hash).unwrap_or_default())
+        .map_err(|_| error(Status::invalid_argument("Invalid hash.")))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_address(address: &str) -> Result<Address, Response> {
+    match hex::decode(address) {
+        Ok(address) if address.len() == ring::signature::ED25519_PUBLIC_KEY_LEN => Ok(address),
+        _ => Err(error(Status::invalid_argument("Invalid address."))),
+    }
+}
+
+async fn check_client(
+    State(gateway): State<Gateway>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: HttpRequest<Body>,
+    next: Next<Body>,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .map(|x| x.as_bytes());
+    if let Err(status) = gateway
+        .guard
+        .check(authorization, Some(addr.ip().to_canonical()))
+    {
+        return error(status);
+    }
+    next.run(request).await
+}
+
+async fn get_chain(State(gateway): State<Gateway>) -> Response {
+    let info = gateway.server.chain_info();
+    ok(json!({
+        "best_hash": hex::encode(info.best_hash),
+        "block_height": info.block_height,
+        "difficulty": info.difficulty,
+        "target": hex::encode(info.target),
+        "chain_work": hex::encode(info.chain_work),
+        "block_reward": info.block_reward,
+        "mempool_size": info.mempool_size,
+        "mempool_bytes": info.mempool_bytes,
+        "network": info.network,
+        "sync_progress": info.sync_progress,
+        "peer_count": info.peer_count,
+        "uptime_secs": info.uptime_secs,
+    }))
+}
+
+/// Block by its height or hash.
+async fn get_block(State(gateway): State<Gateway>, Path(id): Path<String>) -> Response {
+    let request = match id.parse::<u32>() {
+        Ok(block_height) => BlockReq {
+            block_height,
+            block_hash: None,
+        },
+        Err(_) => BlockReq {
+            block_height: 0,
+            block_hash: Some(id),
+        },
+    };
+    match gateway.server.find_block(&request) {
+        Ok(block) => ok(serde_json::from_str(&block.block_json).unwrap_or_default()),
+        Err(status) => error(status),
+    }
+}
+
+/// Submits a transaction, given as JSON in the body.
+async fn submit_transaction(State(gateway): State<Gateway>, tx_json: String) -> Response {
+    let hash = serde_json::from_str::<crate::transaction::Transaction>(&tx_json)
+        .map(|tx| hex::encode(tx.hash()));
+    match Rpc::add_transaction(&gateway.server, Request::new(Transaction { tx_json })).await {
+        Ok(_) => ok(json!({ "hash": hash.unwrap_or_default() })),
+        Err(status) => error(status),
+    }
+}
+
+/// Confirmed or pending transaction.
+async fn get_transaction(State(gateway): State<Gateway>, Path(hash): Path<String>) -> Response {
+    let hash = match parse_hash(&hash) {
+        Ok(hash) => hash,
+        Err(response) => return response,
+    };
+    match gateway.server.find_transaction(&hash) {
+        Some((tx, confirmed)) => ok(json!({ "transaction": tx, "confirmed": confirmed })),
+        None => error(Status::not_found("Transaction not found.")),
+    }
+}
+
+/// Confirmed balance and the balance once the pending transactions are confirmed.
+async fn get_balance(State(gateway): State<Gateway>, Path(address): Path<String>) -> Response {
+    let address = match parse_address(&address) {
+        Ok(address) => address,
+        Err(response) => return response,
+    };
+    let db = gateway.server.database.lock().unwrap();
+    let mut confirmed = 0;
+    let mut pending = 0;
+    for (tx_hash, output_index, amount) in db.get_utxo(&address) {
+        confirmed += amount;
+        if !db.is_spent_in_mempool(&tx_hash, output_index) {
+            pending += amount;
+        }
+    }
+    for tx in db.get_pending_transactions() {
+        pending += tx
+            .outputs
+            .iter()
+            .filter(|x| x.receiver == address && !x.is_unspendable())
+            .map(|x| x.value)
+            .sum::<u64>();
+    }
+    ok(json!({ "confirmed": confirmed, "pending": pending }))
+}
+
+async fn get_utxos(State(gateway): State<Gateway>, Path(address): Path<String>) -> Response {
+    let key = match parse_address(&address) {
+        Ok(address) => address,
+        Err(response) => return response,
+    };
+    match Rpc::get_utxo(&gateway.server, Request::new(PublicKey { key })).await {
+        Ok(response) => ok(response
+            .into_inner()
+            .unspent_outputs
+            .iter()
+            .map(|x| {
+                json!({
+                    "tx_hash": hex::encode(&x.previous_transaction_hash),
+                    "output_index": x.previous_transaction_output_index,
+                    "amount": x.amount,
+                    "spent_in_mempool": x.spent_in_mempool,
+                })
+            })
+            .collect()),
+        Err(status) => error(status),
+    }
+}
+
+/// Transactions sent or received by the address in chain order, followed by
+/// the pending ones.
+async fn get_history(State(gateway): State<Gateway>, Path(address): Path<String>) -> Response {
+    let address = match parse_address(&address) {
+        Ok(address) => address,
+        Err(response) => return response,
+    };
+    let db = gateway.server.database.lock().unwrap();
+    let hashes = db.get_transaction_hashes(&address);
+    let mut history = vec![];
+    for (height, block) in db.get_blocks().iter().enumerate() {
+        for tx in block.transactions.iter() {
+            let hash = tx.hash();
+            if hashes.contains(&hash) {
+                history.push(json!({ "hash": hex::encode(hash), "block_height": height }));
+            }
+        }
+    }
+    for tx in db.get_pending_transactions() {
+        if tx.sender == address || tx.outputs.iter().any(|x| x.receiver == address) {
+            history.push(json!({ "hash": hex::encode(tx.hash()), "block_height": null }));
+        }
+    }
+    ok(Value::Array(history))
+}
+
+async fn get_mempool(State(gateway): State<Gateway>) -> Response {
+    let db = gateway.server.database.lock().unwrap();
+    ok(db
+        .get_pending_transactions()
+        .iter()
+        .map(|tx| json!({ "hash": hex::encode(tx.hash()), "transaction": tx }))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::serve;
+    use crate::config::models::AccountConfig;
+    use crate::crypto::account::Account;
+    use crate::network::node::tests::{regtest_network, server};
+    use crate::network::rpc::RpcGuard;
+    use crate::transaction::builder::TransactionBuilder;
+
+    /// Sends an HTTP request and returns the status code and the JSON body.
+    async fn http(
+        port: u16,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        body: &str,
+    ) -> (u16, Value) {
+        let mut stream = TcpStream::connect(("::1", port)).await.unwrap();
+        let authorization = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            authorization,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let (network, _) = regtest_network("gateway_routes");
+        let server = server(&network);
+        let mut rpc_config = server.config.rpc.clone();
+        rpc_config.auth_token = Some("secret".to_string());
+        let port = std::net::TcpListener::bind("[::1]:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let gateway = server.clone();
+        tokio::spawn(async move {
+            let address = format!("[::1]:{}", port);
+            let guard = RpcGuard::new(&rpc_config);
+            let _ = serve(gateway, guard, &address, std::future::pending()).await;
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let token = Some("secret");
+
+        // the gateway requires the token of the RPC service
+        let (status, body) = http(port, "GET", "/chain", None, "").await;
+        assert_eq!(status, 401);
+        assert_eq!(body["error"], "Invalid RPC auth token.");
+        let (status, body) = http(port, "GET", "/chain", token, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["block_height"], 1);
+        assert_eq!(body["network"], "regtest");
+
+        let (status, body) = http(port, "GET", "/blocks/0", token, "").await;
+        assert_eq!(status, 200);
+        let genesis_hash = server.database.lock().unwrap().head().unwrap().hash;
+        assert_eq!(body["hash"], serde_json::to_value(genesis_hash).unwrap());
+        let (status, _) = http(port, "GET", "/blocks/xyz", token, "").await;
+        assert_eq!(status, 400);
+
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let funding = TransactionBuilder::new(&account)
+            .add_input([9u8; 32], 0, 10_000)
+            .add_output(account.public_key(), 10_000)
+            .build()
+            .unwrap();
+        {
+            let mut db = server.database.lock().unwrap();
+            db.add_transaction(funding.hash(), funding.clone());
+            db.add_utxo(funding.hash(), 0);
+            db.map_address_to_transaction_hash(account.public_key(), funding.hash());
+        }
+        let receiver = [7u8; 32];
+        let tx = TransactionBuilder::new(&account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&receiver, 9_000)
+            .build()
+            .unwrap();
+        let tx_json = serde_json::to_string(&tx).unwrap();
+        let (status, body) = http(port, "POST", "/transactions", token, &tx_json).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["hash"], hex::encode(tx.hash()));
+        let (status, _) = http(port, "POST", "/transactions", token, &tx_json).await;
+        assert_eq!(status, 409);
+        let (status, _) = http(port, "POST", "/transactions", token, "{}").await;
+        assert_eq!(status, 400);
+
+        let path = format!("/transactions/{}", hex::encode(tx.hash()));
+        let (status, body) = http(port, "GET", &path, token, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["confirmed"], false);
+        let path = format!("/transactions/{}", hex::encode([1u8; 32]));
+        let (status, _) = http(port, "GET", &path, token, "").await;
+        assert_eq!(status, 404);
+
+        let (status, body) = http(port, "GET", "/mempool", token, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        let path = format!("/addresses/{}/balance", hex::encode(receiver));
+        let (status, body) = http(port, "GET", &path, token, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["confirmed"], 0);
+        assert_eq!(body["pending"], 9_000);
+        let path = format!("/addresses/{}/utxos", hex::encode(account.public_key()));
+        let (status, body) = http(port, "GET", &path, token, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body[0]["spent_in_mempool"], true);
+        let (status, _) = http(port, "GET", "/addresses/xyz/balance", token, "").await;
+        assert_eq!(status, 400);
+    }
+}
@@ -1 +1,12 @@
+pub mod address_manager;
+pub mod auth;
+pub mod ban_manager;
+pub mod events;
+pub mod exporter;
+pub mod gateway;
 pub mod node;
+pub mod orphan_pool;
+pub mod peer;
+pub mod rate_limiter;
+pub mod rpc;
+pub mod tls;
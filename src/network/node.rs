@@ -1,117 +1,304 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
-use std::pin::Pin;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::AtomicU8;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use tonic::transport::Channel;
-use tonic::{transport::Server, Request, Response, Status};
+use prost::Message;
+use tokio::sync::watch;
+use tonic::transport::{Channel, Server};
+use tonic::{Request, Response, Status};
 
-use crate::crypto::hash_utils::Address;
+use super::address_manager::{normalize_address, AddressManager};
+use super::auth::{NodeIdentity, MAX_HANDSHAKE_AGE_SECS};
+use super::ban_manager::{peer_ip, BanManager, Misbehavior};
+use super::events::ChainEvents;
+use super::orphan_pool::{OrphanPool, MAX_ORPHANS};
+use super::peer::Peer;
+use super::rpc::RpcGuard;
+use super::tls::{connect, TlsSettings};
+use super::{exporter, gateway};
+use crate::block::compact_block::CompactBlock;
+use crate::block::BlockHeader;
+use crate::chain;
+use crate::config::models::Config;
+use crate::crypto::hash_utils::HashResult;
 use crate::database::database::DatabaseType;
+use crate::mempool::acceptance::{accept_transaction, MempoolError};
+use crate::mempool::fee_estimator::FeeEstimator;
+use crate::mempool::persistence;
+use crate::metrics::{metrics, RpcMetricsLayer};
+use crate::mining::pow_utils::verify_proof_of_work;
 use crate::proto::proto_node::node_client::NodeClient;
 use crate::proto::proto_node::node_server::{Node, NodeServer};
+use crate::proto::proto_node::rpc_server::RpcServer;
 use crate::proto::proto_node::{
-    self, Block, BlockReq, Chain, HandshakeMessage, PeerList, PublicKey, Transaction,
-    TransactionReq, UnspentOutput, UnspentOutputs,
+    self, AuthMessage, Block, BlockAnnouncement, BlockReq, BlockTransactions, BlockTransactionsReq,
+    Chain, Challenge, HandshakeMessage, PeerList, PingMessage,
 };
 
+/// Interval in which the outbound connections are topped up to the target.
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Interval in which the peers are pinged.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// Pings not answered within this time count as failed.
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+/// Number of announced blocks whose transactions peers may request.
+const MAX_RELAYED_BLOCKS: usize = 16;
+
+/// Nonces issued per connection, with the time they were issued at.
+type Challenges = HashMap<SocketAddr, (Vec<u8>, u64)>;
+
 pub struct Network {
-    peers: Arc<Mutex<HashMap<String, NodeClient<Channel>>>>,
+    peers: Arc<Mutex<HashMap<String, Peer>>>,
     blocked_peers: Arc<Vec<String>>,
-    seed_list: Vec<String>,
+    address_manager: Arc<Mutex<AddressManager>>,
+    ban_manager: Arc<Mutex<BanManager>>,
+    orphan_pool: Arc<Mutex<OrphanPool>>,
+    relayed_blocks: Arc<Mutex<VecDeque<crate::block::Block>>>,
+    tls: Option<TlsSettings>,
+    identity: Option<Arc<NodeIdentity>>,
+    /// Nonces issued to connections which have yet to authenticate.
+    challenges: Arc<Mutex<Challenges>>,
+    /// Connections which proved ownership of a trusted identity key, with the
+    /// address of the peer they belong to.
+    authenticated: Arc<Mutex<HashMap<SocketAddr, String>>>,
+    events: Arc<ChainEvents>,
+    started: Instant,
+    /// Set once the node is shutting down.
+    shutdown: watch::Sender<bool>,
     database: Arc<Mutex<DatabaseType>>,
-    port: u16,
+    config: Config,
+    fee_estimator: Arc<Mutex<FeeEstimator>>,
+    difficulty: Arc<AtomicU8>,
     block_announce_tx: crossbeam::channel::Sender<crate::block::Block>,
     block_publish_rx: crossbeam::channel::Receiver<crate::block::Block>,
+    mempool_update_tx: crossbeam::channel::Sender<()>,
 }
 impl Network {
     pub fn new(
-        port: u16,
-        seed_list: Vec<String>,
+        config: Config,
         database: Arc<Mutex<DatabaseType>>,
+        fee_estimator: Arc<Mutex<FeeEstimator>>,
+        difficulty: Arc<AtomicU8>,
         block_announce_tx: crossbeam::channel::Sender<crate::block::Block>,
         block_publish_rx: crossbeam::channel::Receiver<crate::block::Block>,
+        mempool_update_tx: crossbeam::channel::Sender<()>,
     ) -> Self {
         let peers = Arc::new(Mutex::new(HashMap::new()));
-        let address = format!("[::]:{}", port);
-        let blocked_peers = Arc::new(vec![address.clone()]);
+        // our own addresses, under which we must not connect to ourselves
+        let port = config.port();
+        let blocked_peers: Arc<Vec<String>> = Arc::new(
+            ["[::]", "[::1]", "127.0.0.1", "localhost"]
+                .iter()
+                .map(|host| normalize_address(&format!("{}:{}", host, port)))
+                .collect(),
+        );
+
+        let mut address_manager = AddressManager::load(&config.network.peers_path);
+        for address in config.network.seed_list.iter() {
+            if !blocked_peers.contains(&normalize_address(address)) {
+                address_manager.add(address);
+            }
+        }
+
+        let tls = config.network.tls.as_ref().map(|tls| {
+            TlsSettings::load(tls).unwrap_or_else(|err| panic!("Invalid TLS config: {}", err))
+        });
+        let identity = config.network.auth.as_ref().map(|auth| {
+            Arc::new(
+                NodeIdentity::load(auth)
+                    .unwrap_or_else(|err| panic!("Invalid auth config: {}", err)),
+            )
+        });
+
         Network {
-            port,
             blocked_peers,
-            seed_list,
             peers,
+            address_manager: Arc::new(Mutex::new(address_manager)),
+            ban_manager: Arc::new(Mutex::new(BanManager::new())),
+            orphan_pool: Arc::new(Mutex::new(OrphanPool::new())),
+            relayed_blocks: Arc::new(Mutex::new(VecDeque::new())),
+            tls,
+            identity,
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+            authenticated: Arc::new(Mutex::new(HashMap::new())),
+            events: Arc::new(ChainEvents::new()),
+            started: Instant::now(),
+            shutdown: watch::channel(false).0,
             database,
+            config,
+            fee_estimator,
+            difficulty,
             block_announce_tx,
             block_publish_rx,
+            mempool_update_tx,
         }
     }
 
     pub async fn start_network_node(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let address = format!("[::]:{}", self.port);
+        let address = format!("[::]:{}", self.config.port());
         log::info!("Node gRPC server started on {}", &address);
-        let server = NetworkServer::new(
-            self.peers.clone(),
-            self.blocked_peers.clone(),
-            self.database.clone(),
-            self.port,
-            self.block_announce_tx.clone(),
-        );
+        let server = NetworkServer::new(self);
 
-        Server::builder()
+        let mut builder = Server::builder();
+        if let Some(tls) = &self.tls {
+            builder = builder.tls_config(tls.server.clone())?;
+        }
+        builder
+            .layer(RpcMetricsLayer)
             .add_service(NodeServer::new(server))
-            .serve(address.parse().unwrap())
+            .serve_with_shutdown(address.parse().unwrap(), self.stopped())
             .await?;
 
         Ok(())
     }
 
-    pub async fn start_sync(&self) -> Result<(), String> {
-        // explore peers
-        let mut longest_chain = ("".to_string(), 0);
-        let peers = self.peers.clone();
-        let blocked_peers = self.blocked_peers.clone();
+    /// Stops the servers from accepting connections and ends the streams to
+    /// subscribers. The requests in progress are completed.
+    pub fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
 
-        let mut neighbours: Vec<String>;
-        {
-            let peers = peers.lock().unwrap();
-            neighbours = self
-                .seed_list
-                .clone()
-                .into_iter()
-                .filter(|p| {
-                    !self.blocked_peers.contains(&p)
-                        && !peers.contains_key(p)
-                        && !p.contains(&format!("[::]:{}", self.port))
-                })
-                .collect();
+    /// Completes once the node is shutting down.
+    fn stopped(&self) -> impl Future<Output = ()> {
+        let mut shutdown = self.shutdown.subscribe();
+        async move { stopped(&mut shutdown).await }
+    }
+
+    /// Writes the state to disk which should survive a restart.
+    pub fn persist(&self) {
+        self.save_addresses();
+        log::info!("Saved the peer addresses.");
+        match NetworkServer::new(self).save_mempool() {
+            Ok(count) => log::info!("Saved {} pending transactions.", count),
+            Err(err) => log::error!("{}", err),
         }
+    }
+
+    /// Adds the transactions saved on shutdown to the pending transactions.
+    /// They are validated against the current chain, so it should be synchronized.
+    pub fn load_mempool(&self) {
+        let path = &self.config.mempool.persist_path;
+        let transactions = match persistence::load(path) {
+            Ok(transactions) => transactions,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            }
+        };
+        let server = NetworkServer::new(self);
+        let count = transactions.len();
+        let mut accepted = 0;
+        for tx in transactions {
+            let tx_hash = tx.hash();
+            match server.accept_pending(tx) {
+                Ok(()) => accepted += 1,
+                Err(err) => log::debug!(
+                    "Dropped saved transaction {}: {}",
+                    hex::encode(tx_hash),
+                    err
+                ),
+            }
+        }
+        if count > 0 {
+            log::info!(
+                "Loaded {} of {} saved pending transactions from {}.",
+                accepted,
+                count,
+                path
+            );
+        }
+    }
+
+    /// Publisher of the chain events streamed to subscribers.
+    pub fn chain_events(&self) -> Arc<ChainEvents> {
+        self.events.clone()
+    }
+
+    /// Serves the client RPC service of wallets, miners and administrators.
+    pub async fn start_rpc_server(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let address = &self.config.rpc.bind_address;
+        log::info!("RPC server started on {}", address);
+        let server = NetworkServer::new(self);
+
+        Server::builder()
+            .layer(RpcMetricsLayer)
+            .add_service(RpcServer::with_interceptor(
+                server,
+                RpcGuard::new(&self.config.rpc),
+            ))
+            .serve_with_shutdown(address.parse()?, self.stopped())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Serves the HTTP JSON gateway to the RPC service, if it is configured.
+    pub async fn start_http_gateway(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(address) = &self.config.rpc.http_bind_address else {
+            return Ok(());
+        };
+        gateway::serve(
+            NetworkServer::new(self),
+            RpcGuard::new(&self.config.rpc),
+            address,
+            self.stopped(),
+        )
+        .await
+    }
+
+    /// Serves the metrics of the node, if it is configured.
+    pub async fn start_metrics_server(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(metrics) = &self.config.metrics else {
+            return Ok(());
+        };
+        exporter::serve(
+            NetworkServer::new(self),
+            &metrics.bind_address,
+            self.stopped(),
+        )
+        .await
+    }
+
+    pub async fn start_sync(&self) -> Result<(), String> {
+        // explore peers, starting with the most reliable known addresses
+        let mut longest_chain = ("".to_string(), 0);
+        let target = self.config.network.target_outbound_peers;
+        let mut neighbours = self.select_addresses(target, &[]);
 
         while let Some(next_peer) = neighbours.pop() {
-            let (new_peers, block_height) = self
-                .connect_and_get_info(next_peer.to_string(), peers.clone())
-                .await;
+            let Some(block_height) = self.connect_peer(next_peer.clone()).await else {
+                continue;
+            };
             if block_height > longest_chain.1 {
                 longest_chain.0 = next_peer;
                 longest_chain.1 = block_height;
             }
 
-            let peers = peers.lock().unwrap();
-            for new_peer in new_peers {
-                if blocked_peers.contains(&new_peer) || peers.contains_key(&new_peer) {
-                    continue;
-                }
-            }
+            // continue with the addresses learned from the peer until the target is reached
+            let missing = target.saturating_sub(self.outbound_count() + neighbours.len());
+            let more = self.select_addresses(missing, &neighbours);
+            neighbours.extend(more);
         }
+        self.save_addresses();
 
-        if longest_chain.0 == "" || longest_chain.1 == 0 {
+        if longest_chain.0.is_empty() || longest_chain.1 == 0 {
             return Err("Failed to find a peer for synchronization!".to_string());
         }
 
         // select the peer with highest block height and download chain
-        if let Some(client) = self.peers.lock().unwrap().get_mut(&longest_chain.0) {
-            if let Ok(chain_json) = client.get_chain(Request::new(proto_node::None {})).await {
+        let peer = self.peers.lock().unwrap().get(&longest_chain.0).cloned();
+        if let Some(Peer { mut client, .. }) = peer {
+            let chain_json = client.get_chain(Request::new(proto_node::None {})).await;
+            record_request(
+                &self.peers,
+                &longest_chain.0,
+                chain_json.as_ref().map(|x| x.get_ref().encoded_len()),
+                0,
+            );
+            if let Ok(chain_json) = chain_json {
                 if let Ok(blocks) =
                     serde_json::from_str::<Vec<crate::block::Block>>(&chain_json.get_ref().blocks)
                 {
@@ -136,13 +323,24 @@ impl Network {
                         if block.verify(&self.database) {
                             self.database.lock().unwrap().insert_block(block);
                         } else {
+                            metrics().validation_failed("block", "invalid_block");
                             log::error!(
                                 "Block ({}) verification failed!",
                                 hex::encode(block.hash.get(..5).unwrap())
                             );
+                            if let Some(ip) = peer_ip(&longest_chain.0) {
+                                misbehaving(
+                                    &self.ban_manager,
+                                    &self.peers,
+                                    &self.authenticated,
+                                    ip,
+                                    Misbehavior::InvalidBlock,
+                                );
+                            }
                             break;
                         }
                     }
+                    self.events.chain_updated(&*self.database.lock().unwrap());
                 }
             }
         }
@@ -150,45 +348,224 @@ impl Network {
         Ok(())
     }
 
-    fn connect_and_get_info(
-        &self,
-        peer_addr: String,
-        peers: Arc<Mutex<HashMap<String, NodeClient<Channel>>>>,
-    ) -> Pin<Box<dyn Future<Output = (Vec<String>, u32)> + Send>> {
+    /// Connects to a node, authenticates the connection if peers are
+    /// authenticated, handshakes and learns the addresses of its peers.
+    /// Returns the block height of the node on success.
+    async fn connect_peer(&self, peer_addr: String) -> Option<u32> {
         let version = self.database.lock().unwrap().get_version();
         let block_height = self.database.lock().unwrap().block_height() as u32;
-        let server_address = format!("[::1]:{}", self.port);
+        let server_address = server_address(&self.config);
+        let network_id = self.config.chain_params.network_id;
 
-        Box::pin(async move {
-            let mut new_peers = vec![];
-            let mut peer_block_height = 0;
+        let Ok(mut client) = connect(&peer_addr, self.tls.as_ref()).await else {
+            log::warn!("Failed to connect to {}.", &peer_addr);
+            self.address_manager
+                .lock()
+                .unwrap()
+                .mark_failure(&peer_addr, unix_timestamp());
+            return None;
+        };
+        log::debug!("Connected to {}.", peer_addr);
 
-            if let Ok(mut client) = NodeClient::connect(peer_addr.clone()).await {
-                log::debug!("Connected to {}.", peer_addr);
+        if let Some(identity) = &self.identity {
+            if let Err(err) = authenticate(&mut client, identity, &self.config).await {
+                log::warn!("Failed to authenticate {}: {}", peer_addr, err);
+                self.address_manager
+                    .lock()
+                    .unwrap()
+                    .mark_failure(&peer_addr, unix_timestamp());
+                return None;
+            }
+        }
 
-                if let Ok(resp) = client
-                    .handshake(Request::new(HandshakeMessage {
-                        version,
-                        block_height,
-                        server_address,
-                    }))
-                    .await
-                {
-                    peers.lock().unwrap().insert(peer_addr, client.clone());
-                    peer_block_height = resp.get_ref().block_height;
+        let handshake = HandshakeMessage {
+            version,
+            block_height,
+            server_address,
+            network_id,
+        };
+        let bytes_sent = handshake.encoded_len();
+        let resp = client.handshake(Request::new(handshake)).await;
+        let mut peer = match resp {
+            Ok(resp) if resp.get_ref().network_id == network_id => {
+                let resp = resp.into_inner();
+                let mut peer = Peer::new(
+                    client.clone(),
+                    true,
+                    resp.version.clone(),
+                    resp.block_height,
+                    unix_timestamp(),
+                );
+                peer.record_success(bytes_sent, resp.encoded_len());
+                peer
+            }
+            Ok(_) => {
+                log::warn!("Peer {} belongs to another network.", peer_addr);
+                self.address_manager
+                    .lock()
+                    .unwrap()
+                    .mark_failure(&peer_addr, unix_timestamp());
+                return None;
+            }
+            Err(status) => {
+                log::warn!("Handshake with {} failed: {}", peer_addr, status.message());
+                self.address_manager
+                    .lock()
+                    .unwrap()
+                    .mark_failure(&peer_addr, unix_timestamp());
+                return None;
+            }
+        };
 
-                    if let Ok(peer_list) = client.get_peer_list(proto_node::None {}).await {
-                        for p in peer_list.get_ref().peers.clone() {
-                            new_peers.push(p.to_string());
-                        }
-                    }
+        self.address_manager
+            .lock()
+            .unwrap()
+            .mark_success(&peer_addr, unix_timestamp());
+
+        let peer_list = client.get_peer_list(proto_node::None {}).await;
+        if let Ok(peer_list) = &peer_list {
+            peer.record_success(0, peer_list.get_ref().encoded_len());
+        }
+        let peer_block_height = peer.block_height;
+        self.peers.lock().unwrap().insert(peer_addr.clone(), peer);
+
+        if let Ok(peer_list) = peer_list {
+            let mut address_manager = self.address_manager.lock().unwrap();
+            for address in peer_list.get_ref().peers.iter() {
+                if self.blocked_peers.contains(&normalize_address(address)) {
+                    continue;
+                }
+                if address_manager.add(address) {
+                    log::debug!("Learned peer address {} from {}.", address, peer_addr);
                 }
-            } else {
-                log::warn!("Failed to connect to {}.", &peer_addr);
             }
+        }
 
-            return (new_peers, peer_block_height);
-        })
+        Some(peer_block_height)
+    }
+
+    /// Selects known addresses to connect to, which are neither connected,
+    /// our own, banned, nor contained in `skip`.
+    fn select_addresses(&self, count: usize, skip: &[String]) -> Vec<String> {
+        let now = unix_timestamp();
+        let peers = self.peers.lock().unwrap();
+        let ban_manager = self.ban_manager.lock().unwrap();
+        self.address_manager
+            .lock()
+            .unwrap()
+            .select(count, now, |address| {
+                peers.contains_key(address)
+                    || self.blocked_peers.iter().any(|x| x == address)
+                    || skip.iter().any(|x| x == address)
+                    || peer_ip(address).is_some_and(|ip| ban_manager.is_banned(ip, now))
+            })
+    }
+
+    fn outbound_count(&self) -> usize {
+        self.peers
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|peer| peer.outbound)
+            .count()
+    }
+
+    fn save_addresses(&self) {
+        self.address_manager
+            .lock()
+            .unwrap()
+            .save(&self.config.network.peers_path);
+    }
+
+    /// Keeps the number of outbound connections at the target by connecting to
+    /// known addresses. Failed addresses are retried with a growing delay.
+    pub async fn maintain_connections(&self) {
+        let target = self.config.network.target_outbound_peers;
+        loop {
+            tokio::time::sleep(CONNECTION_CHECK_INTERVAL).await;
+            self.ban_manager.lock().unwrap().prune(unix_timestamp());
+
+            let missing = target.saturating_sub(self.outbound_count());
+            for address in self.select_addresses(missing, &[]) {
+                log::debug!(
+                    "Connecting to {} to reach {} outbound peers.",
+                    address,
+                    target
+                );
+                self.connect_peer(address).await;
+            }
+            self.save_addresses();
+        }
+    }
+
+    /// Pings the peers periodically to measure their latency and disconnects
+    /// the ones which stopped answering. Their addresses are retried later
+    /// with the back-off of the address manager.
+    pub async fn monitor_peers(&self) {
+        loop {
+            tokio::time::sleep(PING_INTERVAL).await;
+            self.ping_peers().await;
+            self.remove_dead_peers();
+        }
+    }
+
+    /// Pings the peers, recording their latency or the failure.
+    async fn ping_peers(&self) {
+        let connected: Vec<(String, Peer)> = self
+            .peers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(address, peer)| (address.clone(), peer.clone()))
+            .collect();
+        for (address, mut peer) in connected {
+            let ping = PingMessage {
+                nonce: rand::random(),
+                block_height: self.database.lock().unwrap().block_height() as u32,
+            };
+            let start = Instant::now();
+            let resp =
+                tokio::time::timeout(PING_TIMEOUT, peer.client.ping(Request::new(ping.clone())))
+                    .await;
+            let latency = start.elapsed();
+
+            let mut peers = self.peers.lock().unwrap();
+            let Some(peer) = peers.get_mut(&address) else {
+                continue;
+            };
+            match resp {
+                Ok(Ok(pong)) if pong.get_ref().nonce == ping.nonce => {
+                    peer.latency = Some(latency);
+                    peer.block_height = pong.get_ref().block_height;
+                    peer.record_success(ping.encoded_len(), pong.get_ref().encoded_len());
+                }
+                _ => {
+                    log::debug!("Peer {} did not answer the ping.", address);
+                    peer.record_failure();
+                }
+            }
+        }
+    }
+
+    fn remove_dead_peers(&self) {
+        let mut peers = self.peers.lock().unwrap();
+        let dead: Vec<String> = peers
+            .iter()
+            .filter(|(_, peer)| peer.is_dead())
+            .map(|(address, _)| address.clone())
+            .collect();
+        for address in dead {
+            log::info!("Disconnected from unresponsive peer {}.", address);
+            peers.remove(&address);
+            self.authenticated
+                .lock()
+                .unwrap()
+                .retain(|_, peer_address| *peer_address != address);
+            self.address_manager
+                .lock()
+                .unwrap()
+                .mark_failure(&address, unix_timestamp());
+        }
     }
 
     pub async fn wait_on_publish_block(&self) {
@@ -209,47 +586,550 @@ impl Network {
     }
 
     pub async fn publish_block(&self, block: &crate::block::Block) -> Result<(), String> {
-        if let Ok(block_json) = serde_json::to_string(block) {
-            let mut peers = self.peers.lock().unwrap().clone();
-            for (_, client) in peers.iter_mut() {
-                let _ = client
-                    .add_block(Request::new(Block {
-                        block_json: block_json.clone(),
-                    }))
-                    .await;
+        broadcast_block(
+            &self.peers,
+            &self.relayed_blocks,
+            block,
+            &server_address(&self.config),
+        )
+        .await
+    }
+}
+
+/// Address under which other nodes reach our server.
+fn server_address(config: &Config) -> String {
+    format!("[::1]:{}", config.port())
+}
+
+/// Announces the block as compact block to the peers. It is kept, so that
+/// the peers can request the transactions they are missing.
+async fn broadcast_block(
+    peers: &Mutex<HashMap<String, Peer>>,
+    relayed_blocks: &Mutex<VecDeque<crate::block::Block>>,
+    block: &crate::block::Block,
+    sender_address: &str,
+) -> Result<(), String> {
+    {
+        let mut relayed_blocks = relayed_blocks.lock().unwrap();
+        if relayed_blocks.len() >= MAX_RELAYED_BLOCKS {
+            relayed_blocks.pop_front();
+        }
+        relayed_blocks.push_back(block.clone());
+    }
+
+    if let Ok(block_json) = serde_json::to_string(&CompactBlock::from_block(block)) {
+        let message = BlockAnnouncement {
+            block_json,
+            sender_address: sender_address.to_string(),
+        };
+        let mut connected = peers.lock().unwrap().clone();
+        for (address, peer) in connected.iter_mut() {
+            let resp = peer
+                .client
+                .add_compact_block(Request::new(message.clone()))
+                .await;
+            if let Err(status) = &resp {
+                log::warn!("Failed to send block to {}: {}", address, status.message());
             }
+            record_request(peers, address, resp.map(|_| 0), message.encoded_len());
+        }
 
-            return Ok(());
+        return Ok(());
+    }
+    Err("Failed to serialize block to json.".to_string())
+}
+
+/// Records the outcome of a request to a peer, given the size of the answer on
+/// success, so that unresponsive peers are eventually disconnected.
+fn record_request<E>(
+    peers: &Mutex<HashMap<String, Peer>>,
+    address: &str,
+    result: Result<usize, E>,
+    bytes_sent: usize,
+) {
+    if let Some(peer) = peers.lock().unwrap().get_mut(address) {
+        match result {
+            Ok(bytes_received) => peer.record_success(bytes_sent, bytes_received),
+            Err(_) => peer.record_failure(),
         }
-        Err("Failed to serialize block to json.".to_string())
     }
 }
 
-struct NetworkServer {
-    peers: Arc<Mutex<HashMap<String, NodeClient<Channel>>>>,
+/// Adds the misbehavior to the ban score of the peer and disconnects it, once it is banned.
+fn misbehaving(
+    ban_manager: &Mutex<BanManager>,
+    peers: &Mutex<HashMap<String, Peer>>,
+    authenticated: &Mutex<HashMap<SocketAddr, String>>,
+    ip: IpAddr,
+    misbehavior: Misbehavior,
+) {
+    let banned = ban_manager
+        .lock()
+        .unwrap()
+        .misbehaving(ip, misbehavior, unix_timestamp());
+    if banned {
+        disconnect(peers, authenticated, ip);
+    }
+}
+
+/// Removes the peers of the IP and forgets the authentication of their connections.
+pub(super) fn disconnect(
+    peers: &Mutex<HashMap<String, Peer>>,
+    authenticated: &Mutex<HashMap<SocketAddr, String>>,
+    ip: IpAddr,
+) {
+    peers
+        .lock()
+        .unwrap()
+        .retain(|address, _| peer_ip(address) != Some(ip));
+    authenticated
+        .lock()
+        .unwrap()
+        .retain(|addr, _| addr.ip().to_canonical() != ip);
+}
+
+#[derive(Clone)]
+pub(super) struct NetworkServer {
+    pub(super) peers: Arc<Mutex<HashMap<String, Peer>>>,
     blocked_peers: Arc<Vec<String>>,
-    database: Arc<Mutex<DatabaseType>>,
-    port: u16,
-    block_announce_tx: crossbeam::channel::Sender<crate::block::Block>,
+    address_manager: Arc<Mutex<AddressManager>>,
+    pub(super) ban_manager: Arc<Mutex<BanManager>>,
+    orphan_pool: Arc<Mutex<OrphanPool>>,
+    relayed_blocks: Arc<Mutex<VecDeque<crate::block::Block>>>,
+    tls: Option<TlsSettings>,
+    identity: Option<Arc<NodeIdentity>>,
+    /// Nonces issued to connections which have yet to authenticate.
+    challenges: Arc<Mutex<Challenges>>,
+    /// Connections which proved ownership of a trusted identity key, with the
+    /// address of the peer they belong to.
+    pub(super) authenticated: Arc<Mutex<HashMap<SocketAddr, String>>>,
+    pub(super) events: Arc<ChainEvents>,
+    pub(super) started: Instant,
+    pub(super) shutdown: watch::Receiver<bool>,
+    pub(super) database: Arc<Mutex<DatabaseType>>,
+    pub(super) config: Config,
+    pub(super) fee_estimator: Arc<Mutex<FeeEstimator>>,
+    pub(super) difficulty: Arc<AtomicU8>,
+    pub(super) block_announce_tx: crossbeam::channel::Sender<crate::block::Block>,
+    mempool_update_tx: crossbeam::channel::Sender<()>,
 }
 impl NetworkServer {
-    fn new(
-        peers: Arc<Mutex<HashMap<String, NodeClient<Channel>>>>,
-        blocked_peers: Arc<Vec<String>>,
-        database: Arc<Mutex<DatabaseType>>,
-        port: u16,
-        block_announce_tx: crossbeam::channel::Sender<crate::block::Block>,
-    ) -> Self {
+    fn new(network: &Network) -> Self {
         NetworkServer {
-            peers,
-            blocked_peers,
-            database,
-            port,
-            block_announce_tx,
+            peers: network.peers.clone(),
+            blocked_peers: network.blocked_peers.clone(),
+            address_manager: network.address_manager.clone(),
+            ban_manager: network.ban_manager.clone(),
+            orphan_pool: network.orphan_pool.clone(),
+            relayed_blocks: network.relayed_blocks.clone(),
+            tls: network.tls.clone(),
+            identity: network.identity.clone(),
+            challenges: network.challenges.clone(),
+            authenticated: network.authenticated.clone(),
+            events: network.events.clone(),
+            started: network.started,
+            shutdown: network.shutdown.subscribe(),
+            database: network.database.clone(),
+            config: network.config.clone(),
+            fee_estimator: network.fee_estimator.clone(),
+            difficulty: network.difficulty.clone(),
+            block_announce_tx: network.block_announce_tx.clone(),
+            mempool_update_tx: network.mempool_update_tx.clone(),
         }
     }
 }
 
+impl NetworkServer {
+    /// Refuses requests of banned peers.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn check_banned<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let Some(ip) = request.remote_addr().map(|x| x.ip().to_canonical()) else {
+            return Ok(());
+        };
+        if self
+            .ban_manager
+            .lock()
+            .unwrap()
+            .is_banned(ip, unix_timestamp())
+        {
+            return Err(Status::permission_denied("Peer is banned."));
+        }
+        Ok(())
+    }
+
+    /// Refuses requests of banned peers and, if peers are authenticated, the
+    /// requests over connections which were not authenticated.
+    #[allow(clippy::result_large_err)]
+    fn check_peer<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        self.check_banned(request)?;
+        if self.identity.is_none() {
+            return Ok(());
+        }
+        let authenticated = request
+            .remote_addr()
+            .is_some_and(|addr| self.authenticated.lock().unwrap().contains_key(&addr));
+        if !authenticated {
+            return Err(Status::unauthenticated("Peer is not authenticated."));
+        }
+        Ok(())
+    }
+
+    pub(super) fn misbehaving<T>(&self, request: &Request<T>, misbehavior: Misbehavior) {
+        if let Some(addr) = request.remote_addr() {
+            misbehaving(
+                &self.ban_manager,
+                &self.peers,
+                &self.authenticated,
+                addr.ip().to_canonical(),
+                misbehavior,
+            );
+        }
+    }
+
+    /// Peer administration is only available to local clients.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn check_local<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        if request
+            .remote_addr()
+            .is_some_and(|x| x.ip().to_canonical().is_loopback())
+        {
+            return Ok(());
+        }
+        Err(Status::permission_denied(
+            "Peer administration is only available locally.",
+        ))
+    }
+
+    pub(super) async fn broadcast_block(&self, block: &crate::block::Block) -> Result<(), String> {
+        broadcast_block(
+            &self.peers,
+            &self.relayed_blocks,
+            block,
+            &server_address(&self.config),
+        )
+        .await
+    }
+
+    /// Connected peer which sent the request. The sender address in the message
+    /// is not authenticated, so it only tells apart the peers sharing the IP
+    /// of the connection.
+    fn sender_peer<T>(&self, request: &Request<T>, sender_address: &str) -> Option<String> {
+        let ip = request.remote_addr()?.ip().to_canonical();
+        let sender_address = claimed_address(request, sender_address);
+        let peers = self.peers.lock().unwrap();
+        if peers.contains_key(&sender_address) {
+            return Some(sender_address);
+        }
+        peers
+            .keys()
+            .find(|address| peer_ip(address) == Some(ip))
+            .cloned()
+    }
+
+    fn peer_client(&self, address: &str) -> Option<NodeClient<Channel>> {
+        let client = self
+            .peers
+            .lock()
+            .unwrap()
+            .get(address)
+            .map(|peer| peer.client.clone());
+        if client.is_none() {
+            log::debug!("Peer {} is no longer connected.", address);
+        }
+        client
+    }
+
+    /// Reconstructs the compact block from the pending transactions, requesting
+    /// the missing ones from the sender. Falls back to the full block, if the
+    /// reconstruction does not match the block.
+    async fn complete_compact_block(&self, compact_block: CompactBlock, sender_address: String) {
+        let mut transactions = {
+            let db = self.database.lock().unwrap();
+            compact_block.reconstruct(db.get_pending_transactions())
+        };
+        let missing = CompactBlock::missing(&transactions);
+        let block_hash = compact_block.hash;
+        let Some(mut client) = self.peer_client(&sender_address) else {
+            return;
+        };
+
+        if !missing.is_empty() {
+            log::debug!(
+                "Requesting {} of {} transactions of block ({}) from {}.",
+                missing.len(),
+                transactions.len(),
+                hex::encode(block_hash.get(..5).unwrap()),
+                sender_address
+            );
+            let request = BlockTransactionsReq {
+                block_hash: block_hash.to_vec(),
+                indices: missing.clone(),
+            };
+            let bytes_sent = request.encoded_len();
+            let resp = client.get_block_transactions(Request::new(request)).await;
+            record_request(
+                &self.peers,
+                &sender_address,
+                resp.as_ref().map(|x| x.get_ref().encoded_len()),
+                bytes_sent,
+            );
+            let fetched = resp.ok().and_then(|resp| {
+                serde_json::from_str::<Vec<crate::transaction::Transaction>>(
+                    &resp.get_ref().transactions_json,
+                )
+                .ok()
+            });
+            if let Some(fetched) = fetched.filter(|x| x.len() == missing.len()) {
+                for (index, tx) in missing.into_iter().zip(fetched) {
+                    transactions[index as usize] = Some(tx);
+                }
+            }
+        }
+
+        let block = match compact_block.assemble(transactions) {
+            Some(block) => block,
+            None => {
+                log::debug!(
+                    "Failed to reconstruct block ({}), requesting the full block.",
+                    hex::encode(block_hash.get(..5).unwrap())
+                );
+                let request = BlockReq {
+                    block_height: 0,
+                    block_hash: Some(hex::encode(block_hash)),
+                };
+                let resp = client.get_block(Request::new(request)).await;
+                let block = resp.ok().and_then(|resp| {
+                    serde_json::from_str::<crate::block::Block>(&resp.get_ref().block_json).ok()
+                });
+                match block {
+                    Some(block) if block.hash == block_hash => block,
+                    _ => {
+                        log::warn!(
+                            "Peer {} did not provide the announced block.",
+                            sender_address
+                        );
+                        return;
+                    }
+                }
+            }
+        };
+        self.receive_block(block, Some(sender_address));
+    }
+
+    /// Hands the block to the blockchain thread, unless it is already known.
+    /// Blocks with unknown parents are kept as orphans and their ancestors are
+    /// requested from the sending peer, if it is connected.
+    fn receive_block(&self, block: crate::block::Block, sender: Option<String>) {
+        let (known, parent_known) = {
+            let db = self.database.lock().unwrap();
+            (
+                db.get_block(&block.hash).is_some(),
+                db.get_block(&block.header.previous_block_hash).is_some(),
+            )
+        };
+        if known || self.orphan_pool.lock().unwrap().contains(&block.hash) {
+            return;
+        }
+        if parent_known {
+            self.connect_block(block);
+            return;
+        }
+
+        let parent_hash = block.header.previous_block_hash;
+        let mut orphan_pool = self.orphan_pool.lock().unwrap();
+        // the parent is already requested for another orphan
+        let requested = orphan_pool.is_missing(&parent_hash);
+        orphan_pool.add(block, unix_timestamp());
+        let Some(sender) = sender.filter(|_| !requested) else {
+            return;
+        };
+
+        let server = self.clone();
+        tokio::spawn(async move { server.fetch_ancestors(sender, parent_hash).await });
+    }
+
+    /// Hands the block and the orphans descending from it to the blockchain thread.
+    fn connect_block(&self, block: crate::block::Block) {
+        let descendants = self
+            .orphan_pool
+            .lock()
+            .unwrap()
+            .take_descendants(&block.hash);
+        if !descendants.is_empty() {
+            log::info!(
+                "Connecting {} orphan blocks to block ({}).",
+                descendants.len(),
+                hex::encode(block.hash.get(..5).unwrap())
+            );
+        }
+        self.block_announce_tx.send(block).unwrap();
+        for block in descendants {
+            self.block_announce_tx.send(block).unwrap();
+        }
+    }
+
+    /// Requests the missing blocks from the peer one by one, until a block
+    /// connects to our chain.
+    async fn fetch_ancestors(&self, address: String, mut block_hash: HashResult) {
+        let Some(mut client) = self.peer_client(&address) else {
+            return;
+        };
+
+        for _ in 0..MAX_ORPHANS {
+            log::debug!(
+                "Requesting missing block ({}) from {}.",
+                hex::encode(block_hash.get(..5).unwrap()),
+                address
+            );
+            let request = BlockReq {
+                block_height: 0,
+                block_hash: Some(hex::encode(block_hash)),
+            };
+            let bytes_sent = request.encoded_len();
+            let resp = client.get_block(Request::new(request)).await;
+            record_request(
+                &self.peers,
+                &address,
+                resp.as_ref().map(|x| x.get_ref().encoded_len()),
+                bytes_sent,
+            );
+            let Ok(resp) = resp else {
+                log::warn!("Peer {} did not provide the missing block.", address);
+                return;
+            };
+
+            let block = serde_json::from_str::<crate::block::Block>(&resp.get_ref().block_json);
+            let block = match block {
+                Ok(block)
+                    if block.hash == block_hash
+                        && verify_proof_of_work(&block.header, &block.hash)
+                        && self.verify_difficulty(&block.header) =>
+                {
+                    block
+                }
+                _ => {
+                    if let Some(ip) = peer_ip(&address) {
+                        misbehaving(
+                            &self.ban_manager,
+                            &self.peers,
+                            &self.authenticated,
+                            ip,
+                            Misbehavior::InvalidBlock,
+                        );
+                    }
+                    return;
+                }
+            };
+
+            let parent_hash = block.header.previous_block_hash;
+            if self
+                .database
+                .lock()
+                .unwrap()
+                .get_block(&parent_hash)
+                .is_some()
+            {
+                self.connect_block(block);
+                return;
+            }
+            if !self
+                .orphan_pool
+                .lock()
+                .unwrap()
+                .add(block, unix_timestamp())
+            {
+                return;
+            }
+            block_hash = parent_hash;
+        }
+        log::warn!("Gave up fetching the missing blocks from {}.", address);
+    }
+
+    /// Checks the difficulty of a received block against the block it extends.
+    pub(super) fn verify_difficulty(&self, header: &BlockHeader) -> bool {
+        let db = self.database.lock().unwrap();
+        chain::verify_difficulty(&*db, &self.config.chain_params, header)
+    }
+
+    /// Block by its hash, or by its height if no hash is given.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn find_block(&self, request: &BlockReq) -> Result<Block, Status> {
+        let db = self.database.lock().unwrap();
+        let block = match &request.block_hash {
+            Some(block_hash) => {
+                let Ok(block_hash) =
+                    HashResult::try_from(hex::decode(block_hash).unwrap_or_default())
+                else {
+                    return Err(Status::invalid_argument("Invalid block hash."));
+                };
+                db.get_block(&block_hash)
+            }
+            None => db.get_blocks().get(request.block_height as usize).copied(),
+        };
+        let Some(block) = block else {
+            return Err(Status::not_found("Block not found."));
+        };
+        match serde_json::to_string(block) {
+            Ok(block_json) => Ok(Block { block_json }),
+            Err(_) => Err(Status::internal("Failed to encode block to JSON.")),
+        }
+    }
+
+    /// Confirmed or pending transaction by its hash, and whether it is confirmed.
+    pub(super) fn find_transaction(
+        &self,
+        tx_hash: &HashResult,
+    ) -> Option<(crate::transaction::Transaction, bool)> {
+        let db = self.database.lock().unwrap();
+        if let Some(tx) = db.get_transaction(tx_hash) {
+            return Some((tx.clone(), true));
+        }
+        db.get_pending_transactions()
+            .iter()
+            .find(|x| x.hash() == *tx_hash)
+            .map(|tx| (tx.clone(), false))
+    }
+
+    /// Validates a transaction and adds it to the pending transactions,
+    /// notifying the fee estimator, the miner and subscribers.
+    pub(super) fn accept_pending(
+        &self,
+        tx: crate::transaction::Transaction,
+    ) -> Result<(), MempoolError> {
+        let tx_hash = tx.hash();
+        let mut db = self.database.lock().unwrap();
+        let evicted = accept_transaction(&mut *db, tx.clone(), &self.config.mempool)?;
+        self.events.transaction_accepted(&tx);
+        let mut fee_estimator = self.fee_estimator.lock().unwrap();
+        for hash in evicted.iter() {
+            fee_estimator.untrack_transaction(hash);
+        }
+        fee_estimator.track_transaction(tx_hash, db.block_height());
+        let _ = self.mempool_update_tx.send(());
+        Ok(())
+    }
+
+    /// Saves the pending transactions to the configured file.
+    pub(super) fn save_mempool(&self) -> Result<usize, String> {
+        let db = self.database.lock().unwrap();
+        let transactions = db.get_pending_transactions();
+        persistence::save(&self.config.mempool.persist_path, transactions)?;
+        Ok(transactions.len())
+    }
+
+    /// Waits until the blockchain thread has added the block to the chain.
+    pub(super) async fn wait_for_head(&self, block_hash: &HashResult) -> Result<(), Status> {
+        for _ in 0..500 {
+            let head_hash = self.database.lock().unwrap().head().map(|x| x.hash);
+            if head_hash == Some(*block_hash) {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Err(Status::internal("Block was not added to the chain."))
+    }
+}
+
 #[tonic::async_trait]
 impl Node for NetworkServer {
     async fn handshake(
@@ -257,6 +1137,18 @@ impl Node for NetworkServer {
         request: Request<HandshakeMessage>,
     ) -> Result<Response<HandshakeMessage>, Status> {
         const VERSION: &str = env!("CARGO_PKG_VERSION");
+        self.check_peer(&request)?;
+        if request.get_ref().network_id != self.config.chain_params.network_id {
+            log::debug!(
+                "Refused handshake of {} from another network.",
+                request.get_ref().server_address
+            );
+            self.misbehaving(&request, Misbehavior::ProtocolViolation);
+            return Err(Status::failed_precondition(format!(
+                "Node belongs to {}.",
+                self.config.chain_params.name
+            )));
+        }
         let block_height = self.database.lock().unwrap().block_height() as u32;
 
         let our_version = semver::Version::parse(VERSION).unwrap();
@@ -273,44 +1165,130 @@ impl Node for NetworkServer {
             log::warn!(
                 "Peer client is running an invalid version ({}).",
                 &request.get_ref().version,
-            )
+            );
+            self.misbehaving(&request, Misbehavior::ProtocolViolation);
         }
 
         let reply = HandshakeMessage {
             version: VERSION.to_string(),
             block_height,
-            server_address: format!("[::1]:{}", self.port),
+            server_address: server_address(&self.config),
+            network_id: self.config.chain_params.network_id,
         };
 
-        let peer_address = request.get_ref().server_address.to_string();
+        let peer_address = claimed_address(&request, &request.get_ref().server_address);
         let contained = self.peers.lock().unwrap().contains_key(&peer_address);
         if !contained && !self.blocked_peers.contains(&peer_address) {
-            if let Ok(client) =
-                NodeClient::connect(format!("http://{}", peer_address.clone())).await
-            {
-                log::debug!("Added a connection to a new peer {}.", peer_address);
-                self.peers
-                    .lock()
-                    .unwrap()
-                    .insert(peer_address, client.clone());
-
-                // let peers = self.peers.clone();
-                // let blocked_peers = self.blocked_peers.clone();
-                // tokio::spawn(
-                //     async move { Network::explore_peers(peers, blocked_peers, client).await },
-                // );
-            } else {
-                log::warn!("Failed to connect to peer {}.", peer_address);
+            self.address_manager.lock().unwrap().add(&peer_address);
+            let client = match connect(&peer_address, self.tls.as_ref()).await {
+                Ok(mut client) => match &self.identity {
+                    Some(identity) => authenticate(&mut client, identity, &self.config)
+                        .await
+                        .map(|_| client),
+                    None => Ok(client),
+                },
+                Err(err) => Err(err.to_string()),
+            };
+            match client {
+                Ok(client) => {
+                    log::debug!("Added a connection to a new peer {}.", peer_address);
+                    self.address_manager
+                        .lock()
+                        .unwrap()
+                        .mark_success(&peer_address, unix_timestamp());
+                    let HandshakeMessage {
+                        version,
+                        block_height,
+                        ..
+                    } = request.get_ref();
+                    self.peers.lock().unwrap().insert(
+                        peer_address,
+                        Peer::new(
+                            client,
+                            false,
+                            version.clone(),
+                            *block_height,
+                            unix_timestamp(),
+                        ),
+                    );
+                }
+                Err(err) => {
+                    log::warn!("Failed to connect to peer {}: {}", peer_address, err);
+                    self.address_manager
+                        .lock()
+                        .unwrap()
+                        .mark_failure(&peer_address, unix_timestamp());
+                }
             }
         }
 
         Ok(Response::new(reply))
     }
 
+    async fn get_challenge(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<Challenge>, Status> {
+        self.check_banned(&request)?;
+        if self.identity.is_none() {
+            return Err(Status::failed_precondition("Peers are not authenticated."));
+        }
+        let Some(addr) = request.remote_addr() else {
+            return Err(Status::failed_precondition(
+                "Connection has no remote address.",
+            ));
+        };
+        let nonce = rand::random::<[u8; 32]>().to_vec();
+        let now = unix_timestamp();
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, (_, issued)| now.saturating_sub(*issued) <= MAX_HANDSHAKE_AGE_SECS);
+        challenges.insert(addr, (nonce.clone(), now));
+        Ok(Response::new(Challenge { nonce }))
+    }
+
+    async fn authenticate(
+        &self,
+        request: Request<AuthMessage>,
+    ) -> Result<Response<AuthMessage>, Status> {
+        self.check_banned(&request)?;
+        let Some(identity) = &self.identity else {
+            return Err(Status::failed_precondition("Peers are not authenticated."));
+        };
+        // a nonce is only valid once and on the connection it was issued on
+        let now = unix_timestamp();
+        let challenge = request
+            .remote_addr()
+            .and_then(|addr| self.challenges.lock().unwrap().remove(&addr))
+            .filter(|(_, issued)| now.saturating_sub(*issued) <= MAX_HANDSHAKE_AGE_SECS);
+        let (Some(addr), Some((nonce, _))) = (request.remote_addr(), challenge) else {
+            return Err(Status::unauthenticated(
+                "No challenge was issued for the connection.",
+            ));
+        };
+        if let Err(err) = identity.verify(request.get_ref(), &nonce, now) {
+            log::debug!("Refused authentication of {}: {}", addr, err);
+            return Err(Status::unauthenticated(err));
+        }
+        let peer_address = claimed_address(&request, &request.get_ref().server_address);
+        self.authenticated
+            .lock()
+            .unwrap()
+            .insert(addr, peer_address);
+
+        let mut reply = AuthMessage {
+            server_address: server_address(&self.config),
+            network_id: self.config.chain_params.network_id,
+            ..Default::default()
+        };
+        identity.sign(&mut reply, now, &request.get_ref().signature);
+        Ok(Response::new(reply))
+    }
+
     async fn get_peer_list(
         &self,
-        _request: Request<proto_node::None>,
+        request: Request<proto_node::None>,
     ) -> Result<Response<PeerList>, Status> {
+        self.check_peer(&request)?;
         let peers: Vec<String> = self
             .peers
             .lock()
@@ -323,28 +1301,164 @@ impl Node for NetworkServer {
         Ok(Response::new(PeerList { peers }))
     }
 
+    async fn ping(&self, request: Request<PingMessage>) -> Result<Response<PingMessage>, Status> {
+        self.check_peer(&request)?;
+        Ok(Response::new(PingMessage {
+            nonce: request.get_ref().nonce,
+            block_height: self.database.lock().unwrap().block_height() as u32,
+        }))
+    }
+
     async fn add_block(
         &self,
-        request: Request<Block>,
+        request: Request<BlockAnnouncement>,
     ) -> Result<Response<proto_node::None>, Status> {
-        if let Ok(block) =
-            serde_json::from_str::<crate::block::Block>(&request.get_ref().block_json)
+        self.check_peer(&request)?;
+        let Ok(block) = serde_json::from_str::<crate::block::Block>(&request.get_ref().block_json)
+        else {
+            metrics().validation_failed("block", "malformed");
+            self.misbehaving(&request, Misbehavior::MalformedMessage);
+            return Err(Status::invalid_argument("Failed to decode block."));
+        };
+        if !verify_proof_of_work(&block.header, &block.hash) {
+            metrics().validation_failed("block", "invalid_pow");
+            self.misbehaving(&request, Misbehavior::InvalidBlock);
+            return Err(Status::failed_precondition(
+                "Block hash does not meet its difficulty.",
+            ));
+        }
+        if !self.verify_difficulty(&block.header) {
+            metrics().validation_failed("block", "invalid_difficulty");
+            self.misbehaving(&request, Misbehavior::InvalidBlock);
+            return Err(Status::failed_precondition(
+                "Block difficulty does not follow the chain.",
+            ));
+        }
+
+        log::debug!(
+            "Received a block {} from the network!",
+            hex::encode(block.hash.get(..5).unwrap())
+        );
+        let sender = self.sender_peer(&request, &request.get_ref().sender_address);
+        self.receive_block(block, sender);
+        Ok(Response::new(proto_node::None {}))
+    }
+
+    async fn add_compact_block(
+        &self,
+        request: Request<BlockAnnouncement>,
+    ) -> Result<Response<proto_node::None>, Status> {
+        self.check_peer(&request)?;
+        let Ok(compact_block) = serde_json::from_str::<CompactBlock>(&request.get_ref().block_json)
+        else {
+            metrics().validation_failed("block", "malformed");
+            self.misbehaving(&request, Misbehavior::MalformedMessage);
+            return Err(Status::invalid_argument("Failed to decode compact block."));
+        };
+        if !verify_proof_of_work(&compact_block.header, &compact_block.hash) {
+            metrics().validation_failed("block", "invalid_pow");
+            self.misbehaving(&request, Misbehavior::InvalidBlock);
+            return Err(Status::failed_precondition(
+                "Block hash does not meet its difficulty.",
+            ));
+        }
+        if !self.verify_difficulty(&compact_block.header) {
+            metrics().validation_failed("block", "invalid_difficulty");
+            self.misbehaving(&request, Misbehavior::InvalidBlock);
+            return Err(Status::failed_precondition(
+                "Block difficulty does not follow the chain.",
+            ));
+        }
+
+        let known = self
+            .database
+            .lock()
+            .unwrap()
+            .get_block(&compact_block.hash)
+            .is_some();
+        if known
+            || self
+                .orphan_pool
+                .lock()
+                .unwrap()
+                .contains(&compact_block.hash)
         {
-            log::debug!(
-                "Received a block {} from the network!",
-                hex::encode(block.hash.get(..5).unwrap())
-            );
-            self.block_announce_tx.send(block).unwrap();
+            return Ok(Response::new(proto_node::None {}));
         }
+        log::debug!(
+            "Received a compact block {} from the network!",
+            hex::encode(compact_block.hash.get(..5).unwrap())
+        );
+
+        let Some(sender_address) = self.sender_peer(&request, &request.get_ref().sender_address)
+        else {
+            log::debug!("Ignored a compact block of a node which is not a peer.");
+            return Ok(Response::new(proto_node::None {}));
+        };
+        // the sender is asked for missing transactions without holding up its announcement
+        let server = self.clone();
+        tokio::spawn(async move {
+            server
+                .complete_compact_block(compact_block, sender_address)
+                .await
+        });
         Ok(Response::new(proto_node::None {}))
     }
 
+    async fn get_block_transactions(
+        &self,
+        request: Request<BlockTransactionsReq>,
+    ) -> Result<Response<BlockTransactions>, Status> {
+        self.check_peer(&request)?;
+        let BlockTransactionsReq {
+            block_hash,
+            indices,
+        } = request.into_inner();
+        let Ok(block_hash) = HashResult::try_from(block_hash) else {
+            return Err(Status::invalid_argument("Invalid block hash."));
+        };
+
+        let relayed = self
+            .relayed_blocks
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|block| block.hash == block_hash)
+            .cloned();
+        let block = relayed.or_else(|| {
+            self.database
+                .lock()
+                .unwrap()
+                .get_block(&block_hash)
+                .cloned()
+        });
+        let Some(block) = block else {
+            return Err(Status::not_found("Block not found."));
+        };
+
+        let transactions: Option<Vec<&crate::transaction::Transaction>> = indices
+            .iter()
+            .map(|index| block.transactions.get(*index as usize))
+            .collect();
+        let Some(transactions) = transactions else {
+            return Err(Status::invalid_argument("Transaction index out of range."));
+        };
+        match serde_json::to_string(&transactions) {
+            Ok(transactions_json) => Ok(Response::new(BlockTransactions { transactions_json })),
+            Err(_) => Err(Status::internal("Failed to encode transactions to JSON.")),
+        }
+    }
+
     async fn get_block(&self, request: Request<BlockReq>) -> Result<Response<Block>, Status> {
-        log::debug!("get_block: {:?}", request);
-        todo!()
+        self.check_peer(&request)?;
+        self.find_block(request.get_ref()).map(Response::new)
     }
 
-    async fn get_chain(&self, _: Request<proto_node::None>) -> Result<Response<Chain>, Status> {
+    async fn get_chain(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<Chain>, Status> {
+        self.check_peer(&request)?;
         let db = self.database.lock().unwrap();
         let blocks = db.get_blocks();
         if let Ok(json) = serde_json::to_string(&blocks) {
@@ -356,43 +1470,348 @@ impl Node for NetworkServer {
             ))
         }
     }
+}
 
-    async fn add_transaction(
-        &self,
-        request: Request<Transaction>,
-    ) -> Result<Response<proto_node::None>, Status> {
-        if let Ok(tx) = serde_json::from_str::<crate::transaction::Transaction>(
-            request.get_ref().tx_json.as_str(),
-        ) {
-            log::debug!("tx_hash={:?}", hex::encode(tx.hash()));
-            self.database.lock().unwrap().add_pending_transaction(tx);
-            return Ok(Response::new(proto_node::None {}));
+/// Authenticates the connection of the client: signs the nonce the peer issued
+/// for it and checks that the peer proves a trusted identity in turn.
+async fn authenticate(
+    client: &mut NodeClient<Channel>,
+    identity: &NodeIdentity,
+    config: &Config,
+) -> Result<(), String> {
+    let nonce = client
+        .get_challenge(Request::new(proto_node::None {}))
+        .await
+        .map_err(|status| status.message().to_string())?
+        .into_inner()
+        .nonce;
+    let mut request = AuthMessage {
+        server_address: server_address(config),
+        network_id: config.chain_params.network_id,
+        ..Default::default()
+    };
+    identity.sign(&mut request, unix_timestamp(), &nonce);
+    let challenge = request.signature.clone();
+    let response = client
+        .authenticate(Request::new(request))
+        .await
+        .map_err(|status| status.message().to_string())?;
+    identity.verify(response.get_ref(), &challenge, unix_timestamp())
+}
+
+/// Address of the server of the node which sent the request. Only the port
+/// is taken from the address it claims, the IP is the one of the connection.
+fn claimed_address<T>(request: &Request<T>, address: &str) -> String {
+    let port = address
+        .rsplit(':')
+        .next()
+        .and_then(|port| port.trim_end_matches('/').parse::<u16>().ok());
+    match (request.remote_addr(), port) {
+        (Some(remote_addr), Some(port)) => {
+            normalize_address(&SocketAddr::new(remote_addr.ip().to_canonical(), port).to_string())
         }
-        Err(Status::invalid_argument("Failed to decode transaction."))
+        _ => normalize_address(address),
     }
+}
 
-    async fn get_transaction(
-        &self,
-        request: Request<TransactionReq>,
-    ) -> Result<Response<Transaction>, Status> {
-        log::debug!("get_tranction: {:?}", request);
-        todo!()
+/// Completes once the node is shutting down.
+pub(super) async fn stopped(shutdown: &mut watch::Receiver<bool>) {
+    let _ = shutdown.wait_for(|stopping| *stopping).await;
+}
+
+pub(super) fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::sync::atomic::AtomicU8;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tonic::transport::server::TcpConnectInfo;
+    use tonic::transport::Endpoint;
+    use tonic::{Code, Request};
+
+    use super::{unix_timestamp, Network, NetworkServer, Node, NodeServer, Server};
+    use crate::config::loader::load_toml;
+    use crate::config::models::AccountConfig;
+    use crate::crypto::account::Account;
+    use crate::database::{database::Database, InMemoryDatabase};
+    use crate::mempool::fee_estimator::FeeEstimator;
+    use crate::network::auth::NodeIdentity;
+    use crate::network::peer::{Peer, MAX_FAILED_REQUESTS};
+    use crate::network::rpc::connect_rpc;
+    use crate::proto::proto_node::node_client::NodeClient;
+    use crate::proto::proto_node::{AuthMessage, HandshakeMessage, None};
+    use crate::transaction::builder::TransactionBuilder;
+
+    /// Regtest node without peers, whose files are kept in the temp dir under the name.
+    /// The blocks handed to the blockchain thread are received from the channel.
+    pub(in crate::network) fn regtest_network(
+        name: &str,
+    ) -> (Network, crossbeam::channel::Receiver<crate::block::Block>) {
+        let mut config = load_toml("./configs/regtest.toml");
+        let dir = std::env::temp_dir();
+        let path = |file: &str| {
+            let path = dir.join(format!("{}_{}_{}", name, std::process::id(), file));
+            path.to_str().unwrap().to_string()
+        };
+        config.network.peers_path = path("peers.json");
+        config.mempool.persist_path = path("mempool.json");
+
+        let mut database = InMemoryDatabase::default();
+        database.create_genesis_block(config.chain_params.genesis_block());
+        let (block_announce_tx, block_announce_rx) = crossbeam::channel::unbounded();
+        let network = Network::new(
+            config.clone(),
+            Arc::new(Mutex::new(database)),
+            Arc::new(Mutex::new(FeeEstimator::new(100, 1_000_000))),
+            Arc::new(AtomicU8::new(config.chain_params.start_difficulty_bit)),
+            block_announce_tx,
+            crossbeam::channel::unbounded().1,
+            crossbeam::channel::unbounded().0,
+        );
+        (network, block_announce_rx)
     }
 
-    async fn get_utxo(
-        &self,
-        request: Request<PublicKey>,
-    ) -> Result<Response<UnspentOutputs>, Status> {
-        let public_key = request.into_inner().key as Address;
-        let utxos = self.database.lock().unwrap().get_utxo(&public_key);
-        let mut unspent_outputs = vec![];
-        for utxo in &utxos {
-            unspent_outputs.push(UnspentOutput {
-                previous_transaction_hash: utxo.0.to_vec(),
-                previous_transaction_output_index: utxo.1,
-                amount: utxo.2,
-            });
+    pub(in crate::network) fn server(network: &Network) -> NetworkServer {
+        NetworkServer::new(network)
+    }
+
+    #[tokio::test]
+    async fn test_ping_peers() {
+        // a node answering pings and an address nothing listens on
+        let (alive, _) = regtest_network("node_ping_alive");
+        let port = std::net::TcpListener::bind("[::1]:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = Server::builder()
+            .add_service(NodeServer::new(server(&alive)))
+            .serve(format!("[::1]:{}", port).parse().unwrap());
+        tokio::spawn(server);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (network, _) = regtest_network("node_ping_peers");
+        let alive_address = format!("http://[::1]:{}", port);
+        for address in [alive_address.clone(), "http://[::1]:1".to_string()] {
+            let endpoint = Endpoint::from_shared(address.clone()).unwrap();
+            let peer = Peer::new(
+                NodeClient::new(endpoint.connect_lazy()),
+                true,
+                String::new(),
+                0,
+                0,
+            );
+            network.peers.lock().unwrap().insert(address, peer);
+        }
+
+        for _ in 0..MAX_FAILED_REQUESTS {
+            network.ping_peers().await;
+            network.remove_dead_peers();
+        }
+        let peers = network.peers.lock().unwrap();
+        assert_eq!(peers.keys().collect::<Vec<_>>(), vec![&alive_address]);
+        let peer = &peers[&alive_address];
+        assert!(peer.latency.is_some());
+        assert_eq!(peer.block_height, 1);
+        assert_eq!(peer.failed_requests, 0);
+    }
+
+    /// Request as received over a connection from the address.
+    fn request_from<T>(address: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(address.parse().unwrap()),
+        });
+        request
+    }
+
+    #[tokio::test]
+    async fn test_sender_peer() {
+        let (network, _) = regtest_network("node_sender_peer");
+        let server = server(&network);
+        for address in [
+            "http://[::1]:18444",
+            "http://[::1]:18445",
+            "http://10.0.0.1:18444",
+        ] {
+            let client = NodeClient::new(Endpoint::from_static(address).connect_lazy());
+            let peer = Peer::new(client, true, String::new(), 0, 0);
+            server
+                .peers
+                .lock()
+                .unwrap()
+                .insert(address.to_string(), peer);
         }
-        Ok(Response::new(UnspentOutputs { unspent_outputs }))
+
+        // the claimed address tells apart the peers of the same host
+        let request = request_from("[::1]:50000", ());
+        assert_eq!(
+            server.sender_peer(&request, "[::1]:18445"),
+            Some("http://[::1]:18445".to_string())
+        );
+        // but cannot point to another host
+        let request = request_from("10.0.0.1:50000", ());
+        assert_eq!(
+            server.sender_peer(&request, "[::1]:18445"),
+            Some("http://10.0.0.1:18444".to_string())
+        );
+        let request = request_from("10.0.0.2:50000", ());
+        assert_eq!(server.sender_peer(&request, "10.0.0.1:18444"), None);
+        assert_eq!(server.sender_peer(&Request::new(()), "[::1]:18444"), None);
+    }
+
+    #[tokio::test]
+    async fn test_authentication() {
+        let identity = |seed: u8| {
+            let config = AccountConfig {
+                keys_path: String::new(),
+            };
+            let account = Account::from_seed(config, &[seed; 32]).unwrap();
+            Arc::new(NodeIdentity::new(account, vec![]))
+        };
+        let (mut network, _) = regtest_network("node_authentication");
+        network.identity = Some(identity(1));
+        let server = server(&network);
+        let peer = identity(2);
+        let network_id = network.config.chain_params.network_id;
+        let connection = "[::1]:50000";
+
+        // handshakes over connections which were not authenticated are refused
+        let handshake = HandshakeMessage {
+            network_id,
+            ..Default::default()
+        };
+        let status = server
+            .handshake(request_from(connection, handshake))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let nonce = server
+            .get_challenge(request_from(connection, None {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .nonce;
+        let mut message = AuthMessage {
+            server_address: "[::1]:18444".to_string(),
+            network_id,
+            ..Default::default()
+        };
+        peer.sign(&mut message, unix_timestamp(), &nonce);
+        let reply = server
+            .authenticate(request_from(connection, message.clone()))
+            .await
+            .unwrap();
+        assert!(peer
+            .verify(reply.get_ref(), &message.signature, unix_timestamp())
+            .is_ok());
+        assert!(server.check_peer(&request_from(connection, ())).is_ok());
+
+        // the authentication holds for the connection only and cannot be replayed
+        let other = "[::1]:50001";
+        assert!(server.check_peer(&request_from(other, ())).is_err());
+        server
+            .get_challenge(request_from(other, None {}))
+            .await
+            .unwrap();
+        let status = server
+            .authenticate(request_from(other, message.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert!(server.check_peer(&request_from(other, ())).is_err());
+        let status = server
+            .authenticate(request_from(connection, message))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // and ends once the peer is removed
+        let client = NodeClient::new(Endpoint::from_static("http://[::1]:18444").connect_lazy());
+        let mut dead = Peer::new(client, false, String::new(), 0, 0);
+        dead.failed_requests = MAX_FAILED_REQUESTS;
+        network
+            .peers
+            .lock()
+            .unwrap()
+            .insert("http://[::1]:18444".to_string(), dead);
+        network.remove_dead_peers();
+        assert!(server.check_peer(&request_from(connection, ())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let funding = TransactionBuilder::new(&account)
+            .add_input([9u8; 32], 0, 10_000)
+            .add_output(account.public_key(), 10_000)
+            .build()
+            .unwrap();
+        let fund = |network: &Network| {
+            let mut db = network.database.lock().unwrap();
+            db.add_transaction(funding.hash(), funding.clone());
+            db.add_utxo(funding.hash(), 0);
+        };
+
+        let (mut network, _) = regtest_network("node_shutdown");
+        let port = std::net::TcpListener::bind("[::1]:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        network.config.rpc.bind_address = format!("[::1]:{}", port);
+        fund(&network);
+        let tx = TransactionBuilder::new(&account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 9_000)
+            .build()
+            .unwrap();
+        server(&network).accept_pending(tx.clone()).unwrap();
+
+        let network = Arc::new(network);
+        let network_cloned = network.clone();
+        let rpc_server =
+            tokio::spawn(async move { network_cloned.start_rpc_server().await.is_ok() });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut client = connect_rpc(&format!("http://[::1]:{}", port), None)
+            .await
+            .unwrap();
+        let mut blocks = client
+            .subscribe_blocks(Request::new(None {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // the server stops and the subscribers are told why
+        network.shutdown();
+        let status = blocks.message().await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        let stopped = tokio::time::timeout(Duration::from_secs(5), rpc_server).await;
+        assert!(stopped.unwrap().unwrap());
+
+        // the pending transactions are back after a restart
+        network.persist();
+        let (restarted, _) = regtest_network("node_shutdown");
+        fund(&restarted);
+        restarted.load_mempool();
+        let db = restarted.database.lock().unwrap();
+        let pending: Vec<_> = db
+            .get_pending_transactions()
+            .iter()
+            .map(|tx| tx.hash())
+            .collect();
+        assert_eq!(pending, vec![tx.hash()]);
     }
 }
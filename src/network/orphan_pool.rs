@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use crate::{block::Block, crypto::hash_utils::HashResult};
+
+/// Maximum number of orphan blocks kept, the oldest are evicted first.
+pub const MAX_ORPHANS: usize = 100;
+/// Orphan blocks whose parents did not arrive within this time are dropped.
+pub const ORPHAN_EXPIRY_SECS: u64 = 20 * 60;
+
+struct Orphan {
+    block: Block,
+    /// Unix timestamp at which the block was received.
+    received: u64,
+}
+
+/// Blocks received before their parents, which are connected to the chain
+/// once the missing parents arrive.
+#[derive(Default)]
+pub struct OrphanPool {
+    orphans: HashMap<HashResult, Orphan>,
+}
+
+impl OrphanPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an orphan block, evicting expired blocks and, if the pool is full,
+    /// the oldest one. Returns whether the block was added.
+    pub fn add(&mut self, block: Block, now: u64) -> bool {
+        if self.orphans.contains_key(&block.hash) {
+            return false;
+        }
+        self.orphans
+            .retain(|_, orphan| now < orphan.received + ORPHAN_EXPIRY_SECS);
+        if self.orphans.len() >= MAX_ORPHANS {
+            let oldest = self
+                .orphans
+                .iter()
+                .min_by_key(|(_, orphan)| orphan.received)
+                .map(|(hash, _)| *hash);
+            if let Some(hash) = oldest {
+                self.orphans.remove(&hash);
+            }
+        }
+
+        log::debug!(
+            "Added orphan block ({}) missing its parent ({}).",
+            hex::encode(block.hash.get(..5).unwrap()),
+            hex::encode(block.header.previous_block_hash.get(..5).unwrap())
+        );
+        self.orphans.insert(
+            block.hash,
+            Orphan {
+                block,
+                received: now,
+            },
+        );
+        true
+    }
+
+    pub fn contains(&self, block_hash: &HashResult) -> bool {
+        self.orphans.contains_key(block_hash)
+    }
+
+    /// Whether orphans are already waiting for the parent.
+    pub fn is_missing(&self, parent_hash: &HashResult) -> bool {
+        self.orphans
+            .values()
+            .any(|orphan| orphan.block.header.previous_block_hash == *parent_hash)
+    }
+
+    /// Removes the orphans descending from the block, ordered such that each
+    /// block follows its parent.
+    pub fn take_descendants(&mut self, block_hash: &HashResult) -> Vec<Block> {
+        let mut descendants = vec![];
+        let mut parents = vec![*block_hash];
+        while let Some(parent_hash) = parents.pop() {
+            let mut children: Vec<HashResult> = self
+                .orphans
+                .values()
+                .filter(|orphan| orphan.block.header.previous_block_hash == parent_hash)
+                .map(|orphan| orphan.block.hash)
+                .collect();
+            children.sort();
+            for hash in children {
+                let orphan = self.orphans.remove(&hash).unwrap();
+                parents.push(hash);
+                descendants.push(orphan.block);
+            }
+        }
+        descendants
+    }
+
+    pub fn len(&self) -> usize {
+        self.orphans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrphanPool, MAX_ORPHANS, ORPHAN_EXPIRY_SECS};
+    use crate::{block::Block, crypto::hash_utils::HashResult};
+
+    fn block(hash: u8, parent: u8) -> Block {
+        let mut block = Block {
+            hash: [hash; 32],
+            ..Default::default()
+        };
+        block.header.previous_block_hash = [parent; 32];
+        block
+    }
+
+    #[test]
+    fn test_take_descendants() {
+        let mut orphan_pool = OrphanPool::new();
+        assert!(orphan_pool.add(block(3, 2), 0));
+        assert!(orphan_pool.add(block(2, 1), 0));
+        assert!(orphan_pool.add(block(9, 8), 0));
+        assert!(!orphan_pool.add(block(2, 1), 0));
+        assert!(orphan_pool.is_missing(&[1; 32]));
+        assert!(!orphan_pool.is_missing(&[3; 32]));
+
+        let descendants = orphan_pool.take_descendants(&[1; 32]);
+        let hashes: Vec<HashResult> = descendants.iter().map(|x| x.hash).collect();
+        assert_eq!(hashes, vec![[2; 32], [3; 32]]);
+        assert_eq!(orphan_pool.len(), 1);
+        assert!(orphan_pool.take_descendants(&[1; 32]).is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut orphan_pool = OrphanPool::new();
+        for i in 0..MAX_ORPHANS {
+            orphan_pool.add(block(i as u8, 255), i as u64);
+        }
+        assert_eq!(orphan_pool.len(), MAX_ORPHANS);
+
+        // the oldest orphan is evicted
+        orphan_pool.add(block(254, 255), MAX_ORPHANS as u64);
+        assert_eq!(orphan_pool.len(), MAX_ORPHANS);
+        assert!(!orphan_pool.contains(&[0; 32]));
+        assert!(orphan_pool.contains(&[254; 32]));
+
+        // expired orphans are dropped
+        orphan_pool.add(block(253, 255), 1 + ORPHAN_EXPIRY_SECS + MAX_ORPHANS as u64);
+        assert_eq!(orphan_pool.len(), 1);
+    }
+}
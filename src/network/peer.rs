@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use tonic::transport::Channel;
+
+use crate::proto::proto_node::node_client::NodeClient;
+
+/// Peers failing this many requests in a row are considered dead and disconnected.
+pub const MAX_FAILED_REQUESTS: u32 = 3;
+
+/// Connection to another node.
+#[derive(Clone)]
+pub struct Peer {
+    pub client: NodeClient<Channel>,
+    /// Whether we connected to the peer, rather than the peer to us.
+    pub outbound: bool,
+    pub version: String,
+    pub block_height: u32,
+    /// Unix timestamp of the handshake.
+    pub connected_since: u64,
+    /// Round trip time of the last answered ping.
+    pub latency: Option<Duration>,
+    /// Size of the messages we sent to the peer.
+    pub bytes_sent: u64,
+    /// Size of the messages the peer answered with.
+    pub bytes_received: u64,
+    /// Requests to the peer failed since the last successful one.
+    pub failed_requests: u32,
+}
+
+impl Peer {
+    pub fn new(
+        client: NodeClient<Channel>,
+        outbound: bool,
+        version: String,
+        block_height: u32,
+        connected_since: u64,
+    ) -> Self {
+        Self {
+            client,
+            outbound,
+            version,
+            block_height,
+            connected_since,
+            latency: None,
+            bytes_sent: 0,
+            bytes_received: 0,
+            failed_requests: 0,
+        }
+    }
+
+    /// Records a successful request and the size of its messages.
+    pub fn record_success(&mut self, bytes_sent: usize, bytes_received: usize) {
+        self.bytes_sent += bytes_sent as u64;
+        self.bytes_received += bytes_received as u64;
+        self.failed_requests = 0;
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failed_requests += 1;
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.failed_requests >= MAX_FAILED_REQUESTS
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Instant;
+
+/// Number of tracked clients above which idle clients are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per client IP. Every request takes a token and the tokens
+/// refill at the configured rate up to the burst size.
+pub struct RateLimiter {
+    requests_per_second: u32,
+    burst: u32,
+    buckets: HashMap<IpAddr, Bucket>,
+}
+
+impl RateLimiter {
+    /// A rate of 0 requests per second disables the limit.
+    pub fn new(requests_per_second: u32, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst: burst.max(1),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token of the client, returns false if it has none left.
+    pub fn check(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.requests_per_second == 0 {
+            return true;
+        }
+        if self.buckets.len() >= MAX_TRACKED_CLIENTS {
+            self.prune(now);
+        }
+
+        let burst = self.burst as f64;
+        let bucket = self.buckets.entry(ip).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second as f64).min(burst);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Forgets clients whose bucket has refilled, as they are not limited.
+    fn prune(&mut self, now: Instant) {
+        let refill_secs = self.burst as f64 / self.requests_per_second as f64;
+        self.buckets.retain(|_, bucket| {
+            now.saturating_duration_since(bucket.updated).as_secs_f64() < refill_secs
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+
+    #[test]
+    fn test_rate_limit() {
+        let mut rate_limiter = RateLimiter::new(2, 3);
+        let alice: IpAddr = "10.0.0.1".parse().unwrap();
+        let bob: IpAddr = "10.0.0.2".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(rate_limiter.check(alice, now));
+        }
+        assert!(!rate_limiter.check(alice, now));
+        assert!(rate_limiter.check(bob, now));
+
+        // two tokens refill per second
+        let later = now + Duration::from_millis(500);
+        assert!(rate_limiter.check(alice, later));
+        assert!(!rate_limiter.check(alice, later));
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let mut rate_limiter = RateLimiter::new(0, 0);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let now = Instant::now();
+        assert!((0..100).all(|_| rate_limiter.check(ip, now)));
+    }
+}
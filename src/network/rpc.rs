@@ -0,0 +1,955 @@
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ring::constant_time::verify_slices_are_equal;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Response, Status};
+
+use super::ban_manager::{peer_ip, Misbehavior, DEFAULT_BAN_DURATION_SECS};
+use super::events::ChainEvent;
+use super::node::{disconnect, stopped, unix_timestamp, NetworkServer};
+use super::rate_limiter::RateLimiter;
+use crate::config::models::RpcConfig;
+use crate::crypto::hash_utils::{Address, HashResult};
+use crate::mempool::acceptance::MempoolError;
+use crate::metrics::metrics;
+use crate::mining::pow_utils::{
+    block_work, solve_sequential, target_from_difficulty_bit, verify_proof_of_work,
+};
+use crate::mining::template::BlockTemplate;
+use crate::proto::proto_node::rpc_client;
+use crate::proto::proto_node::rpc_server::Rpc;
+use crate::proto::proto_node::{
+    self, AddressEvent, BanEntry, BanList, BanReq, Block, BlockEvent, BlockHashes, BlockReq,
+    ChainInfo, FeeEstimate, FeeEstimateReq, GenerateBlocksReq, PeerInfo, PeerInfoList, PublicKey,
+    SavedMempool, Transaction, TransactionEvent, TransactionReq, TransactionStatus, UnspentOutput,
+    UnspentOutputs,
+};
+
+/// Buffered messages of a subscription, beyond which events wait for the client.
+const SUBSCRIPTION_BUFFER: usize = 64;
+/// Blocks a single request may generate, as they are mined one after another
+/// before it is answered.
+const MAX_GENERATE_BLOCKS: u32 = 100;
+
+type EventStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Client of the RPC service sending the configured auth token.
+pub type RpcClient = rpc_client::RpcClient<InterceptedService<Channel, AuthToken>>;
+
+/// Connects to the RPC service of a node.
+pub async fn connect_rpc(
+    rpc_url: &str,
+    token: Option<&str>,
+) -> Result<RpcClient, tonic::transport::Error> {
+    let channel = Endpoint::from_shared(rpc_url.to_string())?
+        .connect()
+        .await?;
+    Ok(rpc_client::RpcClient::with_interceptor(
+        channel,
+        AuthToken::new(token),
+    ))
+}
+
+fn bearer(token: &str) -> MetadataValue<Ascii> {
+    format!("Bearer {}", token)
+        .parse()
+        .expect("RPC auth token must be printable ASCII.")
+}
+
+/// Adds the auth token to the requests of a client.
+#[derive(Clone)]
+pub struct AuthToken(Option<MetadataValue<Ascii>>);
+
+impl AuthToken {
+    pub fn new(token: Option<&str>) -> Self {
+        Self(token.map(bearer))
+    }
+}
+
+impl Interceptor for AuthToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.0 {
+            request
+                .metadata_mut()
+                .insert("authorization", token.clone());
+        }
+        Ok(request)
+    }
+}
+
+/// Checks the auth token and the rate limit of requests to the RPC service.
+#[derive(Clone)]
+pub struct RpcGuard {
+    token: Option<MetadataValue<Ascii>>,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+}
+
+impl RpcGuard {
+    pub fn new(config: &RpcConfig) -> Self {
+        Self {
+            token: config.auth_token.as_deref().map(bearer),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::new(
+                config.requests_per_second,
+                config.burst,
+            ))),
+        }
+    }
+}
+
+impl RpcGuard {
+    /// Checks the authorization header and the rate limit of the client.
+    #[allow(clippy::result_large_err)]
+    pub fn check(&self, authorization: Option<&[u8]>, ip: Option<IpAddr>) -> Result<(), Status> {
+        if let Some(token) = &self.token {
+            // compared in constant time, so that the timing does not leak the token
+            let valid =
+                authorization.is_some_and(|x| verify_slices_are_equal(x, token.as_bytes()).is_ok());
+            if !valid {
+                return Err(Status::unauthenticated("Invalid RPC auth token."));
+            }
+        }
+        if let Some(ip) = ip {
+            if !self.rate_limiter.lock().unwrap().check(ip, Instant::now()) {
+                return Err(Status::resource_exhausted("Rate limit exceeded."));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Interceptor for RpcGuard {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        self.check(
+            request
+                .metadata()
+                .get("authorization")
+                .map(|x| x.as_bytes()),
+            request.remote_addr().map(|x| x.ip().to_canonical()),
+        )?;
+        Ok(request)
+    }
+}
+
+impl NetworkServer {
+    /// Streams the messages `map` creates from the chain events until the
+    /// client disconnects or the node shuts down. Clients lagging behind are
+    /// disconnected, as they missed events.
+    fn subscribe<T, F>(&self, map: F) -> EventStream<T>
+    where
+        T: Send + 'static,
+        F: Fn(ChainEvent) -> Vec<T> + Send + 'static,
+    {
+        let mut receiver = self.events.subscribe();
+        let mut shutdown = self.shutdown.clone();
+        let (tx, rx) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = receiver.recv() => event,
+                    _ = stopped(&mut shutdown) => {
+                        let _ = tx.send(Err(Status::unavailable("Node is shutting down."))).await;
+                        return;
+                    }
+                };
+                match event {
+                    Ok(event) => {
+                        for message in map(event) {
+                            if tx.send(Ok(message)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(count)) => {
+                        let status = Status::data_loss(format!("Missed {} events.", count));
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+        Box::pin(ReceiverStream::new(rx))
+    }
+
+    /// State of the chain, the pending transactions and the node.
+    pub(super) fn chain_info(&self) -> ChainInfo {
+        let difficulty = self.difficulty.load(Ordering::Relaxed);
+        let (peer_count, best_peer_height) = {
+            let peers = self.peers.lock().unwrap();
+            let best_peer_height = peers.values().map(|peer| peer.block_height).max();
+            (peers.len(), best_peer_height.unwrap_or(0))
+        };
+
+        let db = self.database.lock().unwrap();
+        let block_height = db.block_height() as u32;
+        let blocks = db.get_blocks();
+        let chain_work: ethnum::U256 = blocks
+            .iter()
+            .map(|block| block_work(block.header.difficulty))
+            .sum();
+        let pending = db.get_pending_transactions();
+        ChainInfo {
+            best_hash: db.head().map(|x| x.hash.to_vec()).unwrap_or_default(),
+            block_height,
+            difficulty: difficulty as u32,
+            target: target_from_difficulty_bit(difficulty)
+                .map(|target| target.to_be_bytes().to_vec())
+                .unwrap_or_default(),
+            chain_work: chain_work.to_be_bytes().to_vec(),
+            block_reward: self.config.chain_params.block_reward(db.block_height()),
+            mempool_size: pending.len() as u32,
+            mempool_bytes: pending.iter().map(|tx| tx.size() as u64).sum(),
+            network: self.config.chain_params.name.clone(),
+            sync_progress: match best_peer_height {
+                0 => 1.0,
+                best => (block_height as f64 / best as f64).min(1.0),
+            },
+            peer_count: peer_count as u32,
+            uptime_secs: self.started.elapsed().as_secs(),
+        }
+    }
+
+    /// Refuses calls changing the state of the node if the RPC service is read-only.
+    #[allow(clippy::result_large_err)]
+    fn check_writable(&self) -> Result<(), Status> {
+        if self.config.rpc.read_only {
+            return Err(Status::permission_denied("RPC service is read-only."));
+        }
+        Ok(())
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn address_event(
+    tx: &crate::transaction::Transaction,
+    status: TransactionStatus,
+    block_height: Option<usize>,
+) -> AddressEvent {
+    AddressEvent {
+        tx_json: to_json(tx),
+        tx_hash: tx.hash().to_vec(),
+        status: status.into(),
+        block_height: block_height.map(|x| x as u32),
+    }
+}
+
+#[tonic::async_trait]
+impl Rpc for NetworkServer {
+    type SubscribeBlocksStream = EventStream<BlockEvent>;
+    type SubscribeTransactionsStream = EventStream<TransactionEvent>;
+    type SubscribeAddressStream = EventStream<AddressEvent>;
+
+    async fn subscribe_blocks(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<Self::SubscribeBlocksStream>, Status> {
+        self.check_banned(&request)?;
+        Ok(Response::new(self.subscribe(|event| match event {
+            ChainEvent::BlockConnected { block, height } => vec![BlockEvent {
+                block_json: to_json(&block),
+                block_height: height as u32,
+                connected: true,
+            }],
+            ChainEvent::BlockDisconnected { block, height } => vec![BlockEvent {
+                block_json: to_json(&block),
+                block_height: height as u32,
+                connected: false,
+            }],
+            ChainEvent::TransactionAccepted(_) => vec![],
+        })))
+    }
+
+    async fn subscribe_transactions(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<Self::SubscribeTransactionsStream>, Status> {
+        self.check_banned(&request)?;
+        Ok(Response::new(self.subscribe(|event| match event {
+            ChainEvent::TransactionAccepted(tx) => vec![TransactionEvent {
+                tx_json: to_json(&tx),
+                tx_hash: tx.hash().to_vec(),
+            }],
+            _ => vec![],
+        })))
+    }
+
+    async fn subscribe_address(
+        &self,
+        request: Request<PublicKey>,
+    ) -> Result<Response<Self::SubscribeAddressStream>, Status> {
+        self.check_banned(&request)?;
+        let address = request.into_inner().key as Address;
+        if address.len() != ring::signature::ED25519_PUBLIC_KEY_LEN {
+            return Err(Status::invalid_argument("Invalid address."));
+        }
+        let touches = move |tx: &crate::transaction::Transaction| {
+            tx.sender == address || tx.outputs.iter().any(|x| x.receiver == address)
+        };
+        Ok(Response::new(self.subscribe(move |event| {
+            match event {
+                ChainEvent::BlockConnected { block, height } => block
+                    .transactions
+                    .iter()
+                    .filter(|tx| touches(tx))
+                    .map(|tx| address_event(tx, TransactionStatus::Confirmed, Some(height)))
+                    .collect(),
+                ChainEvent::BlockDisconnected { block, height } => block
+                    .transactions
+                    .iter()
+                    .filter(|tx| touches(tx))
+                    .map(|tx| address_event(tx, TransactionStatus::Reverted, Some(height)))
+                    .collect(),
+                ChainEvent::TransactionAccepted(tx) if touches(&tx) => {
+                    vec![address_event(&tx, TransactionStatus::Pending, None)]
+                }
+                ChainEvent::TransactionAccepted(_) => vec![],
+            }
+        })))
+    }
+
+    async fn add_transaction(
+        &self,
+        request: Request<Transaction>,
+    ) -> Result<Response<proto_node::None>, Status> {
+        self.check_writable()?;
+        self.check_banned(&request)?;
+        if let Ok(tx) = serde_json::from_str::<crate::transaction::Transaction>(
+            request.get_ref().tx_json.as_str(),
+        ) {
+            log::debug!("tx_hash={:?}", hex::encode(tx.hash()));
+            return match self.accept_pending(tx) {
+                Ok(()) => Ok(Response::new(proto_node::None {})),
+                Err(MempoolError::AlreadyKnown) => Err(Status::already_exists(
+                    MempoolError::AlreadyKnown.to_string(),
+                )),
+                Err(err) => {
+                    metrics().validation_failed("transaction", err.reason());
+                    log::debug!("Rejected transaction: {}", err);
+                    // the other rejections may stem from a different view of the chain
+                    if matches!(
+                        err,
+                        MempoolError::Coinbase
+                            | MempoolError::DuplicateInputs
+                            | MempoolError::InvalidScript
+                            | MempoolError::NegativeFee
+                    ) {
+                        self.misbehaving(&request, Misbehavior::InvalidTransaction);
+                    }
+                    Err(Status::failed_precondition(err.to_string()))
+                }
+            };
+        }
+        metrics().validation_failed("transaction", "malformed");
+        self.misbehaving(&request, Misbehavior::MalformedMessage);
+        Err(Status::invalid_argument("Failed to decode transaction."))
+    }
+
+    async fn get_chain_info(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<ChainInfo>, Status> {
+        self.check_banned(&request)?;
+        Ok(Response::new(self.chain_info()))
+    }
+
+    async fn get_block(&self, request: Request<BlockReq>) -> Result<Response<Block>, Status> {
+        self.check_banned(&request)?;
+        self.find_block(request.get_ref()).map(Response::new)
+    }
+
+    async fn get_peer_info(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<PeerInfoList>, Status> {
+        self.check_banned(&request)?;
+        let mut peers: Vec<PeerInfo> = self
+            .peers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(address, peer)| PeerInfo {
+                address: address.clone(),
+                version: peer.version.clone(),
+                block_height: peer.block_height,
+                latency_ms: peer.latency.map(|x| x.as_millis() as u64),
+                bytes_sent: peer.bytes_sent,
+                bytes_received: peer.bytes_received,
+                connected_since: peer.connected_since,
+                outbound: peer.outbound,
+            })
+            .collect();
+        peers.sort_by(|a, b| a.address.cmp(&b.address));
+        Ok(Response::new(PeerInfoList { peers }))
+    }
+
+    async fn get_transaction(
+        &self,
+        request: Request<TransactionReq>,
+    ) -> Result<Response<Transaction>, Status> {
+        self.check_banned(&request)?;
+        let Ok(tx_hash) =
+            HashResult::try_from(hex::decode(&request.get_ref().tx_hash).unwrap_or_default())
+        else {
+            return Err(Status::invalid_argument("Invalid transaction hash."));
+        };
+        let Some((tx, _)) = self.find_transaction(&tx_hash) else {
+            return Err(Status::not_found("Transaction not found."));
+        };
+        match serde_json::to_string(&tx) {
+            Ok(tx_json) => Ok(Response::new(Transaction { tx_json })),
+            Err(_) => Err(Status::internal("Failed to encode transaction to JSON.")),
+        }
+    }
+
+    async fn get_utxo(
+        &self,
+        request: Request<PublicKey>,
+    ) -> Result<Response<UnspentOutputs>, Status> {
+        self.check_banned(&request)?;
+        let public_key = request.into_inner().key as Address;
+        let db = self.database.lock().unwrap();
+        let utxos = db.get_utxo(&public_key);
+        let mut unspent_outputs = vec![];
+        for utxo in &utxos {
+            unspent_outputs.push(UnspentOutput {
+                previous_transaction_hash: utxo.0.to_vec(),
+                previous_transaction_output_index: utxo.1,
+                amount: utxo.2,
+                spent_in_mempool: db.is_spent_in_mempool(&utxo.0, utxo.1),
+            });
+        }
+        Ok(Response::new(UnspentOutputs { unspent_outputs }))
+    }
+
+    async fn estimate_fee(
+        &self,
+        request: Request<FeeEstimateReq>,
+    ) -> Result<Response<FeeEstimate>, Status> {
+        self.check_banned(&request)?;
+        let target_blocks = request.get_ref().target_blocks as usize;
+        if target_blocks == 0 {
+            return Err(Status::invalid_argument(
+                "Target must be at least one block.",
+            ));
+        }
+
+        let db = self.database.lock().unwrap();
+        let estimate = self
+            .fee_estimator
+            .lock()
+            .unwrap()
+            .estimate_fee(&*db, target_blocks);
+        match estimate {
+            Some(fee_rate) => Ok(Response::new(FeeEstimate {
+                fee_rate: fee_rate.max(self.config.mempool.min_relay_fee_rate),
+            })),
+            None => Err(Status::unavailable(
+                "Not enough confirmed transactions to estimate the fee.",
+            )),
+        }
+    }
+
+    async fn get_block_template(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<proto_node::BlockTemplate>, Status> {
+        self.check_banned(&request)?;
+        let db = self.database.lock().unwrap();
+        let template = BlockTemplate::new(
+            &*db,
+            self.difficulty.load(Ordering::Relaxed),
+            self.config.chain_params.block_reward(db.block_height()),
+            self.config.mining.max_block_size,
+        );
+        log::debug!(
+            "Handing out block template for height {} with {} transactions.",
+            template.height,
+            template.transactions.len()
+        );
+        Ok(Response::new((&template).into()))
+    }
+
+    async fn submit_block(
+        &self,
+        request: Request<Block>,
+    ) -> Result<Response<proto_node::None>, Status> {
+        self.check_banned(&request)?;
+        self.check_writable()?;
+        let Ok(block) = serde_json::from_str::<crate::block::Block>(&request.get_ref().block_json)
+        else {
+            metrics().validation_failed("block", "malformed");
+            self.misbehaving(&request, Misbehavior::MalformedMessage);
+            return Err(Status::invalid_argument("Failed to decode block."));
+        };
+
+        if block.header.difficulty < self.difficulty.load(Ordering::Relaxed) {
+            metrics().validation_failed("block", "low_difficulty");
+            return Err(Status::failed_precondition("Block difficulty is too low."));
+        }
+        if !self.verify_difficulty(&block.header) {
+            metrics().validation_failed("block", "invalid_difficulty");
+            return Err(Status::failed_precondition(
+                "Block difficulty does not follow the chain.",
+            ));
+        }
+        let height = self.database.lock().unwrap().block_height();
+        if block.header.reward != self.config.chain_params.block_reward(height) {
+            metrics().validation_failed("block", "invalid_reward");
+            return Err(Status::failed_precondition(
+                "Block reward does not follow the reward schedule.",
+            ));
+        }
+        if !verify_proof_of_work(&block.header, &block.hash) {
+            metrics().validation_failed("block", "invalid_pow");
+            self.misbehaving(&request, Misbehavior::InvalidBlock);
+            return Err(Status::failed_precondition(
+                "Block hash does not meet its difficulty.",
+            ));
+        }
+        if !block.verify(&self.database) {
+            metrics().validation_failed("block", "invalid_block");
+            return Err(Status::failed_precondition(
+                "Block is invalid or does not extend the chain.",
+            ));
+        }
+
+        log::info!(
+            "External miner submitted a block ({}).",
+            hex::encode(block.hash.get(..5).unwrap())
+        );
+        if let Err(err) = self.broadcast_block(&block).await {
+            log::warn!("Failed to announce submitted block: {}", err);
+        }
+        self.block_announce_tx.send(block).unwrap();
+        Ok(Response::new(proto_node::None {}))
+    }
+
+    async fn generate_blocks(
+        &self,
+        request: Request<GenerateBlocksReq>,
+    ) -> Result<Response<BlockHashes>, Status> {
+        self.check_banned(&request)?;
+        self.check_writable()?;
+        if !self.config.chain_params.generate_on_demand {
+            return Err(Status::failed_precondition(format!(
+                "Blocks cannot be generated on {}.",
+                self.config.chain_params.name
+            )));
+        }
+        let GenerateBlocksReq { count, address } = request.into_inner();
+        if address.len() != ring::signature::ED25519_PUBLIC_KEY_LEN {
+            return Err(Status::invalid_argument("Invalid address."));
+        }
+        if count > MAX_GENERATE_BLOCKS {
+            return Err(Status::invalid_argument(format!(
+                "At most {} blocks can be generated at once.",
+                MAX_GENERATE_BLOCKS
+            )));
+        }
+
+        let mut hashes = vec![];
+        for _ in 0..count {
+            let mut block = {
+                let db = self.database.lock().unwrap();
+                let template = BlockTemplate::new(
+                    &*db,
+                    self.difficulty.load(Ordering::Relaxed),
+                    self.config.chain_params.block_reward(db.block_height()),
+                    self.config.mining.max_block_size,
+                );
+                let coinbase = crate::transaction::Transaction::create_block_coinbase(
+                    &[(address.clone(), template.coinbase_value())],
+                    template.height,
+                    0,
+                );
+                template.assemble(vec![coinbase])
+            };
+            let Some((block_hash, _)) =
+                solve_sequential(block.header.difficulty, &mut block.header)
+            else {
+                return Err(Status::internal("Failed to solve the block."));
+            };
+            block.hash = block_hash;
+
+            // the next block is generated on top of this one
+            self.block_announce_tx.send(block.clone()).unwrap();
+            self.wait_for_head(&block.hash).await?;
+            if let Err(err) = self.broadcast_block(&block).await {
+                log::warn!("Failed to announce generated block: {}", err);
+            }
+            hashes.push(block.hash.to_vec());
+        }
+
+        log::info!("Generated {} blocks.", count);
+        Ok(Response::new(BlockHashes { hashes }))
+    }
+
+    async fn list_bans(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<BanList>, Status> {
+        self.check_local(&request)?;
+        let now = unix_timestamp();
+        let mut ban_manager = self.ban_manager.lock().unwrap();
+        ban_manager.prune(now);
+        let bans = ban_manager
+            .entries(now)
+            .into_iter()
+            .map(|(ip, ban_score, banned_until)| BanEntry {
+                address: ip.to_string(),
+                ban_score,
+                banned_until,
+            })
+            .collect();
+        Ok(Response::new(BanList { bans }))
+    }
+
+    async fn ban_peer(
+        &self,
+        request: Request<BanReq>,
+    ) -> Result<Response<proto_node::None>, Status> {
+        self.check_local(&request)?;
+        self.check_writable()?;
+        let BanReq {
+            address,
+            duration_secs,
+        } = request.into_inner();
+        let Some(ip) = peer_ip(&address) else {
+            return Err(Status::invalid_argument("Invalid peer address."));
+        };
+        let duration_secs = match duration_secs {
+            0 => DEFAULT_BAN_DURATION_SECS,
+            duration_secs => duration_secs,
+        };
+
+        self.ban_manager
+            .lock()
+            .unwrap()
+            .ban(ip, unix_timestamp().saturating_add(duration_secs));
+        disconnect(&self.peers, &self.authenticated, ip);
+        Ok(Response::new(proto_node::None {}))
+    }
+
+    async fn unban_peer(
+        &self,
+        request: Request<BanReq>,
+    ) -> Result<Response<proto_node::None>, Status> {
+        self.check_local(&request)?;
+        self.check_writable()?;
+        let Some(ip) = peer_ip(&request.get_ref().address) else {
+            return Err(Status::invalid_argument("Invalid peer address."));
+        };
+        if !self.ban_manager.lock().unwrap().unban(ip) {
+            return Err(Status::not_found("Peer is not banned."));
+        }
+        log::info!("Unbanned peer {}.", ip);
+        Ok(Response::new(proto_node::None {}))
+    }
+
+    async fn save_mempool(
+        &self,
+        request: Request<proto_node::None>,
+    ) -> Result<Response<SavedMempool>, Status> {
+        self.check_banned(&request)?;
+        self.check_writable()?;
+        let count = self.save_mempool().map_err(Status::internal)?;
+        log::info!("Saved {} pending transactions.", count);
+        Ok(Response::new(SavedMempool {
+            transaction_count: count as u32,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+    use tonic::transport::Endpoint;
+    use tonic::{Code, Request};
+
+    use super::{RpcGuard, MAX_GENERATE_BLOCKS};
+    use crate::chain;
+    use crate::config::loader::load_toml;
+    use crate::config::models::AccountConfig;
+    use crate::crypto::account::Account;
+    use crate::crypto::hash_utils::HashResult;
+    use crate::mining::pow_utils::{block_work, target_from_difficulty_bit};
+    use crate::mining::template::BlockTemplate;
+    use crate::network::node::tests::{regtest_network, server};
+    use crate::network::peer::Peer;
+    use crate::proto::proto_node::node_client::NodeClient;
+    use crate::proto::proto_node::rpc_server::Rpc;
+    use crate::proto::proto_node::{
+        GenerateBlocksReq, None, PublicKey, Transaction, TransactionReq, TransactionStatus,
+    };
+    use crate::transaction::builder::TransactionBuilder;
+
+    #[test]
+    fn test_rpc_guard() {
+        let mut config = load_toml("./configs/regtest.toml").rpc;
+        config.auth_token = Some("secret".to_string());
+        let guard = RpcGuard::new(&config);
+
+        assert!(guard.check(Some(b"Bearer secret"), None).is_ok());
+        for authorization in [None, Some(&b"Bearer secreT"[..]), Some(b"Bearer secret2")] {
+            let status = guard.check(authorization, None).unwrap_err();
+            assert_eq!(status.code(), Code::Unauthenticated);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_blocks() {
+        let (network, block_announce_rx) = regtest_network("rpc_generate_blocks");
+        let server = server(&network);
+        // adds the generated blocks like the blockchain thread
+        let database = server.database.clone();
+        std::thread::spawn(move || {
+            for block in block_announce_rx.iter().take(2) {
+                database.lock().unwrap().insert_block(block);
+            }
+        });
+
+        let address = vec![3u8; 32];
+        let hashes = server
+            .generate_blocks(Request::new(GenerateBlocksReq {
+                count: 2,
+                address: address.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .hashes;
+        assert_eq!(hashes.len(), 2);
+        {
+            let db = server.database.lock().unwrap();
+            assert_eq!(db.block_height(), 3);
+            assert_eq!(db.head().unwrap().hash.to_vec(), hashes[1]);
+            assert_eq!(db.get_utxo(&address).len(), 2);
+        }
+
+        let status = server
+            .generate_blocks(Request::new(GenerateBlocksReq {
+                count: 1,
+                address: vec![3u8; 31],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let status = server
+            .generate_blocks(Request::new(GenerateBlocksReq {
+                count: MAX_GENERATE_BLOCKS + 1,
+                address: address.clone(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // other networks only accept blocks solved by miners
+        let mut server = server;
+        server.config.chain_params.generate_on_demand = false;
+        let status = server
+            .generate_blocks(Request::new(GenerateBlocksReq { count: 1, address }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction() {
+        let (network, _) = regtest_network("rpc_get_transaction");
+        let server = server(&network);
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let funding = TransactionBuilder::new(&account)
+            .add_input([9u8; 32], 0, 10_000)
+            .add_output(account.public_key(), 10_000)
+            .build()
+            .unwrap();
+        {
+            let mut db = server.database.lock().unwrap();
+            db.add_transaction(funding.hash(), funding.clone());
+            db.add_utxo(funding.hash(), 0);
+        }
+        let tx = TransactionBuilder::new(&account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 9_000)
+            .build()
+            .unwrap();
+        let tx_json = serde_json::to_string(&tx).unwrap();
+        Rpc::add_transaction(&server, Request::new(Transaction { tx_json }))
+            .await
+            .unwrap();
+
+        // confirmed and pending transactions are found alike
+        for hash in [funding.hash(), tx.hash()] {
+            let found = server
+                .get_transaction(Request::new(TransactionReq {
+                    tx_hash: hex::encode(hash),
+                }))
+                .await
+                .unwrap();
+            let found: crate::transaction::Transaction =
+                serde_json::from_str(&found.get_ref().tx_json).unwrap();
+            assert_eq!(found.hash(), hash);
+        }
+
+        let status = server
+            .get_transaction(Request::new(TransactionReq {
+                tx_hash: hex::encode([1u8; 32]),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        let status = server
+            .get_transaction(Request::new(TransactionReq {
+                tx_hash: "xyz".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_get_chain_info() {
+        let (network, _) = regtest_network("rpc_get_chain_info");
+        let server = server(&network);
+        let account = Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap();
+        let funding = TransactionBuilder::new(&account)
+            .add_input([9u8; 32], 0, 10_000)
+            .add_output(account.public_key(), 10_000)
+            .build()
+            .unwrap();
+        {
+            let mut db = server.database.lock().unwrap();
+            db.add_transaction(funding.hash(), funding.clone());
+            db.add_utxo(funding.hash(), 0);
+        }
+        let tx = TransactionBuilder::new(&account)
+            .add_input(funding.hash(), 0, 10_000)
+            .add_output(&[7u8; 32], 9_000)
+            .build()
+            .unwrap();
+        let tx_json = serde_json::to_string(&tx).unwrap();
+        Rpc::add_transaction(&server, Request::new(Transaction { tx_json }))
+            .await
+            .unwrap();
+        // a peer ahead of us, the node is a quarter synced
+        let endpoint = Endpoint::from_static("http://[::1]:1");
+        let peer = Peer::new(
+            NodeClient::new(endpoint.connect_lazy()),
+            true,
+            String::new(),
+            4,
+            0,
+        );
+        server
+            .peers
+            .lock()
+            .unwrap()
+            .insert("http://[::1]:1".to_string(), peer);
+
+        let info = server
+            .get_chain_info(Request::new(None {}))
+            .await
+            .unwrap()
+            .into_inner();
+        let genesis = server.database.lock().unwrap().head().unwrap().clone();
+        let difficulty = server.config.chain_params.start_difficulty_bit;
+        assert_eq!(info.best_hash, genesis.hash.to_vec());
+        assert_eq!(info.block_height, 1);
+        assert_eq!(info.difficulty, difficulty as u32);
+        assert_eq!(
+            info.target,
+            target_from_difficulty_bit(difficulty)
+                .unwrap()
+                .to_be_bytes()
+                .to_vec()
+        );
+        assert_eq!(
+            info.chain_work,
+            block_work(genesis.header.difficulty).to_be_bytes().to_vec()
+        );
+        assert_eq!(
+            info.block_reward,
+            server.config.chain_params.block_reward(1)
+        );
+        assert_eq!(info.mempool_size, 1);
+        assert_eq!(info.mempool_bytes, tx.size() as u64);
+        assert_eq!(info.network, "regtest");
+        assert_eq!(info.peer_count, 1);
+        assert_eq!(info.sync_progress, 0.25);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_address_reorganization() {
+        let (network, _) = regtest_network("rpc_subscribe_address_reorganization");
+        let server = server(&network);
+        let chain_params = server.config.chain_params.clone();
+        let genesis = server.database.lock().unwrap().head().unwrap().hash;
+        let block = |parent: HashResult, height: usize, miner: u8| {
+            let reward = chain_params.block_reward(height);
+            let coinbase = crate::transaction::Transaction::create_block_coinbase(
+                &[(vec![miner; 32], reward)],
+                height,
+                0,
+            );
+            BlockTemplate {
+                previous_block_hash: parent,
+                height,
+                difficulty: chain_params.start_difficulty_bit,
+                timestamp: height as u64,
+                reward,
+                fees: 0,
+                transactions: vec![],
+            }
+            .assemble(vec![coinbase])
+        };
+        let mut events = server
+            .subscribe_address(Request::new(PublicKey { key: vec![1u8; 32] }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // the reward of the first block is replaced by the longer chain of another miner
+        let a1 = block(genesis, 1, 1);
+        let b1 = block(genesis, 1, 2);
+        let b2 = block(b1.hash, 2, 2);
+        for block in [a1.clone(), b1, b2] {
+            chain::add_block(&server.database, &chain_params, block).unwrap();
+            server
+                .events
+                .chain_updated(&*server.database.lock().unwrap());
+        }
+        let coinbase_hash = a1.transactions[0].hash();
+        let confirmed = events.next().await.unwrap().unwrap();
+        assert_eq!(confirmed.status(), TransactionStatus::Confirmed);
+        assert_eq!(confirmed.tx_hash, coinbase_hash.to_vec());
+        let reverted = events.next().await.unwrap().unwrap();
+        assert_eq!(reverted.status(), TransactionStatus::Reverted);
+        assert_eq!(reverted.tx_hash, coinbase_hash.to_vec());
+        assert_eq!(reverted.block_height, Some(1));
+
+        // the reverted reward is no longer spendable
+        let db = server.database.lock().unwrap();
+        assert!(!db.is_utxo(&coinbase_hash, 0));
+        assert!(db.get_utxo(&vec![1u8; 32]).is_empty());
+    }
+}
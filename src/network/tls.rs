@@ -0,0 +1,52 @@
+use std::fs;
+
+use tonic::transport::{
+    Certificate, Channel, ClientTlsConfig, Endpoint, Identity, ServerTlsConfig,
+};
+
+use crate::{config::models::TlsConfig, proto::proto_node::node_client::NodeClient};
+
+/// TLS configuration of the server and of the connections to peers. Both
+/// sides present a certificate of the configured authority.
+#[derive(Clone)]
+pub struct TlsSettings {
+    pub server: ServerTlsConfig,
+    pub client: ClientTlsConfig,
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|err| format!("Failed to read {}: {}", path, err))
+}
+
+impl TlsSettings {
+    pub fn load(config: &TlsConfig) -> Result<Self, String> {
+        let identity = Identity::from_pem(read(&config.cert_path)?, read(&config.key_path)?);
+        let ca = Certificate::from_pem(read(&config.ca_path)?);
+        Ok(Self {
+            server: ServerTlsConfig::new()
+                .identity(identity.clone())
+                .client_ca_root(ca.clone()),
+            client: ClientTlsConfig::new()
+                .identity(identity)
+                .ca_certificate(ca)
+                .domain_name(config.domain_name.clone()),
+        })
+    }
+}
+
+/// Connects to a node, using TLS if it is enabled.
+pub async fn connect(
+    address: &str,
+    tls: Option<&TlsSettings>,
+) -> Result<NodeClient<Channel>, tonic::transport::Error> {
+    let Some(tls) = tls else {
+        return NodeClient::connect(address.to_string()).await;
+    };
+    // tonic only applies TLS to https URLs
+    let url = address.replacen("http://", "https://", 1);
+    let channel = Endpoint::from_shared(url)?
+        .tls_config(tls.client.clone())?
+        .connect()
+        .await?;
+    Ok(NodeClient::new(channel))
+}
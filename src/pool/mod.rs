@@ -0,0 +1,4 @@
+pub mod pplns;
+pub mod protocol;
+pub mod server;
+pub mod worker;
@@ -0,0 +1,81 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{crypto::hash_utils::Address, types::Satoshi};
+
+/// Pay-per-last-N-shares: the block reward is split among the workers in
+/// proportion to their shares within the last N shares submitted to the pool.
+pub struct Pplns {
+    window: usize,
+    shares: VecDeque<Address>,
+}
+
+impl Pplns {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            shares: VecDeque::with_capacity(window),
+        }
+    }
+
+    pub fn add_share(&mut self, address: Address) {
+        if self.shares.len() >= self.window {
+            self.shares.pop_front();
+        }
+        self.shares.push_back(address);
+    }
+
+    /// Splits the amount among the workers of the share window. The remainder
+    /// of the integer division, or everything if there are no shares yet, goes
+    /// to the pool.
+    pub fn payouts(&self, amount: Satoshi, pool_address: &Address) -> Vec<(Address, Satoshi)> {
+        let mut shares_per_worker: BTreeMap<&Address, u64> = BTreeMap::new();
+        for address in self.shares.iter() {
+            *shares_per_worker.entry(address).or_default() += 1;
+        }
+
+        let total_shares = self.shares.len() as u64;
+        let mut payouts: Vec<(Address, Satoshi)> = shares_per_worker
+            .into_iter()
+            .map(|(address, shares)| (address.clone(), amount * shares / total_shares))
+            .filter(|(_, payout)| *payout > 0)
+            .collect();
+
+        let remainder = amount - payouts.iter().map(|x| x.1).sum::<Satoshi>();
+        if remainder > 0 {
+            match payouts
+                .iter_mut()
+                .find(|(address, _)| address == pool_address)
+            {
+                Some(payout) => payout.1 += remainder,
+                None => payouts.push((pool_address.clone(), remainder)),
+            }
+        }
+        payouts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pplns;
+
+    #[test]
+    fn test_payouts() {
+        let pool = vec![0u8; 32];
+        let (alice, bob) = (vec![1u8; 32], vec![2u8; 32]);
+        let mut pplns = Pplns::new(4);
+        assert_eq!(pplns.payouts(50, &pool), vec![(pool.clone(), 50)]);
+
+        pplns.add_share(bob.clone());
+        pplns.add_share(alice.clone());
+        pplns.add_share(alice.clone());
+        assert_eq!(
+            pplns.payouts(50, &pool),
+            vec![(alice.clone(), 33), (bob.clone(), 16), (pool.clone(), 1)]
+        );
+
+        // the oldest share drops out of the window
+        pplns.add_share(alice.clone());
+        pplns.add_share(alice.clone());
+        assert_eq!(pplns.payouts(50, &pool), vec![(alice, 50)]);
+    }
+}
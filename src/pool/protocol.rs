@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    block::BlockHeader,
+    crypto::{
+        hash_utils::{Address, HashResult},
+        merkle_tree::generate_merkle_root,
+    },
+    transaction::Transaction,
+    types::Satoshi,
+};
+
+/// Messages of the pool protocol are JSON objects, one per line.
+///
+/// Workers send requests, which are answered with a response of the same id.
+/// The pool pushes notifications, e.g. new jobs, at any time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Request {
+    pub id: u64,
+    #[serde(flatten)]
+    pub call: Call,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "method", content = "params")]
+pub enum Call {
+    /// Asks for an extra nonce range; answered with a `Subscription`.
+    #[serde(rename = "mining.subscribe")]
+    Subscribe,
+    /// Registers the hex encoded public key which is paid for the shares.
+    #[serde(rename = "mining.authorize")]
+    Authorize { address: String },
+    #[serde(rename = "mining.submit")]
+    Submit(Share),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Response {
+    pub id: u64,
+    pub result: serde_json::Value,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "method", content = "params")]
+pub enum Notification {
+    /// Minimum difficulty of the shares accepted from now on.
+    #[serde(rename = "mining.set_difficulty")]
+    SetDifficulty { difficulty: u8 },
+    #[serde(rename = "mining.notify")]
+    Notify(Job),
+}
+
+/// Extra nonce prefix assigned to a worker. The worker rolls the lower 32 bits
+/// of the coinbase extra nonce itself, so that no two workers search the same space.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Subscription {
+    pub extranonce1: u32,
+}
+
+/// Solution of a job, meeting at least the share difficulty.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Share {
+    pub job_id: u64,
+    pub extranonce2: u32,
+    pub nonce: u32,
+}
+
+/// Block template handed out to the workers, with the coinbase payouts
+/// already decided by the pool.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Job {
+    pub job_id: u64,
+    pub previous_block_hash: HashResult,
+    pub height: usize,
+    pub difficulty: u8,
+    pub timestamp: u64,
+    pub reward: Satoshi,
+    pub payouts: Vec<(Address, Satoshi)>,
+    /// Hashes of the transactions following the coinbase.
+    pub transaction_hashes: Vec<HashResult>,
+    /// Work on previous jobs is worthless, since the chain has moved on.
+    pub clean_jobs: bool,
+}
+
+impl Job {
+    pub fn coinbase(&self, extranonce1: u32, extranonce2: u32) -> Transaction {
+        let extra_nonce = ((extranonce1 as u64) << 32) | extranonce2 as u64;
+        Transaction::create_block_coinbase(&self.payouts, self.height, extra_nonce)
+    }
+
+    /// Builds the header for the coinbase; the nonce is still to be found.
+    pub fn header(&self, coinbase: &Transaction) -> BlockHeader {
+        let mut tx_hashes = vec![coinbase.hash()];
+        tx_hashes.extend(self.transaction_hashes.iter());
+
+        BlockHeader::from(
+            generate_merkle_root(tx_hashes),
+            self.previous_block_hash,
+            self.difficulty,
+            self.timestamp,
+            self.reward,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Call, Request, Share};
+
+    #[test]
+    fn test_request_format() {
+        let request = Request {
+            id: 3,
+            call: Call::Submit(Share {
+                job_id: 1,
+                extranonce2: 2,
+                nonce: 7,
+            }),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"id":3,"method":"mining.submit","params":{"job_id":1,"extranonce2":2,"nonce":7}}"#
+        );
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), request);
+
+        let subscribe: Request =
+            serde_json::from_str(r#"{"id":1,"method":"mining.subscribe"}"#).unwrap();
+        assert_eq!(subscribe.call, Call::Subscribe);
+    }
+}
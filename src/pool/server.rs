@@ -0,0 +1,378 @@
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ethnum::U256;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+use crate::{
+    block::Block,
+    config::models::PoolConfig,
+    crypto::hash_utils::Address,
+    mining::{
+        pow_utils::{compare_difficulty, target_from_difficulty_bit},
+        template::BlockTemplate,
+    },
+    network::rpc::{connect_rpc, RpcClient},
+    proto::proto_node,
+};
+
+use super::{
+    pplns::Pplns,
+    protocol::{Call, Job, Notification, Request, Response, Share, Subscription},
+};
+
+#[derive(Default)]
+struct WorkerStats {
+    address: Option<Address>,
+    accepted: u64,
+    rejected: u64,
+}
+
+struct PoolState {
+    /// Jobs for which shares are accepted, along with their block template.
+    jobs: HashMap<u64, (Job, BlockTemplate)>,
+    latest_job: Option<Job>,
+    next_job_id: u64,
+    next_extranonce1: u32,
+    pplns: Pplns,
+    /// Workers by their extra nonce prefix.
+    workers: HashMap<u32, WorkerStats>,
+    /// Shares submitted for the current jobs, to reject duplicates.
+    submitted: HashSet<(u64, u32, u32, u32)>,
+}
+
+/// Mining pool distributing block templates of a node to workers over TCP.
+///
+/// Each job carries a coinbase paying the block reward according to the
+/// shares of the last `pplns_window` shares. Workers submit shares meeting
+/// the share difficulty; shares which meet the block difficulty as well are
+/// submitted to the node as blocks.
+pub struct PoolServer {
+    config: PoolConfig,
+    pool_address: Address,
+    state: Mutex<PoolState>,
+    jobs_tx: broadcast::Sender<Job>,
+}
+
+impl PoolServer {
+    pub fn new(config: PoolConfig, pool_address: Address) -> Self {
+        let (jobs_tx, _) = broadcast::channel(16);
+        Self {
+            state: Mutex::new(PoolState {
+                jobs: HashMap::new(),
+                latest_job: None,
+                next_job_id: 0,
+                next_extranonce1: 0,
+                pplns: Pplns::new(config.pplns_window),
+                workers: HashMap::new(),
+                submitted: HashSet::new(),
+            }),
+            config,
+            pool_address,
+            jobs_tx,
+        }
+    }
+
+    pub async fn run(self: Arc<Self>) -> Result<(), Box<dyn std::error::Error>> {
+        let client = connect_rpc(&self.config.rpc_url, self.config.rpc_token.as_deref()).await?;
+        let pool = self.clone();
+        let refresh_client = client.clone();
+        tokio::spawn(async move { pool.refresh_jobs(refresh_client).await });
+
+        let listener = TcpListener::bind(&self.config.bind_address).await?;
+        log::info!("Mining pool started on {}", &self.config.bind_address);
+        loop {
+            let (socket, address) = listener.accept().await?;
+            let pool = self.clone();
+            let client = client.clone();
+            tokio::spawn(async move { pool.handle_worker(socket, address, client).await });
+        }
+    }
+
+    /// Fetches a block template from the node periodically and hands it out as a new job.
+    async fn refresh_jobs(&self, mut client: RpcClient) {
+        let interval = Duration::from_secs(self.config.template_refresh_secs);
+        loop {
+            let template = client
+                .get_block_template(proto_node::None {})
+                .await
+                .map_err(|status| status.message().to_string())
+                .and_then(|resp| BlockTemplate::try_from(resp.into_inner()));
+            match template {
+                Ok(template) => {
+                    let job = self.new_job(template);
+                    let _ = self.jobs_tx.send(job);
+                }
+                Err(err) => log::error!("Failed to get a block template: {}", err),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    fn new_job(&self, template: BlockTemplate) -> Job {
+        let mut state = self.state.lock().unwrap();
+        let clean_jobs = state
+            .latest_job
+            .as_ref()
+            .is_none_or(|job| job.previous_block_hash != template.previous_block_hash);
+        if clean_jobs {
+            state.jobs.clear();
+            state.submitted.clear();
+        }
+
+        let job = Job {
+            job_id: state.next_job_id,
+            previous_block_hash: template.previous_block_hash,
+            height: template.height,
+            difficulty: template.difficulty,
+            timestamp: template.timestamp,
+            reward: template.reward,
+            payouts: state
+                .pplns
+                .payouts(template.coinbase_value(), &self.pool_address),
+            transaction_hashes: template.transactions.iter().map(|x| x.hash()).collect(),
+            clean_jobs,
+        };
+        state.next_job_id += 1;
+        state.jobs.insert(job.job_id, (job.clone(), template));
+        state.latest_job = Some(job.clone());
+        job
+    }
+
+    async fn handle_worker(&self, socket: TcpStream, address: SocketAddr, mut client: RpcClient) {
+        log::debug!("Worker connected from {}.", address);
+        let (reader, mut writer) = socket.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut jobs_rx = self.jobs_tx.subscribe();
+        let mut extranonce1: Option<u32> = None;
+
+        loop {
+            let messages = tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => self.handle_request(&line, &mut extranonce1, &mut client).await,
+                    _ => break,
+                },
+                job = jobs_rx.recv() => match job {
+                    Ok(job) if extranonce1.is_some() => vec![notification(Notification::Notify(job))],
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+
+            for message in messages {
+                if writer.write_all(message.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        if let Some(extranonce1) = extranonce1 {
+            let mut state = self.state.lock().unwrap();
+            if let Some(stats) = state.workers.remove(&extranonce1) {
+                log::info!(
+                    "Worker {} disconnected after {} accepted and {} rejected shares.",
+                    address,
+                    stats.accepted,
+                    stats.rejected
+                );
+            }
+        }
+    }
+
+    /// Handles a request of a worker and returns the messages to send back.
+    async fn handle_request(
+        &self,
+        line: &str,
+        extranonce1: &mut Option<u32>,
+        client: &mut RpcClient,
+    ) -> Vec<String> {
+        let request = match serde_json::from_str::<Request>(line) {
+            Ok(request) => request,
+            Err(err) => {
+                log::debug!("Invalid request from worker: {}", err);
+                return vec![response(0, Err(format!("Invalid request: {}", err)))];
+            }
+        };
+
+        match request.call {
+            Call::Subscribe => {
+                let (subscription, latest_job) = {
+                    let mut state = self.state.lock().unwrap();
+                    let subscription = Subscription {
+                        extranonce1: state.next_extranonce1,
+                    };
+                    state.next_extranonce1 = state.next_extranonce1.wrapping_add(1);
+                    state
+                        .workers
+                        .insert(subscription.extranonce1, WorkerStats::default());
+                    (subscription, state.latest_job.clone())
+                };
+                *extranonce1 = Some(subscription.extranonce1);
+
+                let mut messages = vec![
+                    response(request.id, Ok(serde_json::to_value(subscription).unwrap())),
+                    notification(Notification::SetDifficulty {
+                        difficulty: self.config.share_difficulty_bit,
+                    }),
+                ];
+                if let Some(job) = latest_job {
+                    messages.push(notification(Notification::Notify(job)));
+                }
+                messages
+            }
+            Call::Authorize { address } => {
+                let result = match (extranonce1, hex::decode(&address)) {
+                    (Some(extranonce1), Ok(address)) if address.len() == 32 => {
+                        let mut state = self.state.lock().unwrap();
+                        if let Some(stats) = state.workers.get_mut(extranonce1) {
+                            stats.address = Some(address);
+                        }
+                        Ok(serde_json::Value::Bool(true))
+                    }
+                    (None, _) => Err("Not subscribed.".to_string()),
+                    _ => Err(format!("Invalid address `{}`.", address)),
+                };
+                vec![response(request.id, result)]
+            }
+            Call::Submit(share) => {
+                let Some(extranonce1) = *extranonce1 else {
+                    return vec![response(request.id, Err("Not subscribed.".to_string()))];
+                };
+                let result = match self.check_share(extranonce1, share) {
+                    Ok(Some(block)) => {
+                        // the share counts even if the block lost the race
+                        if let Err(err) = self.submit_block(block, client).await {
+                            log::warn!("{}", err);
+                        }
+                        Ok(())
+                    }
+                    Ok(None) => Ok(()),
+                    Err(err) => Err(err),
+                };
+                vec![response(
+                    request.id,
+                    result.map(|_| serde_json::Value::Bool(true)),
+                )]
+            }
+        }
+    }
+
+    /// Validates a share and credits it to the worker. Returns the block if
+    /// the share meets the block difficulty as well.
+    fn check_share(&self, extranonce1: u32, share: Share) -> Result<Option<Block>, String> {
+        let mut state = self.state.lock().unwrap();
+        let result = Self::validate_share(&mut state, &self.config, extranonce1, share);
+
+        let Some(stats) = state.workers.get_mut(&extranonce1) else {
+            return Err("Not subscribed.".to_string());
+        };
+        match &result {
+            Ok(_) => stats.accepted += 1,
+            Err(err) => {
+                stats.rejected += 1;
+                log::debug!("Rejected share of worker {}: {}", extranonce1, err);
+            }
+        }
+        if result.is_ok() {
+            let address = stats.address.clone().ok_or("Not authorized.")?;
+            state.pplns.add_share(address);
+        }
+        result
+    }
+
+    fn validate_share(
+        state: &mut PoolState,
+        config: &PoolConfig,
+        extranonce1: u32,
+        share: Share,
+    ) -> Result<Option<Block>, String> {
+        if state
+            .workers
+            .get(&extranonce1)
+            .is_none_or(|stats| stats.address.is_none())
+        {
+            return Err("Not authorized.".to_string());
+        }
+        let Some((job, template)) = state.jobs.get(&share.job_id).cloned() else {
+            return Err("Stale job.".to_string());
+        };
+        if !state
+            .submitted
+            .insert((share.job_id, extranonce1, share.extranonce2, share.nonce))
+        {
+            return Err("Duplicate share.".to_string());
+        }
+
+        let coinbase = job.coinbase(extranonce1, share.extranonce2);
+        let mut header = job.header(&coinbase);
+        header.nonce = share.nonce;
+        let hash = header.finalize();
+        let hash_int = U256::from_be_bytes(hash);
+
+        let share_difficulty = config.share_difficulty_bit.min(job.difficulty);
+        if !compare_difficulty(
+            target_from_difficulty_bit(share_difficulty).unwrap_or(U256::MAX),
+            hash_int,
+        ) {
+            return Err("Share does not meet the difficulty.".to_string());
+        }
+        if !compare_difficulty(
+            target_from_difficulty_bit(job.difficulty).unwrap_or(U256::MAX),
+            hash_int,
+        ) {
+            return Ok(None);
+        }
+
+        let mut block = template.assemble(vec![coinbase]);
+        block.header.nonce = share.nonce;
+        block.hash = hash;
+        Ok(Some(block))
+    }
+
+    async fn submit_block(&self, block: Block, client: &mut RpcClient) -> Result<(), String> {
+        let block_json = serde_json::to_string(&block).unwrap();
+        client
+            .submit_block(proto_node::Block { block_json })
+            .await
+            .map_err(|status| format!("Block rejected by the node: {}", status.message()))?;
+
+        log::info!(
+            "★★★ The pool mined a block ({})! ★★★",
+            block.get_hash_as_string(true)
+        );
+        let state = self.state.lock().unwrap();
+        for (extranonce1, stats) in state.workers.iter() {
+            log::info!(
+                "Worker {}: {} accepted, {} rejected shares.",
+                extranonce1,
+                stats.accepted,
+                stats.rejected
+            );
+        }
+        Ok(())
+    }
+}
+
+fn response(id: u64, result: Result<serde_json::Value, String>) -> String {
+    let response = match result {
+        Ok(result) => Response {
+            id,
+            result,
+            error: None,
+        },
+        Err(err) => Response {
+            id,
+            result: serde_json::Value::Null,
+            error: Some(err),
+        },
+    };
+    serde_json::to_string(&response).unwrap() + "\n"
+}
+
+fn notification(notification: Notification) -> String {
+    serde_json::to_string(&notification).unwrap() + "\n"
+}
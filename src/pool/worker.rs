@@ -0,0 +1,166 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{unbounded, Sender};
+
+use crate::{
+    crypto::hash_utils::Address,
+    mining::pow_utils::{proof_of_work, PowInterrupts, PowResult},
+};
+
+use super::protocol::{Call, Job, Notification, Request, Response, Share, Subscription};
+
+/// Jobs are pushed by the pool, this only bounds how long a job is mined without news.
+const JOB_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Request id of the subscription; later requests count up from here.
+const SUBSCRIBE_ID: u64 = 1;
+
+/// Mines shares for a pool until the connection is closed.
+///
+/// The shares are credited to the given address, which the pool pays
+/// according to its payout scheme.
+pub fn run_worker(pool_address: &str, address: &Address, threads: usize) -> Result<(), String> {
+    let stream = TcpStream::connect(pool_address)
+        .map_err(|err| format!("Failed to connect to {}. Error: {}", pool_address, err))?;
+    let mut writer = stream.try_clone().map_err(|err| err.to_string())?;
+
+    let latest_job: Arc<Mutex<Option<Job>>> = Arc::new(Mutex::new(None));
+    let share_difficulty = Arc::new(AtomicU8::new(u8::MAX));
+    let (subscription_tx, subscription_rx) = unbounded::<Subscription>();
+    let (job_update_tx, job_update_rx) = unbounded::<()>();
+    let (cancel_tx, cancel_rx) = unbounded::<()>();
+    {
+        let (latest_job, share_difficulty) = (latest_job.clone(), share_difficulty.clone());
+        thread::spawn(move || {
+            read_messages(
+                stream,
+                &latest_job,
+                &share_difficulty,
+                subscription_tx,
+                job_update_tx,
+            );
+            // the connection is gone, so is the work
+            let _ = cancel_tx.send(());
+        });
+    }
+
+    let mut next_id = SUBSCRIBE_ID;
+    let mut send = |call: Call| -> Result<(), String> {
+        let request = Request { id: next_id, call };
+        next_id += 1;
+        let line = serde_json::to_string(&request).unwrap() + "\n";
+        writer
+            .write_all(line.as_bytes())
+            .map_err(|err| format!("Failed to send to the pool. Error: {}", err))
+    };
+
+    send(Call::Subscribe)?;
+    let subscription = subscription_rx
+        .recv()
+        .map_err(|_| "The pool closed the connection.".to_string())?;
+    send(Call::Authorize {
+        address: hex::encode(address),
+    })?;
+    log::info!(
+        "Subscribed to the pool with extra nonce {}.",
+        subscription.extranonce1
+    );
+
+    let mut extranonce2 = 0u32;
+    loop {
+        let job = latest_job.lock().unwrap().clone();
+        let Some(job) = job else {
+            // wait for the first job
+            if cancel_rx.recv_timeout(Duration::from_millis(100)).is_ok() {
+                break;
+            }
+            continue;
+        };
+
+        let coinbase = job.coinbase(subscription.extranonce1, extranonce2);
+        let mut header = job.header(&coinbase);
+        let interrupts = PowInterrupts {
+            cancel_rx: &cancel_rx,
+            template_update_rx: &job_update_rx,
+            refresh_at: Instant::now() + JOB_REFRESH_INTERVAL,
+        };
+        let mut hash_count = 0;
+        let result = proof_of_work(
+            share_difficulty.load(Ordering::Relaxed),
+            &mut header,
+            &interrupts,
+            &mut hash_count,
+            threads,
+        );
+
+        match result {
+            PowResult::Found(_) => {
+                log::debug!("Found a share for job {}.", job.job_id);
+                send(Call::Submit(Share {
+                    job_id: job.job_id,
+                    extranonce2,
+                    nonce: header.nonce,
+                }))?;
+            }
+            PowResult::Exhausted | PowResult::Stale => {}
+            PowResult::Cancelled => break,
+        }
+        // each share must come from a distinct coinbase, or it would be a duplicate
+        extranonce2 = extranonce2.wrapping_add(1);
+    }
+
+    Err("The pool closed the connection.".to_string())
+}
+
+/// Reads the messages of the pool until the connection is closed.
+fn read_messages(
+    stream: TcpStream,
+    latest_job: &Mutex<Option<Job>>,
+    share_difficulty: &AtomicU8,
+    subscription_tx: Sender<Subscription>,
+    job_update_tx: Sender<()>,
+) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if let Ok(notification) = serde_json::from_str::<Notification>(&line) {
+            match notification {
+                Notification::SetDifficulty { difficulty } => {
+                    log::info!("Share difficulty set to {}.", difficulty);
+                    share_difficulty.store(difficulty, Ordering::Relaxed);
+                }
+                Notification::Notify(job) => {
+                    log::debug!("New job {} at height {}.", job.job_id, job.height);
+                    *latest_job.lock().unwrap() = Some(job);
+                    let _ = job_update_tx.send(());
+                }
+            }
+            continue;
+        }
+
+        match serde_json::from_str::<Response>(&line) {
+            Ok(Response {
+                id,
+                error: Some(err),
+                ..
+            }) => log::warn!("Request {} was rejected by the pool: {}", id, err),
+            Ok(Response { id, result, .. }) if id == SUBSCRIBE_ID => {
+                match serde_json::from_value::<Subscription>(result) {
+                    Ok(subscription) => {
+                        let _ = subscription_tx.send(subscription);
+                    }
+                    Err(err) => log::error!("Invalid subscription: {}", err),
+                }
+            }
+            Ok(Response { id, .. }) => log::debug!("Request {} was accepted by the pool.", id),
+            Err(err) => log::warn!("Invalid message from the pool: {}", err),
+        }
+    }
+}
@@ -11,6 +11,34 @@ pub struct HandshakeMessage {
     pub block_height: u32,
     #[prost(string, tag = "3")]
     pub server_address: ::prost::alloc::string::String,
+    /// Identifies the network of the chain parameters; peers of other networks are refused.
+    #[prost(uint32, tag = "4")]
+    pub network_id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Challenge {
+    /// Random nonce, valid once and only on the connection it was issued on.
+    #[prost(bytes = "vec", tag = "1")]
+    pub nonce: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthMessage {
+    #[prost(string, tag = "1")]
+    pub server_address: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub network_id: u32,
+    /// Identity key of the node.
+    #[prost(bytes = "vec", tag = "3")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+    /// Unix timestamp at which the message was signed.
+    #[prost(uint64, tag = "4")]
+    pub timestamp: u64,
+    /// Signature proving ownership of the identity key. Requests sign the issued
+    /// nonce, answers the signature of the request.
+    #[prost(bytes = "vec", tag = "5")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -26,6 +54,30 @@ pub struct Block {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockAnnouncement {
+    /// Either a block or a compact block.
+    #[prost(string, tag = "1")]
+    pub block_json: ::prost::alloc::string::String,
+    /// Server address of the announcing node.
+    #[prost(string, tag = "2")]
+    pub sender_address: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockTransactionsReq {
+    #[prost(bytes = "vec", tag = "1")]
+    pub block_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, repeated, tag = "2")]
+    pub indices: ::prost::alloc::vec::Vec<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockTransactions {
+    #[prost(string, tag = "1")]
+    pub transactions_json: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BlockReq {
     #[prost(uint32, tag = "1")]
     pub block_height: u32,
@@ -69,6 +121,8 @@ pub struct UnspentOutput {
     pub previous_transaction_output_index: u32,
     #[prost(uint64, tag = "3")]
     pub amount: u64,
+    #[prost(bool, tag = "4")]
+    pub spent_in_mempool: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -78,21 +132,229 @@ pub struct UnspentOutputs {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FeeEstimateReq {
+    #[prost(uint32, tag = "1")]
+    pub target_blocks: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FeeEstimate {
+    #[prost(uint64, tag = "1")]
+    pub fee_rate: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockTemplate {
+    #[prost(bytes = "vec", tag = "1")]
+    pub previous_block_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub height: u64,
+    #[prost(uint32, tag = "3")]
+    pub difficulty: u32,
+    #[prost(uint64, tag = "4")]
+    pub timestamp: u64,
+    #[prost(uint64, tag = "5")]
+    pub reward: u64,
+    #[prost(uint64, tag = "6")]
+    pub fees: u64,
+    #[prost(string, tag = "7")]
+    pub transactions_json: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GenerateBlocksReq {
+    #[prost(uint32, tag = "1")]
+    pub count: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub address: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockHashes {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub hashes: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BanEntry {
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub ban_score: u32,
+    /// Unix timestamp until which the peer is banned, 0 if it is not banned.
+    #[prost(uint64, tag = "3")]
+    pub banned_until: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BanList {
+    #[prost(message, repeated, tag = "1")]
+    pub bans: ::prost::alloc::vec::Vec<BanEntry>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BanReq {
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    /// Duration of the ban, 0 bans for the default duration. Ignored when unbanning.
+    #[prost(uint64, tag = "2")]
+    pub duration_secs: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PeerList {
     #[prost(string, repeated, tag = "1")]
     pub peers: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PingMessage {
+    #[prost(uint64, tag = "1")]
+    pub nonce: u64,
+    #[prost(uint32, tag = "2")]
+    pub block_height: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerInfo {
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub version: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "3")]
+    pub block_height: u32,
+    /// Round trip time of the last ping, unset until a ping was answered.
+    #[prost(uint64, optional, tag = "4")]
+    pub latency_ms: ::core::option::Option<u64>,
+    #[prost(uint64, tag = "5")]
+    pub bytes_sent: u64,
+    #[prost(uint64, tag = "6")]
+    pub bytes_received: u64,
+    /// Unix timestamp at which the connection was established.
+    #[prost(uint64, tag = "7")]
+    pub connected_since: u64,
+    #[prost(bool, tag = "8")]
+    pub outbound: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerInfoList {
+    #[prost(message, repeated, tag = "1")]
+    pub peers: ::prost::alloc::vec::Vec<PeerInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PublicKey {
     #[prost(bytes = "vec", tag = "1")]
     pub key: ::prost::alloc::vec::Vec<u8>,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockEvent {
+    #[prost(string, tag = "1")]
+    pub block_json: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub block_height: u32,
+    /// False if the block was disconnected by a reorganization.
+    #[prost(bool, tag = "3")]
+    pub connected: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransactionEvent {
+    #[prost(string, tag = "1")]
+    pub tx_json: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub tx_hash: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AddressEvent {
+    #[prost(string, tag = "1")]
+    pub tx_json: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub tx_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(enumeration = "TransactionStatus", tag = "3")]
+    pub status: i32,
+    /// Height of the confirming block, unless pending.
+    #[prost(uint32, optional, tag = "4")]
+    pub block_height: ::core::option::Option<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChainInfo {
+    #[prost(bytes = "vec", tag = "1")]
+    pub best_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, tag = "2")]
+    pub block_height: u32,
+    #[prost(uint32, tag = "3")]
+    pub difficulty: u32,
+    /// Big-endian 256-bit target the block hashes must not exceed.
+    #[prost(bytes = "vec", tag = "4")]
+    pub target: ::prost::alloc::vec::Vec<u8>,
+    /// Big-endian 256-bit expected number of hashes to mine the chain.
+    #[prost(bytes = "vec", tag = "5")]
+    pub chain_work: ::prost::alloc::vec::Vec<u8>,
+    /// Reward of the next block.
+    #[prost(uint64, tag = "6")]
+    pub block_reward: u64,
+    #[prost(uint32, tag = "7")]
+    pub mempool_size: u32,
+    #[prost(uint64, tag = "8")]
+    pub mempool_bytes: u64,
+    #[prost(string, tag = "9")]
+    pub network: ::prost::alloc::string::String,
+    /// Ratio of our block height to the highest block height of the peers.
+    #[prost(double, tag = "10")]
+    pub sync_progress: f64,
+    #[prost(uint32, tag = "11")]
+    pub peer_count: u32,
+    #[prost(uint64, tag = "12")]
+    pub uptime_secs: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SavedMempool {
+    #[prost(uint32, tag = "1")]
+    pub transaction_count: u32,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TransactionStatus {
+    Pending = 0,
+    Confirmed = 1,
+    /// The block confirming the transaction was disconnected by a reorganization, which rolled
+    /// back the outputs it spent and created. Unless it is a coinbase or conflicts with the new
+    /// chain, the transaction is pending again, or CONFIRMED follows if the new chain includes it.
+    Reverted = 2,
+}
+impl TransactionStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            TransactionStatus::Pending => "PENDING",
+            TransactionStatus::Confirmed => "CONFIRMED",
+            TransactionStatus::Reverted => "REVERTED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PENDING" => Some(Self::Pending),
+            "CONFIRMED" => Some(Self::Confirmed),
+            "REVERTED" => Some(Self::Reverted),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 pub mod node_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
     #[derive(Debug, Clone)]
     pub struct NodeClient<T> {
         inner: tonic::client::Grpc<T>,
@@ -123,10 +385,7 @@ pub mod node_client {
             let inner = tonic::client::Grpc::with_origin(inner, origin);
             Self { inner }
         }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> NodeClient<InterceptedService<T, F>>
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> NodeClient<InterceptedService<T, F>>
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
@@ -136,9 +395,8 @@ pub mod node_client {
                     <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
                 >,
             >,
-            <T as tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-            >>::Error: Into<StdError> + Send + Sync,
+            <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
+                Into<StdError> + Send + Sync,
         {
             NodeClient::new(InterceptedService::new(inner, interceptor))
         }
@@ -173,29 +431,58 @@ pub mod node_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
-        /// Exchanges basic information with an another node.
+        /// Exchanges basic information with an another node. If peers are authenticated, the connection has to be authenticated first.
         pub async fn handshake(
             &mut self,
             request: impl tonic::IntoRequest<super::HandshakeMessage>,
-        ) -> std::result::Result<
-            tonic::Response<super::HandshakeMessage>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+        ) -> std::result::Result<tonic::Response<super::HandshakeMessage>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/Handshake");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "Handshake"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Issues a nonce which the peer signs to authenticate the connection it was requested on.
+        pub async fn get_challenge(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<tonic::Response<super::Challenge>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/GetChallenge");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "GetChallenge"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Authenticates the connection by proving ownership of the identity key over the nonce issued on it. The answer proves the identity of the node in turn.
+        pub async fn authenticate(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AuthMessage>,
+        ) -> std::result::Result<tonic::Response<super::AuthMessage>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/proto_node.Node/Handshake",
-            );
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/Authenticate");
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("proto_node.Node", "Handshake"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "Authenticate"));
             self.inner.unary(req, path, codec).await
         }
         /// Returns the list of currently connected peers.
@@ -203,42 +490,90 @@ pub mod node_client {
             &mut self,
             request: impl tonic::IntoRequest<super::None>,
         ) -> std::result::Result<tonic::Response<super::PeerList>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/proto_node.Node/GetPeerList",
-            );
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/GetPeerList");
             let mut req = request.into_request();
             req.extensions_mut()
                 .insert(GrpcMethod::new("proto_node.Node", "GetPeerList"));
             self.inner.unary(req, path, codec).await
         }
-        /// Adds the block to the current block list.
+        /// Checks whether the node is alive. The node answers with the same nonce and its block height.
+        pub async fn ping(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PingMessage>,
+        ) -> std::result::Result<tonic::Response<super::PingMessage>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/Ping");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "Ping"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Adds the block to the current block list. Missing ancestors are requested from the sender.
         pub async fn add_block(
             &mut self,
-            request: impl tonic::IntoRequest<super::Block>,
+            request: impl tonic::IntoRequest<super::BlockAnnouncement>,
         ) -> std::result::Result<tonic::Response<super::None>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static("/proto_node.Node/AddBlock");
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("proto_node.Node", "AddBlock"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "AddBlock"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Announces a block as header and short transaction IDs. The receiver reconstructs it from its pending transactions and requests the missing ones from the sender.
+        pub async fn add_compact_block(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BlockAnnouncement>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/AddCompactBlock");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "AddCompactBlock"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Gets transactions of a recently announced block by their index.
+        pub async fn get_block_transactions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BlockTransactionsReq>,
+        ) -> std::result::Result<tonic::Response<super::BlockTransactions>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto_node.Node/GetBlockTransactions");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "GetBlockTransactions"));
             self.inner.unary(req, path, codec).await
         }
         /// Gets the block by its block height or hash. If hash, set block height to 0.
@@ -246,19 +581,17 @@ pub mod node_client {
             &mut self,
             request: impl tonic::IntoRequest<super::BlockReq>,
         ) -> std::result::Result<tonic::Response<super::Block>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static("/proto_node.Node/GetBlock");
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("proto_node.Node", "GetBlock"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "GetBlock"));
             self.inner.unary(req, path, codec).await
         }
         /// Gets all blocks that constitute a chain.
@@ -266,42 +599,119 @@ pub mod node_client {
             &mut self,
             request: impl tonic::IntoRequest<super::None>,
         ) -> std::result::Result<tonic::Response<super::Chain>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static("/proto_node.Node/GetChain");
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("proto_node.Node", "GetChain"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Node", "GetChain"));
             self.inner.unary(req, path, codec).await
         }
+    }
+}
+/// Generated client implementations.
+pub mod rpc_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
+    /// Client-facing service of wallets, miners and administrators, served on its own address.
+    #[derive(Debug, Clone)]
+    pub struct RpcClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl RpcClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> RpcClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> RpcClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<http::Request<tonic::body::BoxBody>>>::Error:
+                Into<StdError> + Send + Sync,
+        {
+            RpcClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
         /// Adds a transaction to the pending transactions.
         pub async fn add_transaction(
             &mut self,
             request: impl tonic::IntoRequest<super::Transaction>,
         ) -> std::result::Result<tonic::Response<super::None>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/proto_node.Node/AddTransaction",
-            );
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/AddTransaction");
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("proto_node.Node", "AddTransaction"));
+                .insert(GrpcMethod::new("proto_node.Rpc", "AddTransaction"));
             self.inner.unary(req, path, codec).await
         }
         /// Gets a transaction whether it is commited or pending.
@@ -309,74 +719,351 @@ pub mod node_client {
             &mut self,
             request: impl tonic::IntoRequest<super::TransactionReq>,
         ) -> std::result::Result<tonic::Response<super::Transaction>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/proto_node.Node/GetTransaction",
-            );
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GetTransaction");
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("proto_node.Node", "GetTransaction"));
+                .insert(GrpcMethod::new("proto_node.Rpc", "GetTransaction"));
             self.inner.unary(req, path, codec).await
         }
-        /// Gets a newly created transaction. Transaction is not added to the mem-pool it is missing a signature.
+        /// Gets the unspent outputs of a public key, marking the ones already spent by pending transactions.
         pub async fn get_utxo(
             &mut self,
             request: impl tonic::IntoRequest<super::PublicKey>,
         ) -> std::result::Result<tonic::Response<super::UnspentOutputs>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/proto_node.Node/GetUTXO");
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GetUTXO");
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("proto_node.Node", "GetUTXO"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "GetUTXO"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod node_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with NodeServer.
-    #[async_trait]
-    pub trait Node: Send + Sync + 'static {
-        /// Exchanges basic information with an another node.
-        async fn handshake(
-            &self,
-            request: tonic::Request<super::HandshakeMessage>,
-        ) -> std::result::Result<
-            tonic::Response<super::HandshakeMessage>,
-            tonic::Status,
-        >;
-        /// Returns the list of currently connected peers.
-        async fn get_peer_list(
-            &self,
-            request: tonic::Request<super::None>,
-        ) -> std::result::Result<tonic::Response<super::PeerList>, tonic::Status>;
-        /// Adds the block to the current block list.
-        async fn add_block(
-            &self,
-            request: tonic::Request<super::Block>,
-        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status>;
-        /// Gets the block by its block height or hash. If hash, set block height to 0.
-        async fn get_block(
-            &self,
+        /// Estimates the fee rate in satoshis per kilobyte for a transaction to be confirmed within the target number of blocks.
+        pub async fn estimate_fee(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FeeEstimateReq>,
+        ) -> std::result::Result<tonic::Response<super::FeeEstimate>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/EstimateFee");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "EstimateFee"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns a template of the next block for external miners. The miner adds a coinbase claiming the reward and fees.
+        pub async fn get_block_template(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<tonic::Response<super::BlockTemplate>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GetBlockTemplate");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "GetBlockTemplate"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Submits a block solved by an external miner, which is added to the chain and announced to the peers.
+        pub async fn submit_block(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Block>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/SubmitBlock");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "SubmitBlock"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Mines blocks paying the reward to the address without delay and returns their hashes. At most 100 blocks are generated per request. Only available in regtest.
+        pub async fn generate_blocks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GenerateBlocksReq>,
+        ) -> std::result::Result<tonic::Response<super::BlockHashes>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GenerateBlocks");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "GenerateBlocks"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Lists the peers with a ban score or an active ban. Only available locally.
+        pub async fn list_bans(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<tonic::Response<super::BanList>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/ListBans");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "ListBans"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Bans a peer by its IP address or URL. Only available locally.
+        pub async fn ban_peer(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BanReq>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/BanPeer");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "BanPeer"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Lifts the ban of a peer and resets its ban score. Only available locally.
+        pub async fn unban_peer(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BanReq>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/UnbanPeer");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "UnbanPeer"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Gets the block by its block height or hash. If hash, set block height to 0.
+        pub async fn get_block(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BlockReq>,
+        ) -> std::result::Result<tonic::Response<super::Block>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GetBlock");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "GetBlock"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the connection details of the currently connected peers.
+        pub async fn get_peer_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<tonic::Response<super::PeerInfoList>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GetPeerInfo");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "GetPeerInfo"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the state of the chain, the pending transactions and the node, for monitoring.
+        pub async fn get_chain_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<tonic::Response<super::ChainInfo>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/GetChainInfo");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "GetChainInfo"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Streams the blocks connected to the chain and the ones disconnected by reorganizations.
+        pub async fn subscribe_blocks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::BlockEvent>>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/SubscribeBlocks");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "SubscribeBlocks"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Streams the transactions entering the pending transactions.
+        pub async fn subscribe_transactions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::TransactionEvent>>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/proto_node.Rpc/SubscribeTransactions");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "SubscribeTransactions"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Streams the transactions sending to or from the address as they become pending, confirmed or reverted.
+        pub async fn subscribe_address(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PublicKey>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::AddressEvent>>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/SubscribeAddress");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "SubscribeAddress"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Saves the pending transactions to disk, which are otherwise saved on shutdown.
+        pub async fn save_mempool(
+            &mut self,
+            request: impl tonic::IntoRequest<super::None>,
+        ) -> std::result::Result<tonic::Response<super::SavedMempool>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto_node.Rpc/SaveMempool");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("proto_node.Rpc", "SaveMempool"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod node_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with NodeServer.
+    #[async_trait]
+    pub trait Node: Send + Sync + 'static {
+        /// Exchanges basic information with an another node. If peers are authenticated, the connection has to be authenticated first.
+        async fn handshake(
+            &self,
+            request: tonic::Request<super::HandshakeMessage>,
+        ) -> std::result::Result<tonic::Response<super::HandshakeMessage>, tonic::Status>;
+        /// Issues a nonce which the peer signs to authenticate the connection it was requested on.
+        async fn get_challenge(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::Challenge>, tonic::Status>;
+        /// Authenticates the connection by proving ownership of the identity key over the nonce issued on it. The answer proves the identity of the node in turn.
+        async fn authenticate(
+            &self,
+            request: tonic::Request<super::AuthMessage>,
+        ) -> std::result::Result<tonic::Response<super::AuthMessage>, tonic::Status>;
+        /// Returns the list of currently connected peers.
+        async fn get_peer_list(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::PeerList>, tonic::Status>;
+        /// Checks whether the node is alive. The node answers with the same nonce and its block height.
+        async fn ping(
+            &self,
+            request: tonic::Request<super::PingMessage>,
+        ) -> std::result::Result<tonic::Response<super::PingMessage>, tonic::Status>;
+        /// Adds the block to the current block list. Missing ancestors are requested from the sender.
+        async fn add_block(
+            &self,
+            request: tonic::Request<super::BlockAnnouncement>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status>;
+        /// Announces a block as header and short transaction IDs. The receiver reconstructs it from its pending transactions and requests the missing ones from the sender.
+        async fn add_compact_block(
+            &self,
+            request: tonic::Request<super::BlockAnnouncement>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status>;
+        /// Gets transactions of a recently announced block by their index.
+        async fn get_block_transactions(
+            &self,
+            request: tonic::Request<super::BlockTransactionsReq>,
+        ) -> std::result::Result<tonic::Response<super::BlockTransactions>, tonic::Status>;
+        /// Gets the block by its block height or hash. If hash, set block height to 0.
+        async fn get_block(
+            &self,
             request: tonic::Request<super::BlockReq>,
         ) -> std::result::Result<tonic::Response<super::Block>, tonic::Status>;
         /// Gets all blocks that constitute a chain.
@@ -384,6 +1071,507 @@ pub mod node_server {
             &self,
             request: tonic::Request<super::None>,
         ) -> std::result::Result<tonic::Response<super::Chain>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct NodeServer<T: Node> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Node> NodeServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for NodeServer<T>
+    where
+        T: Node,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/proto_node.Node/Handshake" => {
+                    #[allow(non_camel_case_types)]
+                    struct HandshakeSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::HandshakeMessage> for HandshakeSvc<T> {
+                        type Response = super::HandshakeMessage;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::HandshakeMessage>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Node>::handshake(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = HandshakeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/GetChallenge" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetChallengeSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::None> for GetChallengeSvc<T> {
+                        type Response = super::Challenge;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Node>::get_challenge(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetChallengeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/Authenticate" => {
+                    #[allow(non_camel_case_types)]
+                    struct AuthenticateSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::AuthMessage> for AuthenticateSvc<T> {
+                        type Response = super::AuthMessage;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AuthMessage>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Node>::authenticate(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AuthenticateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/GetPeerList" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPeerListSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::None> for GetPeerListSvc<T> {
+                        type Response = super::PeerList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Node>::get_peer_list(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPeerListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/Ping" => {
+                    #[allow(non_camel_case_types)]
+                    struct PingSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::PingMessage> for PingSvc<T> {
+                        type Response = super::PingMessage;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PingMessage>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Node>::ping(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PingSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/AddBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddBlockSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::BlockAnnouncement> for AddBlockSvc<T> {
+                        type Response = super::None;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BlockAnnouncement>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Node>::add_block(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/AddCompactBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddCompactBlockSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::BlockAnnouncement> for AddCompactBlockSvc<T> {
+                        type Response = super::None;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BlockAnnouncement>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Node>::add_compact_block(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddCompactBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/GetBlockTransactions" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockTransactionsSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::BlockTransactionsReq>
+                        for GetBlockTransactionsSvc<T>
+                    {
+                        type Response = super::BlockTransactions;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BlockTransactionsReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Node>::get_block_transactions(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBlockTransactionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/GetBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::BlockReq> for GetBlockSvc<T> {
+                        type Response = super::Block;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BlockReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Node>::get_block(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Node/GetChain" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetChainSvc<T: Node>(pub Arc<T>);
+                    impl<T: Node> tonic::server::UnaryService<super::None> for GetChainSvc<T> {
+                        type Response = super::Chain;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Node>::get_chain(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetChainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: Node> Clone for NodeServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: Node> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: Node> tonic::server::NamedService for NodeServer<T> {
+        const NAME: &'static str = "proto_node.Node";
+    }
+}
+/// Generated server implementations.
+pub mod rpc_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with RpcServer.
+    #[async_trait]
+    pub trait Rpc: Send + Sync + 'static {
         /// Adds a transaction to the pending transactions.
         async fn add_transaction(
             &self,
@@ -394,14 +1582,100 @@ pub mod node_server {
             &self,
             request: tonic::Request<super::TransactionReq>,
         ) -> std::result::Result<tonic::Response<super::Transaction>, tonic::Status>;
-        /// Gets a newly created transaction. Transaction is not added to the mem-pool it is missing a signature.
+        /// Gets the unspent outputs of a public key, marking the ones already spent by pending transactions.
         async fn get_utxo(
             &self,
             request: tonic::Request<super::PublicKey>,
         ) -> std::result::Result<tonic::Response<super::UnspentOutputs>, tonic::Status>;
+        /// Estimates the fee rate in satoshis per kilobyte for a transaction to be confirmed within the target number of blocks.
+        async fn estimate_fee(
+            &self,
+            request: tonic::Request<super::FeeEstimateReq>,
+        ) -> std::result::Result<tonic::Response<super::FeeEstimate>, tonic::Status>;
+        /// Returns a template of the next block for external miners. The miner adds a coinbase claiming the reward and fees.
+        async fn get_block_template(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::BlockTemplate>, tonic::Status>;
+        /// Submits a block solved by an external miner, which is added to the chain and announced to the peers.
+        async fn submit_block(
+            &self,
+            request: tonic::Request<super::Block>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status>;
+        /// Mines blocks paying the reward to the address without delay and returns their hashes. At most 100 blocks are generated per request. Only available in regtest.
+        async fn generate_blocks(
+            &self,
+            request: tonic::Request<super::GenerateBlocksReq>,
+        ) -> std::result::Result<tonic::Response<super::BlockHashes>, tonic::Status>;
+        /// Lists the peers with a ban score or an active ban. Only available locally.
+        async fn list_bans(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::BanList>, tonic::Status>;
+        /// Bans a peer by its IP address or URL. Only available locally.
+        async fn ban_peer(
+            &self,
+            request: tonic::Request<super::BanReq>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status>;
+        /// Lifts the ban of a peer and resets its ban score. Only available locally.
+        async fn unban_peer(
+            &self,
+            request: tonic::Request<super::BanReq>,
+        ) -> std::result::Result<tonic::Response<super::None>, tonic::Status>;
+        /// Gets the block by its block height or hash. If hash, set block height to 0.
+        async fn get_block(
+            &self,
+            request: tonic::Request<super::BlockReq>,
+        ) -> std::result::Result<tonic::Response<super::Block>, tonic::Status>;
+        /// Returns the connection details of the currently connected peers.
+        async fn get_peer_info(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::PeerInfoList>, tonic::Status>;
+        /// Returns the state of the chain, the pending transactions and the node, for monitoring.
+        async fn get_chain_info(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::ChainInfo>, tonic::Status>;
+        /// Server streaming response type for the SubscribeBlocks method.
+        type SubscribeBlocksStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::BlockEvent, tonic::Status>,
+            > + Send
+            + 'static;
+        /// Streams the blocks connected to the chain and the ones disconnected by reorganizations.
+        async fn subscribe_blocks(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeBlocksStream>, tonic::Status>;
+        /// Server streaming response type for the SubscribeTransactions method.
+        type SubscribeTransactionsStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::TransactionEvent, tonic::Status>,
+            > + Send
+            + 'static;
+        /// Streams the transactions entering the pending transactions.
+        async fn subscribe_transactions(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeTransactionsStream>, tonic::Status>;
+        /// Server streaming response type for the SubscribeAddress method.
+        type SubscribeAddressStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::AddressEvent, tonic::Status>,
+            > + Send
+            + 'static;
+        /// Streams the transactions sending to or from the address as they become pending, confirmed or reverted.
+        async fn subscribe_address(
+            &self,
+            request: tonic::Request<super::PublicKey>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeAddressStream>, tonic::Status>;
+        /// Saves the pending transactions to disk, which are otherwise saved on shutdown.
+        async fn save_mempool(
+            &self,
+            request: tonic::Request<super::None>,
+        ) -> std::result::Result<tonic::Response<super::SavedMempool>, tonic::Status>;
     }
+    /// Client-facing service of wallets, miners and administrators, served on its own address.
     #[derive(Debug)]
-    pub struct NodeServer<T: Node> {
+    pub struct RpcServer<T: Rpc> {
         inner: _Inner<T>,
         accept_compression_encodings: EnabledCompressionEncodings,
         send_compression_encodings: EnabledCompressionEncodings,
@@ -409,7 +1683,7 @@ pub mod node_server {
         max_encoding_message_size: Option<usize>,
     }
     struct _Inner<T>(Arc<T>);
-    impl<T: Node> NodeServer<T> {
+    impl<T: Rpc> RpcServer<T> {
         pub fn new(inner: T) -> Self {
             Self::from_arc(Arc::new(inner))
         }
@@ -423,10 +1697,7 @@ pub mod node_server {
                 max_encoding_message_size: None,
             }
         }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
         where
             F: tonic::service::Interceptor,
         {
@@ -461,9 +1732,9 @@ pub mod node_server {
             self
         }
     }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for NodeServer<T>
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for RpcServer<T>
     where
-        T: Node,
+        T: Rpc,
         B: Body + Send + 'static,
         B::Error: Into<StdError> + Send + 'static,
     {
@@ -479,24 +1750,96 @@ pub mod node_server {
         fn call(&mut self, req: http::Request<B>) -> Self::Future {
             let inner = self.inner.clone();
             match req.uri().path() {
-                "/proto_node.Node/Handshake" => {
+                "/proto_node.Rpc/AddTransaction" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddTransactionSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::Transaction> for AddTransactionSvc<T> {
+                        type Response = super::None;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Transaction>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Rpc>::add_transaction(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AddTransactionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/GetTransaction" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTransactionSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::TransactionReq> for GetTransactionSvc<T> {
+                        type Response = super::Transaction;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TransactionReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Rpc>::get_transaction(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTransactionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/GetUTXO" => {
                     #[allow(non_camel_case_types)]
-                    struct HandshakeSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::HandshakeMessage>
-                    for HandshakeSvc<T> {
-                        type Response = super::HandshakeMessage;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
+                    struct GetUTXOSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::PublicKey> for GetUTXOSvc<T> {
+                        type Response = super::UnspentOutputs;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::HandshakeMessage>,
+                            request: tonic::Request<super::PublicKey>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as Node>::handshake(&inner, request).await
-                            };
+                            let fut = async move { <T as Rpc>::get_utxo(&inner, request).await };
                             Box::pin(fut)
                         }
                     }
@@ -507,7 +1850,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = HandshakeSvc(inner);
+                        let method = GetUTXOSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -523,23 +1866,55 @@ pub mod node_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/GetPeerList" => {
+                "/proto_node.Rpc/EstimateFee" => {
                     #[allow(non_camel_case_types)]
-                    struct GetPeerListSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::None>
-                    for GetPeerListSvc<T> {
-                        type Response = super::PeerList;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
+                    struct EstimateFeeSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::FeeEstimateReq> for EstimateFeeSvc<T> {
+                        type Response = super::FeeEstimate;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::None>,
+                            request: tonic::Request<super::FeeEstimateReq>,
                         ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Rpc>::estimate_fee(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EstimateFeeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/GetBlockTemplate" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockTemplateSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::None> for GetBlockTemplateSvc<T> {
+                        type Response = super::BlockTemplate;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Node>::get_peer_list(&inner, request).await
+                                <T as Rpc>::get_block_template(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -551,7 +1926,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetPeerListSvc(inner);
+                        let method = GetBlockTemplateSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -567,24 +1942,55 @@ pub mod node_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/AddBlock" => {
+                "/proto_node.Rpc/SubmitBlock" => {
                     #[allow(non_camel_case_types)]
-                    struct AddBlockSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::Block>
-                    for AddBlockSvc<T> {
+                    struct SubmitBlockSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::Block> for SubmitBlockSvc<T> {
                         type Response = super::None;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::Block>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Rpc>::submit_block(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/GenerateBlocks" => {
+                    #[allow(non_camel_case_types)]
+                    struct GenerateBlocksSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::GenerateBlocksReq> for GenerateBlocksSvc<T> {
+                        type Response = super::BlockHashes;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Block>,
+                            request: tonic::Request<super::GenerateBlocksReq>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as Node>::add_block(&inner, request).await
-                            };
+                            let fut =
+                                async move { <T as Rpc>::generate_blocks(&inner, request).await };
                             Box::pin(fut)
                         }
                     }
@@ -595,7 +2001,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AddBlockSvc(inner);
+                        let method = GenerateBlocksSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -611,24 +2017,123 @@ pub mod node_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/GetBlock" => {
+                "/proto_node.Rpc/ListBans" => {
                     #[allow(non_camel_case_types)]
-                    struct GetBlockSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::BlockReq>
-                    for GetBlockSvc<T> {
+                    struct ListBansSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::None> for ListBansSvc<T> {
+                        type Response = super::BanList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Rpc>::list_bans(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListBansSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/BanPeer" => {
+                    #[allow(non_camel_case_types)]
+                    struct BanPeerSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::BanReq> for BanPeerSvc<T> {
+                        type Response = super::None;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::BanReq>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Rpc>::ban_peer(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BanPeerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/UnbanPeer" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnbanPeerSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::BanReq> for UnbanPeerSvc<T> {
+                        type Response = super::None;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::BanReq>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { <T as Rpc>::unban_peer(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnbanPeerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/GetBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::BlockReq> for GetBlockSvc<T> {
                         type Response = super::Block;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
                             request: tonic::Request<super::BlockReq>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as Node>::get_block(&inner, request).await
-                            };
+                            let fut = async move { <T as Rpc>::get_block(&inner, request).await };
                             Box::pin(fut)
                         }
                     }
@@ -655,24 +2160,16 @@ pub mod node_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/GetChain" => {
+                "/proto_node.Rpc/GetPeerInfo" => {
                     #[allow(non_camel_case_types)]
-                    struct GetChainSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::None>
-                    for GetChainSvc<T> {
-                        type Response = super::Chain;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::None>,
-                        ) -> Self::Future {
+                    struct GetPeerInfoSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::None> for GetPeerInfoSvc<T> {
+                        type Response = super::PeerInfoList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as Node>::get_chain(&inner, request).await
-                            };
+                            let fut =
+                                async move { <T as Rpc>::get_peer_info(&inner, request).await };
                             Box::pin(fut)
                         }
                     }
@@ -683,7 +2180,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetChainSvc(inner);
+                        let method = GetPeerInfoSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -699,24 +2196,16 @@ pub mod node_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/AddTransaction" => {
+                "/proto_node.Rpc/GetChainInfo" => {
                     #[allow(non_camel_case_types)]
-                    struct AddTransactionSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::Transaction>
-                    for AddTransactionSvc<T> {
-                        type Response = super::None;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::Transaction>,
-                        ) -> Self::Future {
+                    struct GetChainInfoSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::None> for GetChainInfoSvc<T> {
+                        type Response = super::ChainInfo;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as Node>::add_transaction(&inner, request).await
-                            };
+                            let fut =
+                                async move { <T as Rpc>::get_chain_info(&inner, request).await };
                             Box::pin(fut)
                         }
                     }
@@ -727,7 +2216,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = AddTransactionSvc(inner);
+                        let method = GetChainInfoSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -743,23 +2232,56 @@ pub mod node_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/GetTransaction" => {
+                "/proto_node.Rpc/SubscribeBlocks" => {
                     #[allow(non_camel_case_types)]
-                    struct GetTransactionSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::TransactionReq>
-                    for GetTransactionSvc<T> {
-                        type Response = super::Transaction;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::TransactionReq>,
-                        ) -> Self::Future {
+                    struct SubscribeBlocksSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::ServerStreamingService<super::None> for SubscribeBlocksSvc<T> {
+                        type Response = super::BlockEvent;
+                        type ResponseStream = T::SubscribeBlocksStream;
+                        type Future =
+                            BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Rpc>::subscribe_blocks(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubscribeBlocksSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto_node.Rpc/SubscribeTransactions" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeTransactionsSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::ServerStreamingService<super::None> for SubscribeTransactionsSvc<T> {
+                        type Response = super::TransactionEvent;
+                        type ResponseStream = T::SubscribeTransactionsStream;
+                        type Future =
+                            BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Node>::get_transaction(&inner, request).await
+                                <T as Rpc>::subscribe_transactions(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -771,7 +2293,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetTransactionSvc(inner);
+                        let method = SubscribeTransactionsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -782,29 +2304,26 @@ pub mod node_server {
                                 max_decoding_message_size,
                                 max_encoding_message_size,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.server_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto_node.Node/GetUTXO" => {
+                "/proto_node.Rpc/SubscribeAddress" => {
                     #[allow(non_camel_case_types)]
-                    struct GetUTXOSvc<T: Node>(pub Arc<T>);
-                    impl<T: Node> tonic::server::UnaryService<super::PublicKey>
-                    for GetUTXOSvc<T> {
-                        type Response = super::UnspentOutputs;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
+                    struct SubscribeAddressSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::ServerStreamingService<super::PublicKey> for SubscribeAddressSvc<T> {
+                        type Response = super::AddressEvent;
+                        type ResponseStream = T::SubscribeAddressStream;
+                        type Future =
+                            BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
                         fn call(
                             &mut self,
                             request: tonic::Request<super::PublicKey>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as Node>::get_utxo(&inner, request).await
-                            };
+                            let fut =
+                                async move { <T as Rpc>::subscribe_address(&inner, request).await };
                             Box::pin(fut)
                         }
                     }
@@ -815,7 +2334,7 @@ pub mod node_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetUTXOSvc(inner);
+                        let method = SubscribeAddressSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -826,27 +2345,59 @@ pub mod node_server {
                                 max_decoding_message_size,
                                 max_encoding_message_size,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.server_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                _ => {
-                    Box::pin(async move {
-                        Ok(
-                            http::Response::builder()
-                                .status(200)
-                                .header("grpc-status", "12")
-                                .header("content-type", "application/grpc")
-                                .body(empty_body())
-                                .unwrap(),
-                        )
-                    })
+                "/proto_node.Rpc/SaveMempool" => {
+                    #[allow(non_camel_case_types)]
+                    struct SaveMempoolSvc<T: Rpc>(pub Arc<T>);
+                    impl<T: Rpc> tonic::server::UnaryService<super::None> for SaveMempoolSvc<T> {
+                        type Response = super::SavedMempool;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::None>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as Rpc>::save_mempool(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SaveMempoolSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
                 }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
             }
         }
     }
-    impl<T: Node> Clone for NodeServer<T> {
+    impl<T: Rpc> Clone for RpcServer<T> {
         fn clone(&self) -> Self {
             let inner = self.inner.clone();
             Self {
@@ -858,7 +2409,7 @@ pub mod node_server {
             }
         }
     }
-    impl<T: Node> Clone for _Inner<T> {
+    impl<T: Rpc> Clone for _Inner<T> {
         fn clone(&self) -> Self {
             Self(Arc::clone(&self.0))
         }
@@ -868,7 +2419,7 @@ pub mod node_server {
             write!(f, "{:?}", self.0)
         }
     }
-    impl<T: Node> tonic::server::NamedService for NodeServer<T> {
-        const NAME: &'static str = "proto_node.Node";
+    impl<T: Rpc> tonic::server::NamedService for RpcServer<T> {
+        const NAME: &'static str = "proto_node.Rpc";
     }
 }
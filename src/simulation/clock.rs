@@ -0,0 +1,29 @@
+/// Clock of a simulation, which only advances when the simulation moves it.
+#[derive(Debug, Clone, Copy)]
+pub struct VirtualClock {
+    now_ms: u64,
+}
+
+impl VirtualClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self { now_ms: start_ms }
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms
+    }
+
+    /// Seconds since the Unix epoch, as used for block timestamps.
+    pub fn timestamp(&self) -> u64 {
+        self.now_ms / 1000
+    }
+
+    pub fn advance_to(&mut self, time_ms: u64) {
+        assert!(
+            time_ms >= self.now_ms,
+            "The virtual clock cannot go backwards."
+        );
+        self.now_ms = time_ms;
+    }
+}
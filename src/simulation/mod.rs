@@ -0,0 +1,4 @@
+pub mod clock;
+pub mod node;
+pub mod simulation;
+pub mod transport;
@@ -0,0 +1,338 @@
+use std::{
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    block::Block,
+    chain::{self, ChainUpdate},
+    config::{chain_params::ChainParams, models::MiningConfig},
+    crypto::{
+        account::Account,
+        hash_utils::{Address, HashResult},
+    },
+    database::{database::DatabaseType, InMemoryDatabase},
+    mining::{
+        miner::Miner,
+        pow_utils::{solve_sequential, verify_proof_of_work},
+        template::BlockTemplate,
+    },
+    types::Satoshi,
+};
+
+use super::{clock::VirtualClock, transport::NodeId};
+
+/// Node of a simulation. It mines with real proof of work on a single thread,
+/// while the time it takes is derived from the number of hashes and the
+/// node's hashrate on the virtual clock.
+pub struct SimulatedNode {
+    pub id: NodeId,
+    /// Address the node's block rewards are paid to.
+    address: Address,
+    database: Arc<Mutex<DatabaseType>>,
+    miner: Miner,
+    /// Difficulty the miner solves blocks with.
+    difficulty: Arc<AtomicU8>,
+    config: MiningConfig,
+    chain_params: ChainParams,
+    /// Hashes per virtual second.
+    hashrate: u64,
+    /// Virtual time at which the node started mining on the current head.
+    mining_started_ms: u64,
+    /// Block the node finds on the current head, unless another block comes first.
+    next_block: Option<(u64, Block)>,
+    /// Received blocks whose parent has not arrived yet.
+    orphans: Vec<Block>,
+    mined_blocks: usize,
+    rejected_blocks: usize,
+    /// Number of times the node switched to a competing chain.
+    reorganizations: usize,
+}
+
+impl SimulatedNode {
+    pub fn new(
+        id: NodeId,
+        config: MiningConfig,
+        chain_params: ChainParams,
+        account: Account,
+        hashrate: u64,
+        clock: &VirtualClock,
+    ) -> Self {
+        let database: Arc<Mutex<DatabaseType>> = Arc::new(Mutex::new(InMemoryDatabase::default()));
+        database
+            .lock()
+            .unwrap()
+            .create_genesis_block(chain_params.genesis_block());
+        let difficulty = Arc::new(AtomicU8::new(chain_params.start_difficulty_bit));
+
+        let mut node = Self {
+            id,
+            address: account.public_key().to_vec(),
+            database,
+            miner: Miner::new(
+                config.clone(),
+                chain_params.clone(),
+                Arc::new(account),
+                difficulty.clone(),
+            ),
+            difficulty,
+            config,
+            chain_params,
+            hashrate: hashrate.max(1),
+            mining_started_ms: clock.now_ms(),
+            next_block: None,
+            orphans: vec![],
+            mined_blocks: 0,
+            rejected_blocks: 0,
+            reorganizations: 0,
+        };
+        node.start_mining(clock);
+        node
+    }
+
+    /// Virtual time at which the node finds its next block.
+    pub fn next_block_at(&self) -> Option<u64> {
+        self.next_block.as_ref().map(|x| x.0)
+    }
+
+    /// Inserts the block the node has found and returns it for relaying.
+    pub fn take_mined_block(&mut self, clock: &VirtualClock) -> Option<Block> {
+        let (_, block) = self.next_block.take()?;
+        self.mined_blocks += 1;
+        if let Err(reason) = chain::add_block(&self.database, &self.chain_params, block.clone()) {
+            log::error!("Node {} mined an invalid block: {}.", self.id, reason);
+        }
+        self.chain_updated(clock);
+        Some(block)
+    }
+
+    /// Verifies a block received from another node and inserts it on success.
+    /// Blocks competing with the head are kept on a side chain, which the node
+    /// switches to once it is longer. Blocks arriving before their parent are
+    /// kept until it arrives.
+    pub fn receive_block(&mut self, block: Block, clock: &VirtualClock) -> bool {
+        if !verify_proof_of_work(&block.header, &block.hash) {
+            self.reject_block(&block);
+            return false;
+        }
+        let parent_known = self
+            .database
+            .lock()
+            .unwrap()
+            .get_block(&block.header.previous_block_hash)
+            .is_some();
+        if !parent_known {
+            self.orphans.push(block);
+            return false;
+        }
+        let block_hash = block.hash;
+        match chain::add_block(&self.database, &self.chain_params, block.clone()) {
+            Ok(ChainUpdate::Reorganized(depth)) => {
+                log::debug!(
+                    "Node {} switched to the longer chain of block ({}), replacing {} blocks.",
+                    self.id,
+                    block.get_hash_as_string(true),
+                    depth
+                );
+                self.reorganizations += 1;
+            }
+            Ok(_) => {}
+            Err(_) => {
+                self.reject_block(&block);
+                return false;
+            }
+        }
+        self.chain_updated(clock);
+
+        let children: Vec<Block>;
+        (children, self.orphans) = std::mem::take(&mut self.orphans)
+            .into_iter()
+            .partition(|orphan| orphan.header.previous_block_hash == block_hash);
+        for child in children {
+            self.receive_block(child, clock);
+        }
+        true
+    }
+
+    fn reject_block(&mut self, block: &Block) {
+        log::debug!(
+            "Node {} rejected block ({}).",
+            self.id,
+            block.get_hash_as_string(true)
+        );
+        self.rejected_blocks += 1;
+    }
+
+    /// Starts mining on the head, unless it is the one the node mines on already.
+    fn chain_updated(&mut self, clock: &VirtualClock) {
+        let (head, head_difficulty, block_height) = {
+            let db = self.database.lock().unwrap();
+            let head = db.head().unwrap();
+            (head.hash, head.header.difficulty, db.block_height())
+        };
+        let mining_on = self
+            .next_block
+            .as_ref()
+            .map(|(_, block)| block.header.previous_block_hash);
+        // a block on a side chain does not change what the node mines on
+        if mining_on == Some(head) {
+            return;
+        }
+
+        let mining_time_ms = clock.now_ms() - self.mining_started_ms;
+        self.miner.add_mining_time(
+            Duration::from_millis(mining_time_ms),
+            mining_time_ms * self.hashrate / 1000,
+        );
+        self.difficulty.store(head_difficulty, Ordering::Relaxed);
+        if !self.chain_params.fixed_difficulty
+            && block_height.is_multiple_of(self.chain_params.block_adjustment_interval)
+        {
+            self.miner.adjust_difficulty();
+        }
+        self.start_mining(clock);
+    }
+
+    /// Solves a block on top of the current head and schedules it for the
+    /// time the node's hashrate needs for the hashes computed.
+    fn start_mining(&mut self, clock: &VirtualClock) {
+        self.mining_started_ms = clock.now_ms();
+        let mut extra_nonce = 0u64;
+
+        loop {
+            let db = self.database.lock().unwrap();
+            let mut template = BlockTemplate::new(
+                &*db,
+                self.miner.difficulty(),
+                self.chain_params.block_reward(db.block_height()),
+                self.config.max_block_size,
+            );
+            drop(db);
+            template.timestamp = clock.timestamp();
+            let mut block =
+                template.assemble(self.miner.coinbase_transactions(&template, extra_nonce));
+
+            if let Some((block_hash, hash_count)) =
+                solve_sequential(block.header.difficulty, &mut block.header)
+            {
+                block.hash = block_hash;
+                let found_at = clock.now_ms() + hash_count * 1000 / self.hashrate;
+                self.next_block = Some((found_at, block));
+                return;
+            }
+            extra_nonce += 1;
+        }
+    }
+
+    pub fn head_hash(&self) -> HashResult {
+        self.database.lock().unwrap().head().unwrap().hash
+    }
+
+    pub fn block_height(&self) -> usize {
+        self.database.lock().unwrap().block_height()
+    }
+
+    pub fn difficulty(&self) -> u8 {
+        self.miner.difficulty()
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Sum of the unspent outputs of the address on the node's chain.
+    pub fn balance(&self, address: &Address) -> Satoshi {
+        let db = self.database.lock().unwrap();
+        db.get_utxo(address).iter().map(|(_, _, value)| value).sum()
+    }
+
+    pub fn mined_blocks(&self) -> usize {
+        self.mined_blocks
+    }
+
+    pub fn rejected_blocks(&self) -> usize {
+        self.rejected_blocks
+    }
+
+    pub fn reorganizations(&self) -> usize {
+        self.reorganizations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimulatedNode;
+    use crate::{
+        config::{
+            chain_params::ChainParams,
+            models::{AccountConfig, MiningConfig},
+        },
+        crypto::account::Account,
+        simulation::clock::VirtualClock,
+    };
+
+    fn node(id: usize, clock: &VirtualClock) -> SimulatedNode {
+        let account = Account::from_seed(
+            AccountConfig {
+                keys_path: String::new(),
+            },
+            &[id as u8 + 1; 32],
+        )
+        .unwrap();
+        let mining = MiningConfig {
+            enabled: true,
+            max_block_size: 1_000_000,
+            threads: 1,
+            template_refresh_secs: 5,
+        };
+        let chain = ChainParams {
+            name: "simulation".to_string(),
+            start_difficulty_bit: 6,
+            ..ChainParams::mainnet()
+        };
+        SimulatedNode::new(id, mining, chain, account, 10, clock)
+    }
+
+    #[test]
+    fn test_competing_blocks() {
+        let clock = VirtualClock::new(0);
+        let mut x = node(0, &clock);
+        let mut y = node(1, &clock);
+        let mut z = node(2, &clock);
+        let x1 = x.take_mined_block(&clock).unwrap();
+        let x2 = x.take_mined_block(&clock).unwrap();
+        let y1 = y.take_mined_block(&clock).unwrap();
+
+        // the competing block is kept until its chain is longer
+        assert!(y.receive_block(x1.clone(), &clock));
+        assert_eq!(y.head_hash(), y1.hash);
+        assert!(y.receive_block(x2.clone(), &clock));
+        assert_eq!(y.head_hash(), x2.hash);
+        assert_eq!(y.reorganizations(), 1);
+        // the reward of the replaced block is gone, the one spending it is not pending
+        let reward = y.chain_params.block_reward(1);
+        assert_eq!(y.balance(y.address()), 0);
+        assert_eq!(y.balance(x.address()), 2 * reward);
+        assert!(y
+            .database
+            .lock()
+            .unwrap()
+            .get_pending_transactions()
+            .is_empty());
+        assert_ne!(
+            y.next_block.as_ref().unwrap().1.header.previous_block_hash,
+            y1.hash
+        );
+
+        // a block arriving before its parent is connected with it
+        assert!(!z.receive_block(x2.clone(), &clock));
+        assert!(z.receive_block(x1, &clock));
+        assert_eq!(z.head_hash(), x2.hash);
+        assert_eq!(z.block_height(), 3);
+
+        assert_eq!(y.rejected_blocks() + z.rejected_blocks(), 0);
+    }
+}
@@ -0,0 +1,203 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    config::models::{AccountConfig, SimulationConfig},
+    crypto::account::Account,
+};
+
+use super::{
+    clock::VirtualClock,
+    node::SimulatedNode,
+    transport::{InMemoryTransport, Message},
+};
+
+/// Virtual time at which every simulation starts, in milliseconds since the Unix epoch.
+const SIMULATION_EPOCH_MS: u64 = 1_700_000_000_000;
+
+/// Deterministic simulation of a network of nodes within one process.
+///
+/// Time only passes on a virtual clock and all randomness comes from a
+/// seeded generator, so a scenario replays exactly for the same configuration.
+/// Events, i.e. nodes finding blocks and messages arriving, are processed one
+/// at a time in the order of their virtual time.
+pub struct Simulation {
+    config: SimulationConfig,
+    clock: VirtualClock,
+    rng: StdRng,
+    nodes: Vec<SimulatedNode>,
+    transport: InMemoryTransport,
+    mined_blocks: usize,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Result<Self, String> {
+        let clock = VirtualClock::new(SIMULATION_EPOCH_MS);
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        let mut nodes = Vec::with_capacity(config.nodes);
+        for id in 0..config.nodes {
+            let seed: [u8; 32] = rng.gen();
+            let account_config = AccountConfig {
+                keys_path: format!("./configs/keys_simulation_{}.pkcs8", id),
+            };
+            let account = Account::from_seed(account_config, &seed)
+                .map_err(|err| format!("Failed to create the account of node {}: {:?}", id, err))?;
+            nodes.push(SimulatedNode::new(
+                id,
+                config.mining.clone(),
+                config.chain.clone(),
+                account,
+                config.hashrate,
+                &clock,
+            ));
+        }
+
+        Ok(Self {
+            transport: InMemoryTransport::new(config.min_latency_ms, config.max_latency_ms),
+            config,
+            clock,
+            rng,
+            nodes,
+            mined_blocks: 0,
+        })
+    }
+
+    /// Runs until the configured number of blocks has been mined.
+    pub fn run(&mut self) {
+        while self.mined_blocks < self.config.blocks && self.step() {}
+    }
+
+    /// Processes the next event. Returns false if there is none left.
+    pub fn step(&mut self) -> bool {
+        // ties are broken by the node id, so that the order is deterministic
+        let next_block = self
+            .nodes
+            .iter()
+            .filter_map(|node| node.next_block_at().map(|at| (at, node.id)))
+            .min();
+        let next_delivery = self.transport.next_delivery_ms();
+
+        match (next_block, next_delivery) {
+            (next_block, Some(deliver_at)) if next_block.is_none_or(|(at, _)| deliver_at <= at) => {
+                let envelope = self.transport.pop().unwrap();
+                self.clock.advance_to(envelope.deliver_at_ms);
+                match envelope.message {
+                    Message::Block(block) => {
+                        self.nodes[envelope.to].receive_block(block, &self.clock);
+                    }
+                }
+            }
+            (Some((found_at, id)), _) => {
+                self.clock.advance_to(found_at);
+                let Some(block) = self.nodes[id].take_mined_block(&self.clock) else {
+                    return false;
+                };
+                log::debug!(
+                    "Node {} mined block ({}) at height {}.",
+                    id,
+                    block.get_hash_as_string(true),
+                    self.nodes[id].block_height() - 1
+                );
+                self.mined_blocks += 1;
+
+                for to in (0..self.nodes.len()).filter(|to| *to != id) {
+                    self.transport.send(
+                        &mut self.rng,
+                        self.clock.now_ms(),
+                        (id, to),
+                        Message::Block(block.clone()),
+                    );
+                }
+            }
+            (None, None) => return false,
+            (None, Some(_)) => unreachable!(),
+        }
+        true
+    }
+
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Virtual time passed since the start of the simulation.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.clock.now_ms() - SIMULATION_EPOCH_MS
+    }
+
+    pub fn nodes(&self) -> &[SimulatedNode] {
+        &self.nodes
+    }
+
+    pub fn mined_blocks(&self) -> usize {
+        self.mined_blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{
+        chain_params::ChainParams,
+        models::{MiningConfig, SimulationConfig},
+    };
+
+    use super::Simulation;
+
+    fn config(seed: u64) -> SimulationConfig {
+        SimulationConfig {
+            seed,
+            nodes: 3,
+            blocks: 30,
+            hashrate: 10,
+            min_latency_ms: 50,
+            max_latency_ms: 500,
+            mining: MiningConfig {
+                enabled: true,
+                max_block_size: 1_000_000,
+                threads: 1,
+                template_refresh_secs: 5,
+            },
+            chain: ChainParams {
+                name: "simulation".to_string(),
+                block_time_secs: 10,
+                start_difficulty_bit: 6,
+                ..ChainParams::mainnet()
+            },
+        }
+    }
+
+    fn run(seed: u64) -> (u64, Vec<(usize, [u8; 32])>) {
+        let mut simulation = Simulation::new(config(seed)).unwrap();
+        simulation.run();
+        assert_eq!(simulation.mined_blocks(), 30);
+        // competing blocks are not mistaken for invalid ones
+        assert!(simulation
+            .nodes()
+            .iter()
+            .all(|node| node.rejected_blocks() == 0));
+        // the unspent outputs hold the rewards of the node's chain, not those of replaced blocks
+        for node in simulation.nodes() {
+            let rewards: u64 = (1..node.block_height())
+                .map(|height| simulation.config.chain.block_reward(height))
+                .sum();
+            let balances: u64 = simulation
+                .nodes()
+                .iter()
+                .map(|miner| node.balance(miner.address()))
+                .sum();
+            assert_eq!(balances, rewards);
+        }
+        let heads = simulation
+            .nodes()
+            .iter()
+            .map(|node| (node.block_height(), node.head_hash()))
+            .collect();
+        (simulation.clock().now_ms(), heads)
+    }
+
+    #[test]
+    fn test_replay() {
+        let first = run(7);
+        assert_eq!(first, run(7));
+        assert_ne!(first, run(8));
+    }
+}
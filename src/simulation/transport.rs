@@ -0,0 +1,116 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use rand::{rngs::StdRng, Rng};
+
+use crate::block::Block;
+
+/// Index of a node within the simulation.
+pub type NodeId = usize;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Block(Block),
+}
+
+#[derive(Debug)]
+pub struct Envelope {
+    pub deliver_at_ms: u64,
+    /// Order in which the messages were sent, which breaks ties of the delivery time.
+    sequence: u64,
+    pub from: NodeId,
+    pub to: NodeId,
+    pub message: Message,
+}
+
+impl PartialEq for Envelope {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Envelope {}
+impl PartialOrd for Envelope {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Envelope {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deliver_at_ms, self.sequence).cmp(&(other.deliver_at_ms, other.sequence))
+    }
+}
+
+/// Passes messages between the nodes of a simulation, each delayed by a
+/// random latency. Messages are delivered in the order of their delivery
+/// time; messages due at the same time in the order they were sent.
+pub struct InMemoryTransport {
+    queue: BinaryHeap<Reverse<Envelope>>,
+    next_sequence: u64,
+    min_latency_ms: u64,
+    max_latency_ms: u64,
+}
+
+impl InMemoryTransport {
+    pub fn new(min_latency_ms: u64, max_latency_ms: u64) -> Self {
+        Self {
+            queue: BinaryHeap::new(),
+            next_sequence: 0,
+            min_latency_ms,
+            max_latency_ms: max_latency_ms.max(min_latency_ms),
+        }
+    }
+
+    pub fn send(
+        &mut self,
+        rng: &mut StdRng,
+        now_ms: u64,
+        (from, to): (NodeId, NodeId),
+        message: Message,
+    ) {
+        let latency = rng.gen_range(self.min_latency_ms..=self.max_latency_ms);
+        self.queue.push(Reverse(Envelope {
+            deliver_at_ms: now_ms + latency,
+            sequence: self.next_sequence,
+            from,
+            to,
+            message,
+        }));
+        self.next_sequence += 1;
+    }
+
+    /// Delivery time of the next message, if any message is in flight.
+    pub fn next_delivery_ms(&self) -> Option<u64> {
+        self.queue.peek().map(|x| x.0.deliver_at_ms)
+    }
+
+    /// Removes the next message to be delivered.
+    pub fn pop(&mut self) -> Option<Envelope> {
+        self.queue.pop().map(|x| x.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::block::Block;
+
+    use super::{InMemoryTransport, Message};
+
+    #[test]
+    fn test_delivery_order() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut transport = InMemoryTransport::new(100, 100);
+        transport.send(&mut rng, 50, (0, 1), Message::Block(Block::default()));
+        transport.send(&mut rng, 0, (0, 2), Message::Block(Block::default()));
+        transport.send(&mut rng, 0, (1, 2), Message::Block(Block::default()));
+
+        assert_eq!(transport.next_delivery_ms(), Some(100));
+        let first = transport.pop().unwrap();
+        let second = transport.pop().unwrap();
+        assert_eq!((first.from, first.to), (0, 2));
+        assert_eq!((second.from, second.to), (1, 2));
+        assert_eq!(transport.pop().unwrap().deliver_at_ms, 150);
+        assert!(transport.pop().is_none());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{
+    crypto::{
+        account::Account,
+        hash_utils::{sha256, Address, HashResult},
+    },
+    types::Satoshi,
+};
+
+use super::{
+    input::{Input, SEQUENCE_REPLACEABLE},
+    output::Output,
+    script::{Item, Operation, Script},
+    Transaction,
+};
+
+/// Maximum number of bytes which can be embedded into an OP_RETURN output.
+pub const MAX_OP_RETURN_SIZE: usize = 80;
+
+/// Builds and signs a transaction paying to any number of receivers.
+///
+/// All added inputs are spent. Whatever remains after paying the outputs and
+/// the fee is sent to the change address, which defaults to the sender.
+pub struct TransactionBuilder<'a> {
+    account: &'a Account,
+    inputs: Vec<(HashResult, u32, Satoshi)>,
+    outputs: Vec<(Address, Satoshi)>,
+    change_address: Option<Address>,
+    data: Option<Vec<u8>>,
+    fee: Satoshi,
+    replaceable: bool,
+}
+
+impl<'a> TransactionBuilder<'a> {
+    pub fn new(account: &'a Account) -> Self {
+        Self {
+            account,
+            inputs: vec![],
+            outputs: vec![],
+            change_address: None,
+            data: None,
+            fee: 0,
+            replaceable: false,
+        }
+    }
+
+    /// Adds an input given by prev tx hash, prev output tx index and value.
+    pub fn add_input(mut self, tx_hash: HashResult, output_index: u32, value: Satoshi) -> Self {
+        self.inputs.push((tx_hash, output_index, value));
+        self
+    }
+
+    pub fn add_inputs(mut self, inputs: Vec<(HashResult, u32, Satoshi)>) -> Self {
+        self.inputs.extend(inputs);
+        self
+    }
+
+    /// Adds a pay-to-pub-key-hash output for the receiver.
+    pub fn add_output(mut self, receiver_pub_key: &[u8], amount: Satoshi) -> Self {
+        self.outputs.push((receiver_pub_key.to_vec(), amount));
+        self
+    }
+
+    pub fn change_address(mut self, address: Option<Address>) -> Self {
+        self.change_address = address;
+        self
+    }
+
+    /// Embeds arbitrary data into an unspendable OP_RETURN output.
+    pub fn op_return(mut self, data: Option<Vec<u8>>) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn fee(mut self, fee: Satoshi) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Signals that the transaction may be replaced by one paying a higher fee.
+    pub fn replaceable(mut self, replaceable: bool) -> Self {
+        self.replaceable = replaceable;
+        self
+    }
+
+    pub fn build(self) -> Result<Transaction, String> {
+        if self.outputs.is_empty() {
+            return Err("Transaction needs at least one receiver.".to_string());
+        }
+        if let Some(data) = &self.data {
+            if data.len() > MAX_OP_RETURN_SIZE {
+                return Err(format!(
+                    "OP_RETURN data exceeds {} bytes.",
+                    MAX_OP_RETURN_SIZE
+                ));
+            }
+        }
+
+        let total_input_value: Satoshi = self.inputs.iter().map(|x| x.2).sum();
+        let total_output_value: Satoshi = self.outputs.iter().map(|x| x.1).sum();
+        if total_input_value < total_output_value + self.fee {
+            return Err("Insufficient funds for the transaction".to_string());
+        }
+
+        let tx_inputs = self
+            .inputs
+            .iter()
+            .map(|(prev_tx_hash, prev_tx_output_index, _)| {
+                let mut input = Input::new(
+                    *prev_tx_hash,
+                    *prev_tx_output_index,
+                    Script::new(vec![
+                        Item::Data(vec![], Some("sig".to_string())),
+                        Item::Data(self.account.public_key().to_vec(), None),
+                    ]),
+                );
+                if self.replaceable {
+                    input.sequence = SEQUENCE_REPLACEABLE;
+                }
+                input
+            })
+            .collect();
+
+        let mut tx_outputs: Vec<Output> = self
+            .outputs
+            .iter()
+            .map(|(receiver, amount)| pay_to_pub_key_hash(receiver, *amount))
+            .collect();
+
+        let change = total_input_value - total_output_value - self.fee;
+        if change > 0 {
+            let change_address = self
+                .change_address
+                .clone()
+                .unwrap_or_else(|| self.account.public_key().to_vec());
+            tx_outputs.push(pay_to_pub_key_hash(&change_address, change));
+        }
+
+        if let Some(data) = self.data {
+            tx_outputs.push(Output::new(
+                0,
+                Script::new(vec![
+                    Item::Operation(Operation::Return),
+                    Item::Data(data, None),
+                ]),
+                vec![],
+            ));
+        }
+
+        let mut tx = Transaction::new(self.account.public_key().to_vec(), tx_inputs, tx_outputs);
+        let tx_hash = tx.hash();
+
+        for input in &mut tx.inputs {
+            let item = input.script_sig.items.iter_mut().find(|item| match item {
+                Item::Data(_, Some(name)) => name == "sig",
+                _ => false,
+            });
+
+            let item = item.expect("Cannot find prepared script item for transaction hash.");
+            let sig = self.account.sign(&tx_hash).to_vec();
+            *item = Item::Data(sig, Some("sig".to_string()));
+        }
+        Ok(tx)
+    }
+}
+
+fn pay_to_pub_key_hash(receiver_pub_key: &[u8], amount: Satoshi) -> Output {
+    Output::new(
+        amount,
+        Script::new(vec![
+            Item::Operation(Operation::Dup),
+            Item::Operation(Operation::Hash256),
+            Item::Data(sha256(receiver_pub_key).to_vec(), None),
+            Item::Operation(Operation::EqualVerify),
+            Item::Operation(Operation::CheckSig),
+        ]),
+        receiver_pub_key.to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::models::AccountConfig,
+        crypto::account::Account,
+        transaction::builder::{TransactionBuilder, MAX_OP_RETURN_SIZE},
+    };
+
+    fn account() -> Account {
+        Account::new(AccountConfig {
+            keys_path: "./configs/keys_test.pkcs8".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_multiple_receivers_with_change() {
+        let account = account();
+        let tx = TransactionBuilder::new(&account)
+            .add_input([1u8; 32], 0, 100)
+            .add_input([2u8; 32], 1, 50)
+            .add_output(&[3u8; 32], 60)
+            .add_output(&[4u8; 32], 70)
+            .change_address(Some(vec![5u8; 32]))
+            .fee(5)
+            .build()
+            .unwrap();
+
+        assert_eq!(tx.inputs.len(), 2);
+        assert_eq!(tx.outputs.len(), 3);
+        assert_eq!(tx.outputs[2].value, 15);
+        assert_eq!(tx.outputs[2].receiver, vec![5u8; 32]);
+        assert!(!tx.signals_replacement());
+    }
+
+    #[test]
+    fn test_replaceable() {
+        let account = account();
+        let tx = TransactionBuilder::new(&account)
+            .add_input([1u8; 32], 0, 10)
+            .add_output(&[3u8; 32], 10)
+            .replaceable(true)
+            .build()
+            .unwrap();
+
+        assert!(tx.signals_replacement());
+    }
+
+    #[test]
+    fn test_op_return() {
+        let account = account();
+        let tx = TransactionBuilder::new(&account)
+            .add_input([1u8; 32], 0, 10)
+            .add_output(&[3u8; 32], 10)
+            .op_return(Some(b"payroll".to_vec()))
+            .build()
+            .unwrap();
+
+        assert_eq!(tx.outputs.len(), 2);
+        assert!(tx.outputs[1].is_unspendable());
+        assert_eq!(tx.outputs[1].value, 0);
+
+        let too_large = TransactionBuilder::new(&account)
+            .add_input([1u8; 32], 0, 10)
+            .add_output(&[3u8; 32], 10)
+            .op_return(Some(vec![0u8; MAX_OP_RETURN_SIZE + 1]))
+            .build();
+        assert!(too_large.is_err());
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        let account = account();
+        let tx = TransactionBuilder::new(&account)
+            .add_input([1u8; 32], 0, 10)
+            .add_output(&[3u8; 32], 10)
+            .fee(1)
+            .build();
+
+        assert!(tx.is_err());
+    }
+}
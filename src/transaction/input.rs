@@ -4,12 +4,23 @@ use crate::crypto::hash_utils::HashResult;
 
 use super::script::Script;
 
+/// Sequence number of inputs which do not allow replacement.
+pub const SEQUENCE_FINAL: u32 = u32::MAX;
+/// Sequence number signaling that the transaction may be replaced by one paying a higher fee.
+pub const SEQUENCE_REPLACEABLE: u32 = u32::MAX - 2;
+
+fn default_sequence() -> u32 {
+    SEQUENCE_FINAL
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Input {
     pub utxo_tx_hash: HashResult,
     pub utxo_output_index: u32,
     pub script_size: u16,
     pub script_sig: Script,
+    #[serde(default = "default_sequence")]
+    pub sequence: u32,
 }
 impl Input {
     pub fn new(prev_tx_hash: HashResult, prev_tx_output_index: u32, script_sig: Script) -> Self {
@@ -19,6 +30,7 @@ impl Input {
             utxo_output_index: prev_tx_output_index,
             script_size: script_size as u16,
             script_sig,
+            sequence: SEQUENCE_FINAL,
         }
     }
     pub fn hash(&self) -> Vec<u8> {
@@ -29,6 +41,21 @@ impl Input {
         for b in self.script_sig.hash() {
             result.push(b);
         }
+        for b in self.sequence.to_be_bytes() {
+            result.push(b);
+        }
         result
     }
+    /// Number of bytes the input occupies: outpoint, script size, the script itself and sequence.
+    pub fn size(&self) -> usize {
+        self.utxo_tx_hash.len() + 4 + 2 + self.script_sig.size() + 4
+    }
+    /// Checks if the input signals opt-in replace-by-fee.
+    pub fn signals_replacement(&self) -> bool {
+        self.sequence < SEQUENCE_FINAL - 1
+    }
+    /// Checks if both inputs spend the same transaction output.
+    pub fn spends_same_output(&self, other: &Input) -> bool {
+        self.utxo_tx_hash == other.utxo_tx_hash && self.utxo_output_index == other.utxo_output_index
+    }
 }
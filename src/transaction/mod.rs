@@ -1,3 +1,4 @@
+pub mod builder;
 pub mod input;
 pub mod output;
 pub mod script;
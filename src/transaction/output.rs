@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::types::Satoshi;
 
-use super::script::Script;
+use super::script::{Item, Operation, Script};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Output {
@@ -32,4 +32,15 @@ impl Output {
         }
         result
     }
+    /// Outputs starting with OP_RETURN only carry data and can never be spent.
+    pub fn is_unspendable(&self) -> bool {
+        matches!(
+            self.script_pub_key.items.first(),
+            Some(Item::Operation(Operation::Return))
+        )
+    }
+    /// Number of bytes the output occupies, including the receiver meta-data.
+    pub fn size(&self) -> usize {
+        8 + self.script_pub_key.size() + self.receiver.len()
+    }
 }
@@ -53,6 +53,16 @@ impl Script {
         }
         result
     }
+    /// Number of bytes the script occupies, including signature data.
+    pub fn size(&self) -> usize {
+        self.items
+            .iter()
+            .map(|item| match item {
+                Item::Data(data, _) => data.len(),
+                Item::Operation(_) => 1,
+            })
+            .sum()
+    }
 }
 
 pub struct ScriptRunner {
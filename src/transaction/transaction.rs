@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     crypto::{
         account::Account,
-        hash_utils::{sha256, HashResult},
+        hash_utils::{sha256, Address, HashResult},
     },
     database::database::DatabaseType,
     mining::pow_utils::get_random_range,
@@ -13,6 +13,7 @@ use crate::{
 };
 
 use super::{
+    builder::TransactionBuilder,
     input::Input,
     output::Output,
     script::{Item, Operation, Script, ScriptRunner},
@@ -44,6 +45,11 @@ impl Transaction {
         database: &Arc<Mutex<DatabaseType>>,
         current_block_transactions: &[Transaction],
     ) -> bool {
+        if self.has_duplicate_inputs() {
+            log::error!("Transaction spends an output more than once.");
+            return false;
+        }
+
         let mut total_input = 0;
         let mut total_output = 0;
 
@@ -103,9 +109,10 @@ impl Transaction {
             total_output += output.value;
         }
 
-        if total_input != total_output {
+        // the difference between inputs and outputs is the miner fee
+        if total_input < total_output {
             log::error!(
-                "Total input amount {} != {} output amount.",
+                "Total input amount {} < {} output amount.",
                 total_input,
                 total_output
             );
@@ -114,30 +121,44 @@ impl Transaction {
         true
     }
     pub fn verify_inputs(&self, database: &Arc<Mutex<DatabaseType>>) -> bool {
+        self.verify_scripts(&*database.lock().unwrap(), &[])
+    }
+    /// Runs the unlocking script of each input against the locking script of
+    /// the output it spends, which checks the signatures. Spent outputs are
+    /// looked up in the chain and then in `related` transactions, like the
+    /// pending ones. Inputs spending unknown outputs are left to the caller.
+    pub fn verify_scripts(&self, database: &DatabaseType, related: &[Transaction]) -> bool {
         let tx_hash = self.hash();
 
         // filter out coinbase input
         for input in self.inputs.iter().filter(|x| x.utxo_tx_hash != [0u8; 32]) {
-            if let Some(prev_tx) = database
-                .lock()
-                .unwrap()
+            let prev_tx = database
                 .get_transaction(&input.utxo_tx_hash)
-            {
-                if let Some(prev_tx_output) = prev_tx.outputs.get(input.utxo_output_index as usize)
-                {
-                    let mut script_runner = ScriptRunner::new(tx_hash);
-                    let mut items = input.script_sig.items.clone();
-                    items.append(&mut prev_tx_output.script_pub_key.items.clone());
-                    if !script_runner.execute_script(items) {
-                        log::debug!("Failed to execute script. Invalid block.");
-                        return false;
-                    }
-                }
+                .or_else(|| related.iter().find(|x| x.hash() == input.utxo_tx_hash));
+            let Some(prev_tx_output) =
+                prev_tx.and_then(|x| x.outputs.get(input.utxo_output_index as usize))
+            else {
+                continue;
+            };
+            let mut script_runner = ScriptRunner::new(tx_hash);
+            let mut items = input.script_sig.items.clone();
+            items.append(&mut prev_tx_output.script_pub_key.items.clone());
+            if !script_runner.execute_script(items) {
+                log::debug!("Failed to execute the script of an input.");
+                return false;
             }
         }
 
         true
     }
+    /// Checks if an output is spent by more than one input.
+    pub fn has_duplicate_inputs(&self) -> bool {
+        self.inputs.iter().enumerate().any(|(i, input)| {
+            self.inputs[..i]
+                .iter()
+                .any(|other| other.spends_same_output(input))
+        })
+    }
     /// Creates a coinbase transaction, which contains the block reward.
     ///
     /// Parameters
@@ -146,6 +167,29 @@ impl Transaction {
     /// - pub_key: public key of the receiver
     ///
     pub fn create_coinbase(reward: Satoshi, pub_key: Vec<u8>) -> Transaction {
+        let data = get_random_range(0, u64::MAX).to_le_bytes().to_vec();
+        Transaction::coinbase_with_data(&[(pub_key, reward)], data)
+    }
+    /// Creates the coinbase transaction of a block. The block height makes the
+    /// transaction unique, the extra nonce extends the nonce space of the block
+    /// header since it changes the merkle root.
+    ///
+    /// Parameters
+    ///
+    /// - payouts: public keys of the receivers and their share of the block reward
+    /// - block_height: height of the mined block
+    /// - extra_nonce: rolled whenever the header nonces are exhausted
+    ///
+    pub fn create_block_coinbase(
+        payouts: &[(Address, Satoshi)],
+        block_height: usize,
+        extra_nonce: u64,
+    ) -> Transaction {
+        let mut data = (block_height as u64).to_le_bytes().to_vec();
+        data.extend(extra_nonce.to_le_bytes());
+        Transaction::coinbase_with_data(payouts, data)
+    }
+    fn coinbase_with_data(payouts: &[(Address, Satoshi)], data: Vec<u8>) -> Transaction {
         Transaction::new(
             [0u8; 32].to_vec(),
             vec![Input::new(
@@ -153,18 +197,23 @@ impl Transaction {
                 0,
                 Script::new(vec![
                     Item::Operation(Operation::Nop),
-                    Item::Data(get_random_range(0, u64::MAX).to_le_bytes().to_vec(), None),
+                    Item::Data(data, None),
                 ]),
             )],
-            vec![Output::new(
-                reward,
-                Script::new(vec![
-                    Item::Data(pub_key.clone(), None),
-                    Item::Operation(Operation::Dup),
-                    Item::Operation(Operation::Equal),
-                ]),
-                pub_key,
-            )],
+            payouts
+                .iter()
+                .map(|(pub_key, reward)| {
+                    Output::new(
+                        *reward,
+                        Script::new(vec![
+                            Item::Data(pub_key.clone(), None),
+                            Item::Operation(Operation::Dup),
+                            Item::Operation(Operation::Equal),
+                        ]),
+                        pub_key.clone(),
+                    )
+                })
+                .collect(),
         )
     }
     /// Creates a pay-to-public-key transaction.
@@ -172,14 +221,17 @@ impl Transaction {
     ///
     /// Parameters
     ///
-    /// - inputs: prev tx hash, prev output tx index, value
-    /// - amount: Amount to be spent. Rest will be taken as miner fee.
+    /// - inputs: prev tx hash, prev output tx index, value. All inputs are spent,
+    ///   use a `CoinSelector` to pick them.
+    /// - amount: Amount to be spent. Rest, minus the fee, is returned as change.
     /// - fee:  Amount to be given to the miner.
     /// - account: Sender's account used for signing the transaction.
     /// - rx_pub_key: Receiver's public key used for unlocking the funds.
     ///
     /// OPTIMIZE: if miner equals sender, avoid adding additional output.
     ///
+    /// See `TransactionBuilder` for transactions with multiple receivers.
+    ///
     pub fn create_pay_to_pub_key_hash(
         inputs: Vec<(HashResult, u32, u64)>,
         amount: u64,
@@ -187,71 +239,27 @@ impl Transaction {
         account: &Account,
         receiver_pub_key: &[u8],
     ) -> Result<Transaction, String> {
-        let mut total_input_value = 0;
-        let mut tx_inputs = Vec::new();
-
-        for (prev_tx_hash, prev_tx_output_index, value) in &inputs {
-            total_input_value += value;
-            tx_inputs.push(Input::new(
-                *prev_tx_hash,
-                *prev_tx_output_index,
-                Script::new(vec![
-                    Item::Data(vec![], Some("sig".to_string())),
-                    Item::Data(account.public_key().to_vec(), None),
-                ]),
-            ));
-            if total_input_value >= amount {
-                break;
-            }
-        }
-
-        let total_input_value = total_input_value;
-        if total_input_value < amount + fee {
-            return Err("Insufficient funds for the transaction".to_string());
-        }
-
-        let create_operations = |receiver_pub_key: Vec<u8>| {
-            vec![
-                Item::Operation(Operation::Dup),
-                Item::Operation(Operation::Hash256),
-                Item::Data(receiver_pub_key, None),
-                Item::Operation(Operation::EqualVerify),
-                Item::Operation(Operation::CheckSig),
-            ]
-        };
-
-        let mut tx_outputs = vec![Output::new(
-            amount,
-            Script::new(create_operations(sha256(receiver_pub_key).to_vec())),
-            receiver_pub_key.to_vec(),
-        )];
-
-        let change = total_input_value - amount - fee;
-        if change > 0 {
-            tx_outputs.push(Output::new(
-                change,
-                Script::new(create_operations(sha256(account.public_key()).to_vec())),
-                account.public_key().to_vec(),
-            ))
-        }
-
-        let mut tx = Transaction::new(account.public_key().to_vec(), tx_inputs, tx_outputs);
-        let tx_hash = tx.hash();
-
-        for input in &mut tx.inputs {
-            let item = input.script_sig.items.iter_mut().find(|item| match item {
-                Item::Data(_, name) => match name {
-                    Some(name) => name == "sig",
-                    None => false,
-                },
-                _ => false,
-            });
-
-            let item = item.expect("Cannot find prepared script item for transaction hash.");
-            let sig = account.sign(&tx_hash).to_vec();
-            *item = Item::Data(sig, Some("sig".to_string()));
-        }
-        Ok(tx)
+        TransactionBuilder::new(account)
+            .add_inputs(inputs)
+            .add_output(receiver_pub_key, amount)
+            .fee(fee)
+            .build()
+    }
+    /// Checks if the transaction can be replaced by one paying a higher fee.
+    pub fn signals_replacement(&self) -> bool {
+        self.inputs.iter().any(|input| input.signals_replacement())
+    }
+    /// Checks if the transaction is a coinbase transaction, which creates the block reward.
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.len() == 1
+            && self.inputs[0].utxo_tx_hash == [0u8; 32]
+            && self.inputs[0].utxo_output_index == 0
+    }
+    /// Number of bytes the transaction occupies. Used for fee rate calculations.
+    pub fn size(&self) -> usize {
+        self.sender.len()
+            + self.inputs.iter().map(|x| x.size()).sum::<usize>()
+            + self.outputs.iter().map(|x| x.size()).sum::<usize>()
     }
     pub fn hash(&self) -> HashResult {
         let mut bytes = vec![];
@@ -276,8 +284,44 @@ impl Transaction {
             database.remove_utxo(&input.utxo_tx_hash, input.utxo_output_index);
         }
 
-        for output_index in 0..self.outputs.len() {
-            database.add_utxo(tx_hash, output_index as u32);
+        for (output_index, output) in self.outputs.iter().enumerate() {
+            if !output.is_unspendable() {
+                database.add_utxo(tx_hash, output_index as u32);
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        database::{
+            database::{Database, DatabaseType},
+            InMemoryDatabase,
+        },
+        transaction::{input::Input, output::Output, script::Script, Transaction},
+    };
+
+    fn spend(funding: &Transaction, value: u64) -> Transaction {
+        Transaction::new(
+            vec![],
+            vec![Input::new(funding.hash(), 0, Script::new(vec![]))],
+            vec![Output::new(value, Script::new(vec![]), vec![7u8; 32])],
+        )
+    }
+
+    #[test]
+    fn test_verify_fee() {
+        let funding = Transaction::create_coinbase(10_000, vec![1u8; 32]);
+        let mut database = InMemoryDatabase::new();
+        database.add_transaction(funding.hash(), funding.clone());
+        let database: Arc<Mutex<DatabaseType>> = Arc::new(Mutex::new(database));
+
+        // the difference between inputs and outputs is paid to the miner
+        assert!(spend(&funding, 10_000).verify(0, &database, &[]));
+        assert!(spend(&funding, 9_000).verify(0, &database, &[]));
+        assert!(!spend(&funding, 10_001).verify(0, &database, &[]));
+    }
+}
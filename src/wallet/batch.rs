@@ -0,0 +1,80 @@
+use std::fs;
+
+use ring::signature::ED25519_PUBLIC_KEY_LEN;
+
+use crate::{crypto::hash_utils::Address, types::Satoshi};
+
+/// Loads the recipients of a batch payment from a CSV file.
+/// See `parse_recipients` for the expected format.
+pub fn load_recipients(path: &str) -> Result<Vec<(Address, Satoshi)>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read batch file {}: {}", path, err))?;
+    parse_recipients(&contents)
+}
+
+/// Parses recipients given as `address,amount` lines, where the address is a
+/// hex encoded public key and the amount is in satoshis. Empty lines, lines
+/// starting with `#` and an `address,amount` header are skipped.
+pub fn parse_recipients(contents: &str) -> Result<Vec<(Address, Satoshi)>, String> {
+    let mut recipients = vec![];
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line == "address,amount" {
+            continue;
+        }
+        recipients.push(
+            parse_recipient(line, ',')
+                .map_err(|err| format!("line {}: {}", line_number + 1, err))?,
+        );
+    }
+
+    if recipients.is_empty() {
+        return Err("No recipients found.".to_string());
+    }
+    Ok(recipients)
+}
+
+/// Parses a single `address<separator>amount` pair.
+pub fn parse_recipient(value: &str, separator: char) -> Result<(Address, Satoshi), String> {
+    let (address, amount) = value
+        .split_once(separator)
+        .ok_or_else(|| format!("expected address{}amount, got `{}`", separator, value))?;
+
+    let address = hex::decode(address.trim())
+        .ok()
+        .filter(|x| x.len() == ED25519_PUBLIC_KEY_LEN)
+        .ok_or_else(|| format!("invalid address `{}`", address.trim()))?;
+    let amount = amount
+        .trim()
+        .parse::<Satoshi>()
+        .map_err(|_| format!("invalid amount `{}`", amount.trim()))?;
+
+    Ok((address, amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_recipients;
+
+    #[test]
+    fn test_parse_recipients() {
+        let address = "ab".repeat(32);
+        let contents = format!(
+            "address,amount\n# payroll\n{},100\n\n{} , 250\n",
+            address, address
+        );
+        let recipients = parse_recipients(&contents).unwrap();
+
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0], (vec![0xab; 32], 100));
+        assert_eq!(recipients[1].1, 250);
+    }
+
+    #[test]
+    fn test_parse_recipients_invalid() {
+        assert!(parse_recipients("").is_err());
+        assert!(parse_recipients("abcd,100").is_err());
+        assert!(parse_recipients(&format!("{},ten", "ab".repeat(32))).is_err());
+    }
+}
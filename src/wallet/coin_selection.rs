@@ -0,0 +1,407 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::{crypto::hash_utils::HashResult, mempool::fee_utils::calculate_fee, types::Satoshi};
+
+/// Unspent output available for spending: prev tx hash, prev output tx index, value.
+pub type Utxo = (HashResult, u32, Satoshi);
+
+/// Size of the sender's public key, which every transaction carries.
+pub const TX_OVERHEAD_SIZE: usize = 32;
+/// Outpoint (32 + 4), script size (2), signature (64), public key (32) and sequence (4).
+pub const INPUT_SIZE: usize = 138;
+/// Value (8), pay-to-pub-key-hash script (4 + 32) and receiver (32).
+pub const OUTPUT_SIZE: usize = 76;
+
+/// Upper bound of subsets explored by the branch-and-bound search.
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// Estimates the size in bytes of a signed pay-to-pub-key-hash transaction.
+pub fn estimate_transaction_size(num_inputs: usize, num_outputs: usize) -> usize {
+    TX_OVERHEAD_SIZE + num_inputs * INPUT_SIZE + num_outputs * OUTPUT_SIZE
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoinSelectionStrategy {
+    /// Spends the largest outputs first, which results in the fewest inputs.
+    LargestFirst,
+    /// Searches for a set of outputs that matches the amount exactly, so that
+    /// no change output is needed. Falls back to largest-first.
+    BranchAndBound,
+    /// Spends outputs in a random order.
+    Random,
+    /// Prefers the selection which leaves the smallest change.
+    MinimizeChange,
+}
+impl FromStr for CoinSelectionStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "largest_first" => Ok(CoinSelectionStrategy::LargestFirst),
+            "branch_and_bound" | "bnb" => Ok(CoinSelectionStrategy::BranchAndBound),
+            "random" => Ok(CoinSelectionStrategy::Random),
+            "minimize_change" => Ok(CoinSelectionStrategy::MinimizeChange),
+            _ => Err(format!("Unknown coin selection strategy `{}`.", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CoinSelectionError {
+    InsufficientFunds {
+        available: Satoshi,
+        required: Satoshi,
+    },
+}
+impl fmt::Display for CoinSelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinSelectionError::InsufficientFunds {
+                available,
+                required,
+            } => write!(
+                f,
+                "Insufficient funds: {} satoshis available, at least {} required.",
+                available, required
+            ),
+        }
+    }
+}
+
+/// Result of a coin selection. Inputs cover `amount + fee + change`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinSelection {
+    pub inputs: Vec<Utxo>,
+    pub fee: Satoshi,
+    pub change: Satoshi,
+}
+impl CoinSelection {
+    pub fn total_input(&self) -> Satoshi {
+        self.inputs.iter().map(|x| x.2).sum()
+    }
+}
+
+pub struct CoinSelector {
+    strategy: CoinSelectionStrategy,
+    /// Fee rate in satoshis per kilobyte.
+    fee_rate: Satoshi,
+    /// Change below this amount is given to the miner instead of creating an output.
+    dust_threshold: Satoshi,
+}
+
+impl CoinSelector {
+    pub fn new(
+        strategy: CoinSelectionStrategy,
+        fee_rate: Satoshi,
+        dust_threshold: Satoshi,
+    ) -> Self {
+        Self {
+            strategy,
+            fee_rate,
+            dust_threshold,
+        }
+    }
+
+    /// Selects unspent outputs to pay `amount` to `num_outputs` receivers,
+    /// including the fee for the estimated size of the transaction.
+    pub fn select(
+        &self,
+        utxos: &[Utxo],
+        amount: Satoshi,
+        num_outputs: usize,
+    ) -> Result<CoinSelection, CoinSelectionError> {
+        // outputs which cost more to spend than they are worth are never selected
+        let input_fee = calculate_fee(INPUT_SIZE, self.fee_rate);
+        let mut candidates: Vec<Utxo> = utxos
+            .iter()
+            .filter(|utxo| utxo.2 > input_fee)
+            .cloned()
+            .collect();
+
+        let selection = match self.strategy {
+            CoinSelectionStrategy::LargestFirst => {
+                candidates.sort_by_key(|x| Reverse(x.2));
+                self.accumulate(&candidates, amount, num_outputs)
+            }
+            CoinSelectionStrategy::BranchAndBound => self
+                .branch_and_bound(&candidates, amount, num_outputs)
+                .or_else(|| {
+                    candidates.sort_by_key(|x| Reverse(x.2));
+                    self.accumulate(&candidates, amount, num_outputs)
+                }),
+            CoinSelectionStrategy::Random => {
+                candidates.shuffle(&mut rand::thread_rng());
+                self.accumulate(&candidates, amount, num_outputs)
+            }
+            CoinSelectionStrategy::MinimizeChange => {
+                self.minimize_change(&mut candidates, amount, num_outputs)
+            }
+        };
+
+        selection.ok_or_else(|| CoinSelectionError::InsufficientFunds {
+            available: utxos.iter().map(|x| x.2).sum(),
+            required: amount
+                + calculate_fee(estimate_transaction_size(1, num_outputs), self.fee_rate),
+        })
+    }
+
+    /// Adds candidates in the given order until the amount and fee are covered.
+    fn accumulate(
+        &self,
+        candidates: &[Utxo],
+        amount: Satoshi,
+        num_outputs: usize,
+    ) -> Option<CoinSelection> {
+        for count in 1..=candidates.len() {
+            if let Some(selection) = self.finalize(&candidates[..count], amount, num_outputs) {
+                return Some(selection);
+            }
+        }
+        None
+    }
+
+    /// Computes fee and change of a fixed set of inputs. Change which would be
+    /// dust, or could not pay for its own output, is added to the fee.
+    fn finalize(
+        &self,
+        inputs: &[Utxo],
+        amount: Satoshi,
+        num_outputs: usize,
+    ) -> Option<CoinSelection> {
+        let total: Satoshi = inputs.iter().map(|x| x.2).sum();
+        let excess = total.checked_sub(amount)?;
+        let fee_without_change = calculate_fee(
+            estimate_transaction_size(inputs.len(), num_outputs),
+            self.fee_rate,
+        );
+        if excess < fee_without_change {
+            return None;
+        }
+
+        let fee_with_change = calculate_fee(
+            estimate_transaction_size(inputs.len(), num_outputs + 1),
+            self.fee_rate,
+        );
+        let (fee, change) = match excess.checked_sub(fee_with_change) {
+            Some(change) if change > 0 && change >= self.dust_threshold => {
+                (fee_with_change, change)
+            }
+            _ => (excess, 0),
+        };
+
+        Some(CoinSelection {
+            inputs: inputs.to_vec(),
+            fee,
+            change,
+        })
+    }
+
+    /// Depth-first search for a subset whose value, after paying for itself,
+    /// lies between the target and the target plus the cost of a change output.
+    fn branch_and_bound(
+        &self,
+        candidates: &[Utxo],
+        amount: Satoshi,
+        num_outputs: usize,
+    ) -> Option<CoinSelection> {
+        let input_fee = calculate_fee(INPUT_SIZE, self.fee_rate);
+        let target =
+            amount + calculate_fee(estimate_transaction_size(0, num_outputs), self.fee_rate);
+        let cost_of_change = calculate_fee(OUTPUT_SIZE, self.fee_rate) + self.dust_threshold;
+
+        let mut sorted = candidates.to_vec();
+        sorted.sort_by_key(|x| Reverse(x.2));
+        let effective: Vec<Satoshi> = sorted.iter().map(|x| x.2 - input_fee).collect();
+
+        let mut remaining: Satoshi = effective.iter().sum();
+        if remaining < target {
+            return None;
+        }
+
+        let mut tries = 0;
+        let mut selected: Vec<bool> = vec![false; sorted.len()];
+        let mut current: Satoshi = 0;
+        let mut index = 0;
+
+        loop {
+            tries += 1;
+            let mut backtrack = false;
+
+            if current > target + cost_of_change || current + remaining < target {
+                backtrack = true;
+            } else if current >= target {
+                let inputs: Vec<Utxo> = sorted
+                    .iter()
+                    .zip(selected.iter())
+                    .filter(|(_, selected)| **selected)
+                    .map(|(utxo, _)| *utxo)
+                    .collect();
+                let total: Satoshi = inputs.iter().map(|x| x.2).sum();
+                return Some(CoinSelection {
+                    inputs,
+                    fee: total - amount,
+                    change: 0,
+                });
+            } else if index >= sorted.len() {
+                backtrack = true;
+            }
+
+            if tries >= BNB_MAX_TRIES {
+                return None;
+            }
+
+            if backtrack {
+                // walk back to the last included candidate and exclude it instead
+                loop {
+                    if index == 0 {
+                        return None;
+                    }
+                    index -= 1;
+                    if selected[index] {
+                        break;
+                    }
+                    remaining += effective[index];
+                }
+                selected[index] = false;
+                current -= effective[index];
+                index += 1;
+            } else {
+                remaining -= effective[index];
+                selected[index] = true;
+                current += effective[index];
+                index += 1;
+            }
+        }
+    }
+
+    /// Uses the smallest single output that covers the payment, otherwise
+    /// accumulates the largest outputs and drops the ones that are not needed.
+    fn minimize_change(
+        &self,
+        candidates: &mut [Utxo],
+        amount: Satoshi,
+        num_outputs: usize,
+    ) -> Option<CoinSelection> {
+        if let Some(selection) = self.branch_and_bound(candidates, amount, num_outputs) {
+            return Some(selection);
+        }
+
+        candidates.sort_by_key(|x| x.2);
+        if let Some(selection) = candidates
+            .iter()
+            .find_map(|utxo| self.finalize(&[*utxo], amount, num_outputs))
+        {
+            return Some(selection);
+        }
+
+        candidates.reverse();
+        let mut selection = self.accumulate(candidates, amount, num_outputs)?;
+        let mut index = selection.inputs.len();
+        while index > 0 {
+            index -= 1;
+            let mut inputs = selection.inputs.clone();
+            inputs.remove(index);
+            if let Some(smaller) = self.finalize(&inputs, amount, num_outputs) {
+                if smaller.change < selection.change || smaller.change == 0 {
+                    selection = smaller;
+                }
+            }
+        }
+        Some(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        estimate_transaction_size, CoinSelectionError, CoinSelectionStrategy, CoinSelector, Utxo,
+    };
+    use crate::mempool::fee_utils::calculate_fee;
+
+    fn utxos(values: &[u64]) -> Vec<Utxo> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| ([i as u8; 32], i as u32, *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_largest_first() {
+        let selector = CoinSelector::new(CoinSelectionStrategy::LargestFirst, 1000, 10);
+        let selection = selector
+            .select(&utxos(&[100, 5000, 2000]), 3000, 1)
+            .unwrap();
+
+        assert_eq!(selection.inputs.len(), 1);
+        assert_eq!(selection.inputs[0].2, 5000);
+        let fee = calculate_fee(estimate_transaction_size(1, 2), 1000);
+        assert_eq!(selection.fee, fee);
+        assert_eq!(selection.change, 5000 - 3000 - fee);
+    }
+
+    #[test]
+    fn test_fee_is_covered() {
+        // the amount alone is covered by the first output, the fee is not
+        let selector = CoinSelector::new(CoinSelectionStrategy::LargestFirst, 1000, 10);
+        let selection = selector.select(&utxos(&[1000, 1000]), 1000, 1).unwrap();
+
+        assert_eq!(selection.inputs.len(), 2);
+        assert_eq!(
+            selection.total_input(),
+            1000 + selection.fee + selection.change
+        );
+    }
+
+    #[test]
+    fn test_branch_and_bound_exact_match() {
+        let fee_rate = 1000;
+        let fee = calculate_fee(estimate_transaction_size(2, 1), fee_rate);
+        let selector = CoinSelector::new(CoinSelectionStrategy::BranchAndBound, fee_rate, 10);
+        let selection = selector
+            .select(&utxos(&[7000, 3000, 2000 + fee, 900]), 5000, 1)
+            .unwrap();
+
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.inputs.len(), 2);
+        assert_eq!(selection.fee, fee);
+    }
+
+    #[test]
+    fn test_minimize_change() {
+        let selector = CoinSelector::new(CoinSelectionStrategy::MinimizeChange, 0, 1);
+        let selection = selector
+            .select(&utxos(&[10000, 1200, 1500]), 1100, 1)
+            .unwrap();
+
+        assert_eq!(selection.inputs.len(), 1);
+        assert_eq!(selection.change, 100);
+    }
+
+    #[test]
+    fn test_dust_change_is_added_to_fee() {
+        let selector = CoinSelector::new(CoinSelectionStrategy::LargestFirst, 0, 50);
+        let selection = selector.select(&utxos(&[1020]), 1000, 1).unwrap();
+
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.fee, 20);
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        let selector = CoinSelector::new(CoinSelectionStrategy::Random, 0, 1);
+        assert_eq!(
+            selector.select(&utxos(&[10, 20]), 100, 1),
+            Err(CoinSelectionError::InsufficientFunds {
+                available: 30,
+                required: 100
+            })
+        );
+    }
+}
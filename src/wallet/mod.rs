@@ -1 +1,3 @@
+pub mod batch;
+pub mod coin_selection;
 pub mod wallet;
@@ -1,11 +1,19 @@
+use std::collections::HashMap;
+
 use tokio::runtime::Runtime;
-use tonic::transport::Channel;
 
 use crate::config::models::WalletConfig;
 use crate::crypto::account::Account;
 use crate::crypto::hash_utils::{hash_from_vec_u8, Address, HashResult};
-use crate::proto::proto_node::node_client::NodeClient;
-use crate::proto::proto_node::{PublicKey, Transaction, UnspentOutputs};
+use crate::mempool::fee_utils::{calculate_fee, fee_rate};
+use crate::network::rpc::{connect_rpc, RpcClient};
+use crate::proto::proto_node::{
+    FeeEstimateReq, GenerateBlocksReq, PublicKey, Transaction, UnspentOutput, UnspentOutputs,
+};
+use crate::transaction::builder::TransactionBuilder;
+use crate::types::Satoshi;
+
+use super::coin_selection::{estimate_transaction_size, CoinSelectionStrategy, CoinSelector, Utxo};
 
 #[derive(Debug)]
 pub enum WalletError {
@@ -18,11 +26,38 @@ impl From<crate::crypto::account::AccountError> for WalletError {
     }
 }
 
+/// Balance of the wallet. The pending balance is what remains once all
+/// pending transactions sent by this wallet are confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Balance {
+    pub confirmed: Satoshi,
+    pub pending: Satoshi,
+}
+
+/// Everything needed to build (or rebuild) a transaction of this wallet.
+#[derive(Clone)]
+struct Payment {
+    inputs: Vec<Utxo>,
+    recipients: Vec<(Address, Satoshi)>,
+    change_address: Option<Address>,
+    data: Option<Vec<u8>>,
+    fee: Satoshi,
+}
+
+/// Transaction sent by this wallet, along with the payment it was built from.
+#[derive(Clone)]
+struct PendingTransaction {
+    transaction: crate::transaction::Transaction,
+    payment: Payment,
+}
+
 pub struct Wallet<'a> {
     rt: &'a Runtime,
     pub config: WalletConfig,
     pub account: Account,
-    client: Option<NodeClient<Channel>>,
+    client: Option<RpcClient>,
+    /// Transactions sent by this wallet which are not yet confirmed.
+    pending_transactions: HashMap<HashResult, PendingTransaction>,
 }
 
 impl<'a> Wallet<'a> {
@@ -32,13 +67,14 @@ impl<'a> Wallet<'a> {
             config: config.clone(),
             account: Account::load_or_create(config.account.clone())?,
             client: None,
+            pending_transactions: HashMap::new(),
         })
     }
 
     pub fn connect_node(&mut self) -> Result<(), tonic::transport::Error> {
         self.rt.block_on(async {
             let rpc_url: String = self.config.rpc_url.to_string();
-            match NodeClient::connect(rpc_url.clone()).await {
+            match connect_rpc(&rpc_url, self.config.rpc_token.as_deref()).await {
                 Ok(client) => {
                     log::debug!("Connected to {}.", &rpc_url);
                     self.client = Some(client);
@@ -72,25 +108,98 @@ impl<'a> Wallet<'a> {
         self.account.public_key_from_hex(address)
     }
 
-    pub fn get_balance(&mut self, address: Address) -> u64 {
-        let unspent_outputs = self.get_unspent_outputs(address);
-        unspent_outputs
-            .unspent_outputs
-            .into_iter()
-            .map(|unspent_output| unspent_output.amount)
-            .sum()
+    /// Asks the node for the fee rate needed to be confirmed within the given
+    /// number of blocks.
+    pub fn estimate_fee(&mut self, target_blocks: u32) -> Result<Satoshi, String> {
+        self.rt
+            .block_on(
+                self.client
+                    .as_mut()
+                    .unwrap()
+                    .estimate_fee(FeeEstimateReq { target_blocks }),
+            )
+            .map(|resp| resp.into_inner().fee_rate)
+            .map_err(|status| status.message().to_string())
     }
 
-    pub fn create_transaction(
-        &mut self,
-        rx_pub_key: &Address,
-        amount: u64,
-        fee: u64,
-    ) -> Result<HashResult, String> {
+    /// Asks a regtest node to mine blocks paying this wallet and returns their hashes.
+    pub fn generate_blocks(&mut self, count: u32) -> Result<Vec<HashResult>, String> {
+        let address = self.account.public_key().to_vec();
+        self.rt
+            .block_on(
+                self.client
+                    .as_mut()
+                    .unwrap()
+                    .generate_blocks(GenerateBlocksReq { count, address }),
+            )
+            .map(|resp| {
+                resp.into_inner()
+                    .hashes
+                    .iter()
+                    .map(|x| hash_from_vec_u8(x))
+                    .collect()
+            })
+            .map_err(|status| status.message().to_string())
+    }
+
+    /// Fee rate used for new transactions: the node's estimate for the
+    /// configured target, or the configured fee rate if there is none.
+    fn get_fee_rate(&mut self) -> Satoshi {
+        match self.estimate_fee(self.config.coin_selection.fee_target_blocks) {
+            Ok(fee_rate) => fee_rate,
+            Err(err) => {
+                log::debug!("Using the configured fee rate: {}", err);
+                self.config.coin_selection.fee_rate
+            }
+        }
+    }
+
+    /// Fetches the unspent outputs of this wallet and forgets the pending
+    /// transactions which were confirmed or dropped by the node.
+    fn refresh_pending_transactions(&mut self) -> UnspentOutputs {
         let unspent_outputs = self.get_unspent_outputs(self.account.public_key().to_vec());
-        let inputs = unspent_outputs
+
+        self.pending_transactions.retain(|tx_hash, pending| {
+            let inputs: Vec<_> = pending
+                .transaction
+                .inputs
+                .iter()
+                .map(|input| {
+                    unspent_outputs.unspent_outputs.iter().find(|x| {
+                        x.previous_transaction_hash == input.utxo_tx_hash
+                            && x.previous_transaction_output_index == input.utxo_output_index
+                    })
+                })
+                .collect();
+
+            if inputs.iter().any(|x| x.is_none()) {
+                log::debug!("Transaction {} confirmed.", hex::encode(tx_hash));
+                false
+            } else if !inputs.iter().flatten().any(|x| x.spent_in_mempool) {
+                log::warn!(
+                    "Transaction {} was dropped by the node.",
+                    hex::encode(tx_hash)
+                );
+                false
+            } else {
+                true
+            }
+        });
+
+        unspent_outputs
+    }
+
+    /// Retrieves the confirmed outputs which are not spent by pending transactions.
+    fn get_spendable_outputs(&mut self) -> Vec<Utxo> {
+        let unspent_outputs = self.refresh_pending_transactions();
+        self.filter_spendable_outputs(unspent_outputs)
+    }
+
+    fn filter_spendable_outputs(&self, unspent_outputs: UnspentOutputs) -> Vec<Utxo> {
+        unspent_outputs
             .unspent_outputs
             .into_iter()
+            .filter(|x| !x.spent_in_mempool && !self.is_spent_by_pending_transaction(x))
             .map(|unspent_output| {
                 (
                     hash_from_vec_u8(&unspent_output.previous_transaction_hash),
@@ -98,26 +207,276 @@ impl<'a> Wallet<'a> {
                     unspent_output.amount,
                 )
             })
-            .collect();
+            .collect()
+    }
 
-        if let Ok(tx) = crate::transaction::Transaction::create_pay_to_pub_key_hash(
-            inputs,
-            amount,
-            fee,
-            &self.account,
-            rx_pub_key,
-        ) {
-            log::debug!("Send-Transaction={:?}", hex::encode(tx.hash()));
-            if let Ok(encoded_tx) = serde_json::to_string(&tx) {
-                self.rt
-                    .block_on(self.client.as_mut().unwrap().add_transaction(Transaction {
-                        tx_json: encoded_tx,
-                    }))
-                    .unwrap();
-                return Ok(tx.hash());
+    fn is_spent_by_pending_transaction(&self, unspent_output: &UnspentOutput) -> bool {
+        self.pending_transactions.values().any(|pending| {
+            pending.transaction.inputs.iter().any(|input| {
+                input.utxo_tx_hash.as_slice() == unspent_output.previous_transaction_hash
+                    && input.utxo_output_index == unspent_output.previous_transaction_output_index
+            })
+        })
+    }
+
+    pub fn get_balance(&mut self) -> Balance {
+        let unspent_outputs = self.refresh_pending_transactions();
+        let confirmed = unspent_outputs
+            .unspent_outputs
+            .iter()
+            .map(|unspent_output| unspent_output.amount)
+            .sum();
+        let spendable: Satoshi = self
+            .filter_spendable_outputs(unspent_outputs)
+            .iter()
+            .map(|x| x.2)
+            .sum();
+        let incoming: Satoshi = self
+            .pending_transactions
+            .values()
+            .flat_map(|pending| pending.transaction.outputs.iter())
+            .filter(|output| output.receiver == self.account.public_key())
+            .map(|output| output.value)
+            .sum();
+
+        Balance {
+            confirmed,
+            pending: spendable + incoming,
+        }
+    }
+
+    /// Hashes of the transactions sent by this wallet which are not yet confirmed.
+    pub fn get_pending_transactions(&self) -> Vec<HashResult> {
+        self.pending_transactions.keys().cloned().collect()
+    }
+
+    /// Creates a transaction paying `amount` to the receiver and sends it to the node.
+    /// The inputs are picked by the given coin selection strategy, the fee is
+    /// based on the estimated fee rate and transaction size.
+    pub fn create_transaction(
+        &mut self,
+        rx_pub_key: &Address,
+        amount: Satoshi,
+        strategy: CoinSelectionStrategy,
+    ) -> Result<HashResult, String> {
+        self.create_batch_transaction(&[(rx_pub_key.clone(), amount)], None, None, strategy)
+    }
+
+    /// Creates a single transaction paying all recipients and sends it to the node.
+    ///
+    /// Parameters
+    ///
+    /// - recipients: receiver's public key and amount
+    /// - change_address: receiver of the change, defaults to this wallet
+    /// - data: optional data embedded into an OP_RETURN output
+    /// - strategy: coin selection strategy used to pick the inputs
+    ///
+    pub fn create_batch_transaction(
+        &mut self,
+        recipients: &[(Address, Satoshi)],
+        change_address: Option<Address>,
+        data: Option<Vec<u8>>,
+        strategy: CoinSelectionStrategy,
+    ) -> Result<HashResult, String> {
+        if recipients.is_empty() {
+            return Err("No recipients given.".to_string());
+        }
+
+        let utxos = self.get_spendable_outputs();
+
+        let amount: Satoshi = recipients.iter().map(|x| x.1).sum();
+        let num_outputs = recipients.len() + data.is_some() as usize;
+        let selector = CoinSelector::new(
+            strategy,
+            self.get_fee_rate(),
+            self.config.coin_selection.dust_threshold,
+        );
+        let selection = selector
+            .select(&utxos, amount, num_outputs)
+            .map_err(|err| err.to_string())?;
+        log::debug!(
+            "Selected {} inputs, fee={}, change={}",
+            selection.inputs.len(),
+            selection.fee,
+            selection.change
+        );
+
+        self.build_and_send(Payment {
+            inputs: selection.inputs,
+            recipients: recipients.to_vec(),
+            change_address,
+            data,
+            fee: selection.fee,
+        })
+    }
+
+    /// Replaces a pending transaction of this wallet with one paying a higher fee.
+    /// The recipients are paid the same amounts, the additional fee is taken from
+    /// the change and, if that is not enough, from additional inputs.
+    ///
+    /// Parameters
+    ///
+    /// - tx_hash: hash of the pending transaction
+    /// - new_fee_rate: fee rate in satoshis per kilobyte, defaults to twice the
+    ///   current one or the estimated fee rate, whichever is higher
+    ///
+    pub fn bump_fee(
+        &mut self,
+        tx_hash: &HashResult,
+        new_fee_rate: Option<Satoshi>,
+    ) -> Result<HashResult, String> {
+        let mut spendable = self.get_spendable_outputs();
+        let pending = self
+            .pending_transactions
+            .get(tx_hash)
+            .cloned()
+            .ok_or("Transaction is not pending.")?;
+        if !pending.transaction.signals_replacement() {
+            return Err("Transaction does not signal replace-by-fee.".to_string());
+        }
+
+        let mut payment = pending.payment;
+        let new_fee_rate = match new_fee_rate {
+            Some(new_fee_rate) => new_fee_rate,
+            None => {
+                (fee_rate(payment.fee, pending.transaction.size()) * 2).max(self.get_fee_rate())
+            }
+        };
+        let amount: Satoshi = payment.recipients.iter().map(|x| x.1).sum();
+        let num_outputs = payment.recipients.len() + payment.data.is_some() as usize;
+
+        spendable.sort_by_key(|x| x.2);
+        let fee = loop {
+            let size = estimate_transaction_size(payment.inputs.len(), num_outputs + 1);
+            let fee = calculate_fee(size, new_fee_rate).max(payment.fee + 1);
+            let total: Satoshi = payment.inputs.iter().map(|x| x.2).sum();
+            if total >= amount + fee {
+                // dust change is given to the miner
+                let change = total - amount - fee;
+                if change < self.config.coin_selection.dust_threshold {
+                    break fee + change;
+                }
+                break fee;
+            }
+            match spendable.pop() {
+                Some(utxo) => payment.inputs.push(utxo),
+                None => return Err("Insufficient funds to bump the fee.".to_string()),
             }
-            return Err("Failed to encode transaction.".to_string());
+        };
+        log::debug!("Bump fee from {} to {}.", payment.fee, fee);
+        payment.fee = fee;
+
+        let new_tx_hash = self.build_and_send(payment)?;
+        self.pending_transactions.remove(tx_hash);
+        Ok(new_tx_hash)
+    }
+
+    /// Builds and signs the transaction, sends it to the node and keeps track
+    /// of it until it is confirmed.
+    fn build_and_send(&mut self, payment: Payment) -> Result<HashResult, String> {
+        let transaction = payment
+            .recipients
+            .iter()
+            .fold(
+                TransactionBuilder::new(&self.account),
+                |builder, (address, amount)| builder.add_output(address, *amount),
+            )
+            .add_inputs(payment.inputs.clone())
+            .change_address(payment.change_address.clone())
+            .op_return(payment.data.clone())
+            .fee(payment.fee)
+            .replaceable(self.config.replace_by_fee)
+            .build()?;
+
+        let tx_hash = transaction.hash();
+        log::debug!("Send-Transaction={:?}", hex::encode(tx_hash));
+        if let Ok(encoded_tx) = serde_json::to_string(&transaction) {
+            self.rt
+                .block_on(self.client.as_mut().unwrap().add_transaction(Transaction {
+                    tx_json: encoded_tx,
+                }))
+                .map_err(|status| status.message().to_string())?;
+            self.pending_transactions.insert(
+                tx_hash,
+                PendingTransaction {
+                    transaction,
+                    payment,
+                },
+            );
+            return Ok(tx_hash);
+        }
+        Err("Failed to encode transaction.".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::runtime::Runtime;
+
+    use super::{Payment, PendingTransaction, Wallet};
+    use crate::config::models::{AccountConfig, CoinSelectionConfig, WalletConfig};
+    use crate::proto::proto_node::{UnspentOutput, UnspentOutputs};
+    use crate::transaction::builder::TransactionBuilder;
+    use crate::wallet::coin_selection::CoinSelectionStrategy;
+
+    fn unspent_output(output_index: u32, amount: u64, spent_in_mempool: bool) -> UnspentOutput {
+        UnspentOutput {
+            previous_transaction_hash: vec![1u8; 32],
+            previous_transaction_output_index: output_index,
+            amount,
+            spent_in_mempool,
         }
-        Err("Failed to create transaction.".to_string())
+    }
+
+    #[test]
+    fn test_spendable_outputs() {
+        let rt = Runtime::new().unwrap();
+        let mut wallet = Wallet::new(
+            &rt,
+            WalletConfig {
+                account: AccountConfig {
+                    keys_path: "./configs/keys_test.pkcs8".to_string(),
+                },
+                rpc_url: String::new(),
+                rpc_token: None,
+                replace_by_fee: false,
+                coin_selection: CoinSelectionConfig {
+                    strategy: CoinSelectionStrategy::LargestFirst,
+                    fee_rate: 0,
+                    fee_target_blocks: 6,
+                    dust_threshold: 1,
+                },
+            },
+        )
+        .unwrap();
+        let payment = Payment {
+            inputs: vec![([1u8; 32], 0, 100)],
+            recipients: vec![(vec![7u8; 32], 100)],
+            change_address: None,
+            data: None,
+            fee: 0,
+        };
+        let transaction = TransactionBuilder::new(&wallet.account)
+            .add_inputs(payment.inputs.clone())
+            .add_output(&[7u8; 32], 100)
+            .build()
+            .unwrap();
+        wallet.pending_transactions.insert(
+            transaction.hash(),
+            PendingTransaction {
+                transaction,
+                payment,
+            },
+        );
+
+        // outputs spent by our own or another pending transaction are not selected
+        let utxos = wallet.filter_spendable_outputs(UnspentOutputs {
+            unspent_outputs: vec![
+                unspent_output(0, 100, false),
+                unspent_output(1, 50, true),
+                unspent_output(2, 30, false),
+            ],
+        });
+        assert_eq!(utxos, vec![([1u8; 32], 2, 30)]);
     }
 }